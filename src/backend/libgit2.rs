@@ -0,0 +1,248 @@
+use git2::{Diff, DiffFormat, DiffOptions, ErrorCode, Repository, Tree};
+use std::path::{Component, Path, PathBuf};
+
+use super::GitBackend;
+use crate::git::{ChangeScope, LoggedCommit};
+
+/// Reads the repository in-process with libgit2, without spawning git.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Libgit2;
+
+/// How many commits a path-filtered [`GitBackend::recent_subjects`] query
+/// diffs before giving up, so paths untouched in a long history stay cheap.
+const FILTERED_HISTORY_BUDGET: usize = 1000;
+
+fn err(e: git2::Error) -> String {
+    format!("libgit2: {}", e.message())
+}
+
+/// Opens the repository containing the current directory, honouring
+/// `GIT_DIR` and the other variables git reads.
+fn open() -> Result<Repository, String> {
+    Repository::open_from_env().map_err(err)
+}
+
+fn head_tree(repo: &Repository) -> Result<Option<Tree<'_>>, String> {
+    match repo.head() {
+        Ok(head) => head.peel_to_tree().map(Some).map_err(err),
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+        Err(e) => Err(err(e)),
+    }
+}
+
+/// Turns `paths`, relative to the current directory like git's pathspecs,
+/// into pathspecs relative to the repository root as libgit2 expects.
+fn pathspecs(repo: &Repository, paths: &[String]) -> Result<Vec<String>, String> {
+    let workdir = repo
+        .workdir()
+        .ok_or("libgit2: the repository has no working tree")?;
+    let workdir = workdir.canonicalize().map_err(|e| e.to_string())?;
+    let cwd = std::env::current_dir()
+        .and_then(|cwd| cwd.canonicalize())
+        .map_err(|e| e.to_string())?;
+    let prefix = cwd.strip_prefix(&workdir).unwrap_or(Path::new(""));
+
+    Ok(paths
+        .iter()
+        .map(|path| {
+            let path = Path::new(path);
+            let joined = match path.strip_prefix(&workdir) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => prefix.join(path),
+            };
+            let mut parts: Vec<String> = Vec::new();
+            for component in joined.components() {
+                match component {
+                    Component::ParentDir => {
+                        parts.pop();
+                    }
+                    Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+                    _ => {}
+                }
+            }
+            if parts.is_empty() {
+                "*".to_string()
+            } else {
+                parts.join("/")
+            }
+        })
+        .collect())
+}
+
+/// The diff selecting the changes in `scope`, with renames detected as the
+/// repository's `diff.renames` setting asks.
+fn scope_diff<'r>(repo: &'r Repository, scope: &ChangeScope) -> Result<Diff<'r>, String> {
+    let head = head_tree(repo)?;
+    let mut options = DiffOptions::new();
+    if let ChangeScope::Paths(paths) = scope {
+        for spec in pathspecs(repo, paths)? {
+            options.pathspec(spec);
+        }
+    }
+    let mut diff = match scope {
+        ChangeScope::Staged => repo.diff_tree_to_index(head.as_ref(), None, Some(&mut options)),
+        ChangeScope::All | ChangeScope::Paths(_) => {
+            repo.diff_tree_to_workdir_with_index(head.as_ref(), Some(&mut options))
+        }
+    }
+    .map_err(err)?;
+    diff.find_similar(None).map_err(err)?;
+    Ok(diff)
+}
+
+impl GitBackend for Libgit2 {
+    fn name(&self) -> &'static str {
+        "libgit2"
+    }
+
+    fn is_repository(&self) -> Result<bool, String> {
+        let repo = match Repository::open_from_env() {
+            Ok(repo) => repo,
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(false),
+            Err(e) => return Err(err(e)),
+        };
+        if repo.is_bare() {
+            return Ok(false);
+        }
+        // git refuses to work on the working tree from inside `.git`
+        let cwd = std::env::current_dir()
+            .and_then(|cwd| cwd.canonicalize())
+            .map_err(|e| e.to_string())?;
+        let git_dir = repo.path().canonicalize().map_err(|e| e.to_string())?;
+        Ok(!cwd.starts_with(git_dir))
+    }
+
+    fn diff(&self, scope: &ChangeScope) -> Result<String, String> {
+        let repo = open()?;
+        let diff = scope_diff(&repo, scope)?;
+        let mut patch = Vec::new();
+        diff.print(DiffFormat::Patch, |_, _, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin() as u8);
+            }
+            patch.extend_from_slice(line.content());
+            true
+        })
+        .map_err(err)?;
+        // Paths and contents that aren't UTF-8 are kept readable
+        Ok(String::from_utf8_lossy(&patch).into_owned())
+    }
+
+    fn changed_files(&self, scope: &ChangeScope) -> Result<Vec<String>, String> {
+        let repo = open()?;
+        let diff = scope_diff(&repo, scope)?;
+        Ok(diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    fn recent_subjects(&self, count: usize, paths: &[String]) -> Result<Vec<String>, String> {
+        let repo = open()?;
+        if count == 0 || head_tree(&repo)?.is_none() {
+            return Ok(Vec::new());
+        }
+        let specs = pathspecs(&repo, paths)?;
+        let mut walk = repo.revwalk().map_err(err)?;
+        walk.push_head().map_err(err)?;
+
+        let budget = if specs.is_empty() {
+            usize::MAX
+        } else {
+            FILTERED_HISTORY_BUDGET
+        };
+        let mut subjects = Vec::new();
+        for oid in walk.take(budget) {
+            let commit = repo.find_commit(oid.map_err(err)?).map_err(err)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            if !specs.is_empty() {
+                let parent = match commit.parent(0) {
+                    Ok(parent) => Some(parent.tree().map_err(err)?),
+                    Err(_) => None,
+                };
+                let mut options = DiffOptions::new();
+                for spec in &specs {
+                    options.pathspec(spec);
+                }
+                let tree = commit.tree().map_err(err)?;
+                let diff = repo
+                    .diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(&mut options))
+                    .map_err(err)?;
+                if diff.deltas().len() == 0 {
+                    continue;
+                }
+            }
+            let subject = commit.summary_bytes().unwrap_or_default();
+            subjects.push(String::from_utf8_lossy(subject).into_owned());
+            if subjects.len() == count {
+                break;
+            }
+        }
+        Ok(subjects)
+    }
+
+    fn log(&self, from: Option<&str>, to: &str) -> Result<Vec<LoggedCommit>, String> {
+        let repo = open()?;
+        let commit_id = |rev: &str| {
+            repo.revparse_single(rev)
+                .and_then(|object| object.peel_to_commit())
+                .map(|commit| commit.id())
+                .map_err(err)
+        };
+        let mut walk = repo.revwalk().map_err(err)?;
+        walk.push(commit_id(to)?).map_err(err)?;
+        if let Some(from) = from {
+            walk.hide(commit_id(from)?).map_err(err)?;
+        }
+
+        let mut commits = Vec::new();
+        for oid in walk {
+            let commit = repo.find_commit(oid.map_err(err)?).map_err(err)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let hash = commit.as_object().short_id().map_err(err)?;
+            commits.push(LoggedCommit {
+                hash: hash.as_str().unwrap_or_default().to_string(),
+                message: String::from_utf8_lossy(commit.message_bytes())
+                    .trim()
+                    .to_string(),
+            });
+        }
+        Ok(commits)
+    }
+
+    fn current_branch(&self) -> Result<Option<String>, String> {
+        let repo = open()?;
+        let head = repo.find_reference("HEAD").map_err(err)?;
+        Ok(head
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(str::to_string))
+    }
+
+    fn repo_root(&self) -> Result<PathBuf, String> {
+        let repo = open()?;
+        let workdir = repo
+            .workdir()
+            .ok_or("libgit2: the repository has no working tree")?;
+        // Without the trailing slash libgit2 keeps
+        Ok(workdir.components().collect())
+    }
+
+    fn common_dir(&self) -> Result<PathBuf, String> {
+        let repo = open()?;
+        let mut dir = repo.path().to_path_buf();
+        if repo.is_worktree() {
+            // A linked worktree's git directory names the common one,
+            // relative to itself, in its `commondir` file
+            let common = std::fs::read_to_string(dir.join("commondir"))
+                .map_err(|e| format!("libgit2: failed to read the worktree's commondir: {}", e))?;
+            dir = dir.join(common.trim());
+        }
+        dir.canonicalize().map_err(|e| e.to_string())
+    }
+}
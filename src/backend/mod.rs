@@ -0,0 +1,80 @@
+use std::path::PathBuf;
+
+use crate::git::{ChangeScope, LoggedCommit};
+
+pub mod libgit2;
+pub mod subprocess;
+
+pub use self::libgit2::Libgit2;
+pub use subprocess::Subprocess;
+
+/// The environment variable that picks a backend: `libgit2`, `subprocess`,
+/// or `auto` (the default).
+pub const BACKEND_VAR: &str = "COMMITCRAFT_GIT_BACKEND";
+
+/// Read access to the repository in the current directory.
+///
+/// Commits, tags and index updates always run the git command, so hooks,
+/// signing and the rest of the user's configuration apply to them.
+pub trait GitBackend {
+    fn name(&self) -> &'static str;
+
+    /// Whether the current directory is in a working tree, either by being
+    /// inside it or through `GIT_DIR` and `GIT_WORK_TREE`. Bare repositories
+    /// and the git directory itself don't count.
+    fn is_repository(&self) -> Result<bool, String>;
+
+    /// The diff of the changes in `scope`, empty when there are none.
+    fn diff(&self, scope: &ChangeScope) -> Result<String, String>;
+
+    /// The paths changed in `scope`, relative to the repository root.
+    fn changed_files(&self, scope: &ChangeScope) -> Result<Vec<String>, String>;
+
+    /// The subjects of the last `count` non-merge commits, newest first,
+    /// limited to commits touching `paths` when any are given.
+    fn recent_subjects(&self, count: usize, paths: &[String]) -> Result<Vec<String>, String>;
+
+    /// The non-merge commits in `from..to`, newest first.
+    fn log(&self, from: Option<&str>, to: &str) -> Result<Vec<LoggedCommit>, String>;
+
+    /// The checked-out branch, or `None` on a detached `HEAD`.
+    fn current_branch(&self) -> Result<Option<String>, String>;
+
+    /// The top-level directory of the working tree.
+    fn repo_root(&self) -> Result<PathBuf, String>;
+
+    /// The git directory shared by all worktrees: the main worktree's
+    /// `.git`, or the bare repository linked worktrees were added to.
+    fn common_dir(&self) -> Result<PathBuf, String>;
+}
+
+/// Which backend serves repository reads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackendKind {
+    /// libgit2, falling back to the git command when it fails, e.g. on a
+    /// repository format libgit2 doesn't support.
+    #[default]
+    Auto,
+    Libgit2,
+    Subprocess,
+}
+
+impl BackendKind {
+    /// The backend chosen with [`BACKEND_VAR`].
+    pub fn from_env() -> Self {
+        match std::env::var(BACKEND_VAR).as_deref() {
+            Ok("libgit2") => BackendKind::Libgit2,
+            Ok("subprocess") => BackendKind::Subprocess,
+            _ => BackendKind::Auto,
+        }
+    }
+}
+
+/// Runs `read` with the selected backend.
+pub fn call<T>(read: impl Fn(&dyn GitBackend) -> Result<T, String>) -> Result<T, String> {
+    match BackendKind::from_env() {
+        BackendKind::Auto => read(&Libgit2).or_else(|_| read(&Subprocess)),
+        BackendKind::Libgit2 => read(&Libgit2),
+        BackendKind::Subprocess => read(&Subprocess),
+    }
+}
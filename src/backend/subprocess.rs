@@ -0,0 +1,104 @@
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use super::GitBackend;
+use crate::git::{diff_base, git_output, lines, ChangeScope, LoggedCommit};
+
+/// Runs the git command and parses its output.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Subprocess;
+
+/// The `git diff` arguments selecting the changes in `scope`. Color and
+/// external diff tools are turned off whatever the user configured.
+fn diff_args(scope: &ChangeScope) -> Vec<&str> {
+    let mut args = vec!["diff", "--no-color", "--no-ext-diff"];
+    match scope {
+        ChangeScope::Staged => args.push("--staged"),
+        ChangeScope::All => args.push(diff_base()),
+        ChangeScope::Paths(paths) => {
+            args.extend([diff_base(), "--"]);
+            args.extend(paths.iter().map(String::as_str));
+        }
+    }
+    args
+}
+
+impl GitBackend for Subprocess {
+    fn name(&self) -> &'static str {
+        "subprocess"
+    }
+
+    fn is_repository(&self) -> Result<bool, String> {
+        // `--is-inside-work-tree` succeeds in bare repositories too, printing
+        // "false", and is false with `GIT_WORK_TREE` from outside the tree
+        Ok(Command::new("git")
+            .arg("rev-parse")
+            .arg("--show-toplevel")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success()))
+    }
+
+    fn diff(&self, scope: &ChangeScope) -> Result<String, String> {
+        git_output(&diff_args(scope))
+    }
+
+    fn changed_files(&self, scope: &ChangeScope) -> Result<Vec<String>, String> {
+        let mut args = diff_args(scope);
+        // Options must come before the `--` of a paths scope
+        args.insert(3, "--name-only");
+        git_output(&args).map(|output| lines(&output))
+    }
+
+    fn recent_subjects(&self, count: usize, paths: &[String]) -> Result<Vec<String>, String> {
+        if count == 0 || diff_base() != "HEAD" {
+            return Ok(Vec::new());
+        }
+        let max_count = format!("--max-count={}", count);
+        let mut args = vec!["log", "--no-merges", "--format=%s", max_count.as_str()];
+        if !paths.is_empty() {
+            args.push("--");
+            args.extend(paths.iter().map(String::as_str));
+        }
+        git_output(&args).map(|output| lines(&output))
+    }
+
+    fn log(&self, from: Option<&str>, to: &str) -> Result<Vec<LoggedCommit>, String> {
+        let range = match from {
+            Some(from) => format!("{}..{}", from, to),
+            None => to.to_string(),
+        };
+        let output = git_output(&["log", "--no-merges", "--format=%h%x1f%B%x1e", &range, "--"])?;
+        Ok(output
+            .split('\x1e')
+            .filter_map(|record| {
+                let (hash, message) = record.trim_start().split_once('\x1f')?;
+                Some(LoggedCommit {
+                    hash: hash.to_string(),
+                    message: message.trim().to_string(),
+                })
+            })
+            .collect())
+    }
+
+    fn current_branch(&self) -> Result<Option<String>, String> {
+        Ok(git_output(&["symbolic-ref", "--short", "-q", "HEAD"])
+            .ok()
+            .map(|branch| branch.trim().to_string())
+            .filter(|branch| !branch.is_empty()))
+    }
+
+    fn repo_root(&self) -> Result<PathBuf, String> {
+        git_output(&["rev-parse", "--show-toplevel"]).map(|root| PathBuf::from(root.trim()))
+    }
+
+    fn common_dir(&self) -> Result<PathBuf, String> {
+        // Printed relative to the current directory unless it's elsewhere
+        let dir = git_output(&["rev-parse", "--git-common-dir"])?;
+        let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+        cwd.join(dir.trim())
+            .canonicalize()
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,507 @@
+use regex::Regex;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::fmt;
+use toml_edit::DocumentMut;
+
+use crate::conventional;
+use crate::git::LoggedCommit;
+
+/// A semantic version such as `1.2.0-rc.1`. Build metadata is dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Option<String>,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: None,
+        }
+    }
+
+    /// Parses a version, with or without a leading `v` as in tags.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.strip_prefix('v').unwrap_or(text);
+        let text = text.split_once('+').map_or(text, |(version, _)| version);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => (core, Some(pre.to_string())),
+            Some(_) => return None,
+            None => (text, None),
+        };
+        let mut numbers = core.split('.').map(|number| {
+            if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            number.parse::<u64>().ok()
+        });
+        let version = Version {
+            major: numbers.next()??,
+            minor: numbers.next()??,
+            patch: numbers.next()??,
+            pre,
+        };
+        numbers.next().is_none().then_some(version)
+    }
+
+    /// The version without its pre-release part.
+    pub fn release(&self) -> Self {
+        Self::new(self.major, self.minor, self.patch)
+    }
+
+    /// The next version for `level`. Before 1.0.0, breaking changes bump the
+    /// minor version and everything else the patch version.
+    pub fn bump(&self, level: Bump) -> Self {
+        match (self.major, level) {
+            (0, Bump::Major) => Self::new(0, self.minor + 1, 0),
+            (0, _) => Self::new(0, self.minor, self.patch + 1),
+            (_, Bump::Major) => Self::new(self.major + 1, 0, 0),
+            (_, Bump::Minor) => Self::new(self.major, self.minor + 1, 0),
+            (_, Bump::Patch) => Self::new(self.major, self.minor, self.patch + 1),
+        }
+    }
+
+    /// The number after `id.` in the pre-release, e.g. 2 for `rc.2`.
+    fn pre_number(&self, id: &str) -> Option<u64> {
+        let pre = self.pre.as_deref()?;
+        if pre == id {
+            return Some(0);
+        }
+        pre.strip_prefix(id)?.strip_prefix('.')?.parse().ok()
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(pre) = &self.pre {
+            write!(f, "-{}", pre)?;
+        }
+        Ok(())
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (&self.pre, &other.pre) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => compare_pre(a, b),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares pre-releases field by field: numeric fields numerically and
+/// below alphanumeric ones, and a shorter list first when all else is equal.
+fn compare_pre(a: &str, b: &str) -> Ordering {
+    let mut a_fields = a.split('.');
+    let mut b_fields = b.split('.');
+    loop {
+        let ordering = match (a_fields.next(), b_fields.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(a), Some(b)) => match (a.parse::<u64>(), b.parse::<u64>()) {
+                (Ok(a), Ok(b)) => a.cmp(&b),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => a.cmp(b),
+            },
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Which part of the version a release bumps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        })
+    }
+}
+
+/// The commits since the last release, counted by their effect on the version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Changes {
+    pub breaking: usize,
+    pub features: usize,
+    pub fixes: usize,
+    /// Other types and commits that don't follow Conventional Commits.
+    pub other: usize,
+}
+
+impl Changes {
+    pub fn from_commits(commits: &[LoggedCommit]) -> Self {
+        let mut changes = Changes::default();
+        for commit in commits {
+            match conventional::parse(&commit.message) {
+                Ok(parsed) if parsed.breaking => changes.breaking += 1,
+                Ok(parsed) if parsed.commit_type == "feat" => changes.features += 1,
+                Ok(parsed) if parsed.commit_type == "fix" => changes.fixes += 1,
+                _ => changes.other += 1,
+            }
+        }
+        changes
+    }
+
+    /// The bump the changes call for, or `None` when there are none.
+    pub fn level(&self) -> Option<Bump> {
+        if self.breaking > 0 {
+            Some(Bump::Major)
+        } else if self.features > 0 {
+            Some(Bump::Minor)
+        } else if self.fixes + self.other > 0 {
+            Some(Bump::Patch)
+        } else {
+            None
+        }
+    }
+}
+
+/// The version to release after `stable`, the latest release, given the
+/// latest pre-release above it. Without any release the first version is
+/// 0.1.0. A pre-release line that already covers the bump is continued, so
+/// `1.2.0-rc.1` becomes `1.2.0` or, with `pre`, `1.2.0-rc.2`.
+pub fn next_version(
+    stable: Option<&Version>,
+    latest_pre: Option<&Version>,
+    level: Bump,
+    pre: Option<&str>,
+) -> Version {
+    let mut target = stable.map_or_else(|| Version::new(0, 1, 0), |stable| stable.bump(level));
+    if let Some(latest_pre) = latest_pre {
+        if latest_pre.release() >= target {
+            target = latest_pre.release();
+        }
+    }
+    let Some(id) = pre else {
+        return target;
+    };
+    let number = latest_pre
+        .filter(|latest_pre| latest_pre.release() == target)
+        .and_then(|latest_pre| latest_pre.pre_number(id))
+        .map_or(1, |number| number + 1);
+    Version {
+        pre: Some(format!("{}.{}", id, number)),
+        ..target
+    }
+}
+
+/// Sets the version of a `Cargo.toml`, in `[package]` and
+/// `[workspace.package]`. Returns the new content and the previous version,
+/// or `None` if the manifest has no version of its own.
+pub fn set_cargo_version(
+    content: &str,
+    version: &Version,
+) -> Result<Option<(String, String)>, String> {
+    let mut manifest: DocumentMut = content
+        .parse()
+        .map_err(|e| format!("Failed to parse Cargo.toml: {}", e))?;
+    let mut previous = None;
+    for path in [&["package"][..], &["workspace", "package"]] {
+        let version_item = path
+            .iter()
+            .try_fold(manifest.as_item_mut(), |item, key| {
+                item.as_table_like_mut()?.get_mut(key)
+            })
+            .and_then(|table| table.as_table_like_mut()?.get_mut("version"));
+        let Some(version_item) = version_item else {
+            continue;
+        };
+        if let Some(current) = version_item.as_str() {
+            previous.get_or_insert_with(|| current.to_string());
+            let decor = version_item
+                .as_value()
+                .map(|value| value.decor().clone())
+                .unwrap_or_default();
+            *version_item = toml_edit::value(version.to_string());
+            if let Some(value) = version_item.as_value_mut() {
+                *value.decor_mut() = decor;
+            }
+        }
+    }
+    Ok(previous.map(|previous| (manifest.to_string(), previous)))
+}
+
+/// Sets the version of the local packages at `previous` in a `Cargo.lock`,
+/// so it matches the manifests without running cargo.
+pub fn set_lock_version(
+    content: &str,
+    previous: &str,
+    version: &Version,
+) -> Result<String, String> {
+    let mut lock: DocumentMut = content
+        .parse()
+        .map_err(|e| format!("Failed to parse Cargo.lock: {}", e))?;
+    if let Some(packages) = lock
+        .get_mut("package")
+        .and_then(|packages| packages.as_array_of_tables_mut())
+    {
+        for package in packages.iter_mut() {
+            let local = !package.contains_key("source");
+            if local && package.get("version").and_then(|v| v.as_str()) == Some(previous) {
+                package["version"] = toml_edit::value(version.to_string());
+            }
+        }
+    }
+    Ok(lock.to_string())
+}
+
+/// Sets the top-level `version` of a `package.json`, leaving the rest of the
+/// file as it is. Returns `None` if there is no version.
+pub fn set_package_json_version(
+    content: &str,
+    version: &Version,
+) -> Result<Option<String>, String> {
+    let package: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse package.json: {}", e))?;
+    if !package.get("version").is_some_and(|v| v.is_string()) {
+        return Ok(None);
+    }
+    // Nested objects such as `engines` or `volta` can have a `version` too
+    let top_level = top_level_strings(content);
+    let field = Regex::new(r#""version"(\s*):(\s*)"[^"]*""#).expect("valid regex");
+    let Some(captures) = field
+        .captures_iter(content)
+        .find(|captures| top_level.contains(&captures.get(0).map_or(0, |m| m.start())))
+    else {
+        return Ok(None);
+    };
+    let range = captures.get(0).map_or(0..0, |m| m.range());
+    Ok(Some(format!(
+        r#"{}"version"{}:{}"{}"{}"#,
+        &content[..range.start],
+        &captures[1],
+        &captures[2],
+        version,
+        &content[range.end..]
+    )))
+}
+
+/// The offsets of the strings directly inside the outermost object or array
+/// of a JSON document.
+fn top_level_strings(content: &str) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (offset, c) in content.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                if depth == 1 {
+                    offsets.push(offset);
+                }
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(v("v1.2.3"), Version::new(1, 2, 3));
+        assert_eq!(v("1.2.3-rc.1+build.5").pre.as_deref(), Some("rc.1"));
+        assert_eq!(v("1.2.3-rc.1").to_string(), "1.2.3-rc.1");
+        assert_eq!(Version::parse("1.2"), None);
+        assert_eq!(Version::parse("1.2.3.4"), None);
+        assert_eq!(Version::parse("release-1"), None);
+        assert_eq!(Version::parse("1.2.3-"), None);
+    }
+
+    #[test]
+    fn test_version_order() {
+        let mut versions = [
+            v("1.0.0"),
+            v("1.0.0-rc.1"),
+            v("1.0.0-beta.11"),
+            v("1.0.0-beta.2"),
+            v("1.0.0-beta"),
+            v("1.0.0-alpha.1"),
+            v("0.9.10"),
+            v("0.9.9"),
+        ];
+        versions.sort();
+        let sorted: Vec<String> = versions.iter().map(Version::to_string).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                "0.9.9",
+                "0.9.10",
+                "1.0.0-alpha.1",
+                "1.0.0-beta",
+                "1.0.0-beta.2",
+                "1.0.0-beta.11",
+                "1.0.0-rc.1",
+                "1.0.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_changes_level() {
+        let log = |messages: &[&str]| -> Vec<LoggedCommit> {
+            messages
+                .iter()
+                .map(|message| LoggedCommit {
+                    hash: "abc".to_string(),
+                    message: message.to_string(),
+                })
+                .collect()
+        };
+        let changes = Changes::from_commits(&log(&[
+            "feat: add refunds",
+            "fix: typo",
+            "docs: guide",
+            "update stuff",
+        ]));
+        assert_eq!(
+            changes,
+            Changes {
+                breaking: 0,
+                features: 1,
+                fixes: 1,
+                other: 2
+            }
+        );
+        assert_eq!(changes.level(), Some(Bump::Minor));
+        let breaking = Changes::from_commits(&log(&["fix: x\n\nBREAKING CHANGE: y"]));
+        assert_eq!(breaking.level(), Some(Bump::Major));
+        assert_eq!(
+            Changes::from_commits(&log(&["chore: deps"])).level(),
+            Some(Bump::Patch)
+        );
+        assert_eq!(Changes::default().level(), None);
+    }
+
+    #[test]
+    fn test_next_version() {
+        let next = |stable: Option<&str>, pre_tag: Option<&str>, level, pre| {
+            next_version(stable.map(v).as_ref(), pre_tag.map(v).as_ref(), level, pre).to_string()
+        };
+        assert_eq!(next(Some("1.2.3"), None, Bump::Major, None), "2.0.0");
+        assert_eq!(next(Some("1.2.3"), None, Bump::Minor, None), "1.3.0");
+        assert_eq!(next(Some("1.2.3"), None, Bump::Patch, None), "1.2.4");
+        // Before 1.0.0
+        assert_eq!(next(Some("0.4.2"), None, Bump::Major, None), "0.5.0");
+        assert_eq!(next(Some("0.4.2"), None, Bump::Minor, None), "0.4.3");
+        assert_eq!(next(None, None, Bump::Major, None), "0.1.0");
+        // Pre-releases
+        assert_eq!(
+            next(Some("1.2.3"), None, Bump::Minor, Some("rc")),
+            "1.3.0-rc.1"
+        );
+        assert_eq!(
+            next(Some("1.2.3"), Some("1.3.0-rc.1"), Bump::Patch, Some("rc")),
+            "1.3.0-rc.2"
+        );
+        assert_eq!(
+            next(Some("1.2.3"), Some("1.3.0-rc.2"), Bump::Minor, None),
+            "1.3.0"
+        );
+        assert_eq!(
+            next(Some("1.2.3"), Some("1.3.0-beta.2"), Bump::Minor, Some("rc")),
+            "1.3.0-rc.1"
+        );
+        // A breaking change outgrows the pre-release line
+        assert_eq!(
+            next(Some("1.2.3"), Some("1.3.0-rc.1"), Bump::Major, Some("rc")),
+            "2.0.0-rc.1"
+        );
+    }
+
+    #[test]
+    fn test_set_cargo_version() {
+        let manifest =
+            "[package]\nname = \"app\"\nversion = \"1.2.3\" # keep me\nedition = \"2021\"\n";
+        let (updated, previous) = set_cargo_version(manifest, &v("1.3.0")).unwrap().unwrap();
+        assert_eq!(previous, "1.2.3");
+        assert_eq!(
+            updated,
+            "[package]\nname = \"app\"\nversion = \"1.3.0\" # keep me\nedition = \"2021\"\n"
+        );
+
+        let workspace =
+            "[workspace]\nmembers = [\"a\"]\n\n[workspace.package]\nversion = \"0.1.0\"\n";
+        let (updated, _) = set_cargo_version(workspace, &v("0.2.0")).unwrap().unwrap();
+        assert!(updated.contains("[workspace.package]\nversion = \"0.2.0\"\n"));
+
+        let inherited = "[package]\nname = \"a\"\nversion.workspace = true\n";
+        assert_eq!(set_cargo_version(inherited, &v("0.2.0")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_set_lock_version() {
+        let lock = "version = 3\n\n[[package]]\nname = \"app\"\nversion = \"1.2.3\"\ndependencies = [\n \"dep\",\n]\n\n[[package]]\nname = \"dep\"\nversion = \"1.2.3\"\nsource = \"registry+https://github.com/rust-lang/crates.io-index\"\n";
+        let updated = set_lock_version(lock, "1.2.3", &v("1.3.0")).unwrap();
+        assert_eq!(updated, lock.replacen("1.2.3", "1.3.0", 1));
+    }
+
+    #[test]
+    fn test_set_package_json_version() {
+        let package =
+            "{\n  \"name\": \"app\",\n  \"version\": \"1.2.3\",\n  \"private\": true\n}\n";
+        assert_eq!(
+            set_package_json_version(package, &v("2.0.0")).unwrap(),
+            Some(package.replace("1.2.3", "2.0.0"))
+        );
+        assert_eq!(
+            set_package_json_version("{\"name\": \"app\"}", &v("2.0.0")).unwrap(),
+            None
+        );
+
+        let nested = "{\n  \"name\": \"app\",\n  \"volta\": { \"node\": \"20.1.0\", \"version\": \"0.1.0\" },\n  \"version\": \"1.2.3\"\n}\n";
+        assert_eq!(
+            set_package_json_version(nested, &v("2.0.0")).unwrap(),
+            Some(nested.replace("1.2.3", "2.0.0"))
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use crate::conventional;
+use crate::git::LoggedCommit;
+
+/// The changelog sections, in order, with the commit types they collect.
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("✨ New Features", &["feat"]),
+    ("🐛 Bug Fixes", &["fix"]),
+    ("📈 Performance Improvements", &["perf"]),
+    (
+        "🏗️ Technical Improvements",
+        &["refactor", "build", "ci", "chore", "style"],
+    ),
+    ("🧪 Testing", &["test"]),
+    ("📚 Documentation", &["docs"]),
+];
+
+const OTHER_SECTION: &str = "📦 Other Changes";
+const BREAKING_SECTION: &str = "⚠️ Breaking Changes";
+
+/// The start of a new changelog file.
+pub const HEADER: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+The format is based on [Keep a Changelog](https://keepachangelog.com/en/1.0.0/),
+and this project adheres to [Semantic Versioning](https://semver.org/spec/v2.0.0.html).
+";
+
+/// A changelog line for one commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub commit_type: String,
+    pub scope: Option<String>,
+    pub text: String,
+    /// The breaking change description, for breaking commits.
+    pub breaking: Option<String>,
+    pub hash: String,
+}
+
+/// The changelog section for a range of commits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Release {
+    /// The version, or `None` for unreleased changes.
+    pub version: Option<String>,
+    pub date: Option<String>,
+    pub entries: Vec<Entry>,
+    /// Commits left out because they don't follow Conventional Commits.
+    pub skipped: usize,
+}
+
+impl Release {
+    /// Collects the entries of `commits`, keeping their order.
+    pub fn from_commits(commits: &[LoggedCommit]) -> Self {
+        let mut release = Release::default();
+        for commit in commits {
+            match conventional::parse(&commit.message) {
+                Ok(parsed) => release.entries.push(Entry {
+                    breaking: parsed.breaking_description().map(str::to_string),
+                    commit_type: parsed.commit_type,
+                    scope: parsed.scope,
+                    text: parsed.subject,
+                    hash: commit.hash.clone(),
+                }),
+                Err(_) => release.skipped += 1,
+            }
+        }
+        release
+    }
+
+    /// The `## [version] - date` heading line.
+    pub fn heading(&self) -> String {
+        match (&self.version, &self.date) {
+            (Some(version), Some(date)) => format!("## [{}] - {}", version, date),
+            (Some(version), None) => format!("## [{}]", version),
+            (None, _) => "## [Unreleased]".to_string(),
+        }
+    }
+
+    /// Renders the release as a Keep a Changelog section.
+    pub fn render(&self) -> String {
+        let mut out = self.heading();
+        out.push('\n');
+
+        let breaking: Vec<&Entry> = self
+            .entries
+            .iter()
+            .filter(|entry| entry.breaking.is_some())
+            .collect();
+        if !breaking.is_empty() {
+            out.push_str(&format!("\n### {}\n", BREAKING_SECTION));
+            for entry in breaking {
+                let description = entry.breaking.as_deref().unwrap_or_default();
+                out.push_str(&format!(
+                    "- {}{} ({})\n",
+                    scope_prefix(entry),
+                    description.replace('\n', " "),
+                    entry.hash
+                ));
+            }
+        }
+
+        let mut sections: Vec<(&str, Vec<&Entry>)> = Vec::new();
+        for entry in &self.entries {
+            let title = section_title(&entry.commit_type);
+            match sections.iter_mut().find(|(known, _)| *known == title) {
+                Some((_, entries)) => entries.push(entry),
+                None => sections.push((title, vec![entry])),
+            }
+        }
+        sections.sort_by_key(|(title, _)| section_order(title));
+
+        for (title, entries) in sections {
+            out.push_str(&format!("\n### {}\n", title));
+            for entry in entries {
+                let marker = if entry.breaking.is_some() {
+                    "**BREAKING** "
+                } else {
+                    ""
+                };
+                out.push_str(&format!(
+                    "- {}{}{} ({})\n",
+                    marker,
+                    scope_prefix(entry),
+                    entry.text,
+                    entry.hash
+                ));
+            }
+        }
+        out
+    }
+
+    /// The entries as a numbered list for [`crate::AIProvider::polish_changelog`].
+    pub fn polish_prompt(&self) -> String {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let scope = entry
+                    .scope
+                    .as_ref()
+                    .map(|scope| format!("({})", scope))
+                    .unwrap_or_default();
+                format!(
+                    "{}. {}{}: {}",
+                    index + 1,
+                    entry.commit_type,
+                    scope,
+                    entry.text
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Replaces the entry texts with polished ones, which must match the
+    /// entries one to one.
+    pub fn apply_polish(&mut self, polished: Vec<String>) -> Result<(), String> {
+        if polished.len() != self.entries.len() {
+            return Err(format!(
+                "Expected {} polished entries but got {}",
+                self.entries.len(),
+                polished.len()
+            ));
+        }
+        for (entry, text) in self.entries.iter_mut().zip(polished) {
+            let text = text.trim();
+            if !text.is_empty() {
+                entry.text = text.to_string();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn scope_prefix(entry: &Entry) -> String {
+    entry
+        .scope
+        .as_ref()
+        .map(|scope| format!("**{}**: ", scope))
+        .unwrap_or_default()
+}
+
+fn section_title(commit_type: &str) -> &'static str {
+    SECTIONS
+        .iter()
+        .find(|(_, types)| types.contains(&commit_type))
+        .map(|(title, _)| *title)
+        .unwrap_or(OTHER_SECTION)
+}
+
+fn section_order(title: &str) -> usize {
+    SECTIONS
+        .iter()
+        .position(|(known, _)| *known == title)
+        .unwrap_or(SECTIONS.len())
+}
+
+/// The version in a tag such as `v1.2.0`.
+pub fn version_from_tag(tag: &str) -> &str {
+    match tag.strip_prefix('v') {
+        Some(version) if version.starts_with(|c: char| c.is_ascii_digit()) => version,
+        _ => tag,
+    }
+}
+
+/// Adds `section` to a changelog above the latest release. A section with
+/// the same heading is replaced, so regenerating `[Unreleased]` doesn't
+/// duplicate it. Files that separate releases with `---` keep doing so.
+pub fn prepend(existing: &str, section: &str) -> String {
+    if existing.trim().is_empty() {
+        return format!("{}\n{}", HEADER, section);
+    }
+    let heading = section.lines().next().unwrap_or_default();
+
+    let mut offset = 0;
+    let mut same_heading = None;
+    let mut first_release = None;
+    for line in existing.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if same_heading.is_none() && trimmed == heading {
+            same_heading = Some(offset);
+        } else if first_release.is_none() && trimmed.starts_with("## ") {
+            first_release = Some(offset);
+        }
+        offset += line.len();
+    }
+
+    if let Some(start) = same_heading {
+        // The old section ends at the next release or separator
+        let mut end = existing.len();
+        let mut offset = start;
+        for (index, line) in existing[start..].split_inclusive('\n').enumerate() {
+            let trimmed = line.trim_end();
+            if index > 0 && (trimmed.starts_with("## ") || trimmed == "---") {
+                end = offset;
+                break;
+            }
+            offset += line.len();
+        }
+        let rest = &existing[end..];
+        return if rest.is_empty() {
+            format!("{}{}", &existing[..start], section)
+        } else {
+            format!("{}{}\n{}", &existing[..start], section, rest)
+        };
+    }
+
+    match first_release {
+        Some(start) => {
+            let separator = if existing.lines().any(|line| line.trim_end() == "---") {
+                "---\n\n"
+            } else {
+                ""
+            };
+            format!(
+                "{}{}\n{}{}",
+                &existing[..start],
+                section,
+                separator,
+                &existing[start..]
+            )
+        }
+        None => format!("{}\n\n{}", existing.trim_end(), section),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(messages: &[&str]) -> Vec<LoggedCommit> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| LoggedCommit {
+                hash: format!("abc{}", index),
+                message: message.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_groups_by_type() {
+        let mut release = Release::from_commits(&log(&[
+            "fix(api): handle timeouts",
+            "update stuff",
+            "feat!: drop v1 endpoints\n\nBREAKING CHANGE: clients must use /v2",
+            "docs: describe refunds",
+            "feat(api): add refunds",
+            "chore: bump deps",
+            "wip: experiments",
+        ]));
+        release.version = Some("1.2.0".to_string());
+        release.date = Some("2025-02-01".to_string());
+        assert_eq!(release.skipped, 1);
+        assert_eq!(
+            release.render(),
+            "## [1.2.0] - 2025-02-01
+
+### ⚠️ Breaking Changes
+- clients must use /v2 (abc2)
+
+### ✨ New Features
+- **BREAKING** drop v1 endpoints (abc2)
+- **api**: add refunds (abc4)
+
+### 🐛 Bug Fixes
+- **api**: handle timeouts (abc0)
+
+### 🏗️ Technical Improvements
+- bump deps (abc5)
+
+### 📚 Documentation
+- describe refunds (abc3)
+
+### 📦 Other Changes
+- experiments (abc6)
+"
+        );
+        assert_eq!(Release::default().heading(), "## [Unreleased]");
+    }
+
+    #[test]
+    fn test_polish() {
+        let mut release = Release::from_commits(&log(&["feat(api): add refunds", "fix: typo"]));
+        assert_eq!(
+            release.polish_prompt(),
+            "1. feat(api): add refunds\n2. fix: typo"
+        );
+        assert!(release.apply_polish(vec!["Refunds".to_string()]).is_err());
+        release
+            .apply_polish(vec![
+                "Orders can be refunded".to_string(),
+                "Fixed a typo in the help text".to_string(),
+            ])
+            .unwrap();
+        assert_eq!(release.entries[0].text, "Orders can be refunded");
+        assert_eq!(release.entries[0].scope.as_deref(), Some("api"));
+    }
+
+    #[test]
+    fn test_version_from_tag() {
+        assert_eq!(version_from_tag("v1.2.0"), "1.2.0");
+        assert_eq!(version_from_tag("1.2.0"), "1.2.0");
+        assert_eq!(version_from_tag("very-old"), "very-old");
+    }
+
+    #[test]
+    fn test_prepend() {
+        let section = "## [1.1.0] - 2025-02-01\n\n### ✨ New Features\n- add refunds (abc)\n";
+        assert_eq!(prepend("", section), format!("{}\n{}", HEADER, section));
+
+        let existing = "# Changelog\n\nIntro.\n\n## [1.0.0] - 2025-01-01\n\n### Added\n- first\n";
+        assert_eq!(
+            prepend(existing, section),
+            format!(
+                "# Changelog\n\nIntro.\n\n{}\n## [1.0.0] - 2025-01-01\n\n### Added\n- first\n",
+                section
+            )
+        );
+
+        // Files separating releases with rules keep doing so
+        let ruled = "# Changelog\n\n## [1.0.0]\n- first\n\n---\n\n## [0.9.0]\n- zero\n";
+        assert_eq!(
+            prepend(ruled, section),
+            format!(
+                "# Changelog\n\n{}\n---\n\n## [1.0.0]\n- first\n\n---\n\n## [0.9.0]\n- zero\n",
+                section
+            )
+        );
+
+        assert_eq!(
+            prepend("# Changelog\n", section),
+            format!("# Changelog\n\n{}", section)
+        );
+    }
+
+    #[test]
+    fn test_prepend_replaces_same_heading() {
+        let unreleased = "## [Unreleased]\n\n### 🐛 Bug Fixes\n- new (def)\n";
+        let existing =
+            "# Changelog\n\n## [Unreleased]\n\n### 🐛 Bug Fixes\n- old (abc)\n\n## [1.0.0]\n- first\n";
+        assert_eq!(
+            prepend(existing, unreleased),
+            format!("# Changelog\n\n{}\n## [1.0.0]\n- first\n", unreleased)
+        );
+
+        let existing = "# Changelog\n\n## [Unreleased]\n- old\n";
+        assert_eq!(
+            prepend(existing, unreleased),
+            format!("# Changelog\n\n{}", unreleased)
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+
+use crate::git::{ChangeScope, CommitSettings, SignSetting};
+use crate::providers::http::NetworkSettings;
+use crate::providers::GenerationSettings;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -8,11 +13,11 @@ pub struct Cli {
     pub command: Option<Commands>,
 
     /// The AI provider to use (e.g., "gemini", "openai", "anthropic"). Overrides config default.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub provider: Option<String>,
 
     /// The specific model or alias to use (e.g., "fast", "gpt-4o"). Overrides config default.
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub model: Option<String>,
 
     /// Generate and output the commit message without committing.
@@ -46,16 +51,271 @@ pub struct Cli {
     /// Skip interactive command editing and commit immediately.
     #[arg(short = 'y', long)]
     pub yes: bool,
+
+    /// Generate from and commit all modifications to tracked files, like `git commit -a`.
+    #[arg(short = 'a', long, conflicts_with_all = ["paths", "diff_file", "stdin"])]
+    pub all: bool,
+
+    /// Limit the analyzed diff and the commit to these paths (after `--`).
+    #[arg(last = true, value_name = "PATHSPEC", conflicts_with_all = ["diff_file", "stdin"])]
+    pub paths: Vec<String>,
+
+    /// Read the diff from a patch file instead of the staged changes.
+    #[arg(long, value_name = "PATH", conflicts_with = "stdin")]
+    pub diff_file: Option<PathBuf>,
+
+    /// Read the diff from stdin instead of the staged changes.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// Output format. `json` prints a single JSON object to stdout and never commits.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Number of recent commit subjects to send as style examples (0 disables). Overrides config.
+    #[arg(long, value_name = "N")]
+    pub history: Option<usize>,
+
+    /// Number of alternative messages to generate and choose from.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=10))]
+    pub candidates: u8,
+
+    /// Sampling temperature. Overrides config.
+    #[arg(long)]
+    pub temperature: Option<f32>,
+
+    /// Nucleus sampling probability mass. Overrides config.
+    #[arg(long)]
+    pub top_p: Option<f32>,
+
+    /// Maximum number of tokens to generate. Overrides config.
+    #[arg(long)]
+    pub max_tokens: Option<u32>,
+
+    /// Request timeout in seconds. Overrides config.
+    #[arg(long)]
+    pub timeout: Option<u64>,
+
+    /// Stop sequence for generation (repeatable). Overrides config.
+    #[arg(long = "stop")]
+    pub stop: Vec<String>,
+
+    /// Connection timeout in seconds. Overrides config.
+    #[arg(long)]
+    pub connect_timeout: Option<u64>,
+
+    /// Add a Signed-off-by trailer to commits.
+    #[arg(long, global = true)]
+    pub signoff: bool,
+
+    /// Sign commits, with git's configured key or -S=<KEYID>. GPG or SSH follows git's gpg.format.
+    #[arg(
+        short = 'S',
+        long,
+        value_name = "KEYID",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        global = true,
+        conflicts_with = "no_gpg_sign"
+    )]
+    pub gpg_sign: Option<String>,
+
+    /// Don't sign commits, even when git or the config would.
+    #[arg(long, global = true)]
+    pub no_gpg_sign: bool,
+
+    /// Skip the pre-commit and commit-msg hooks.
+    #[arg(long, global = true)]
+    pub no_verify: bool,
+
+    /// Commit author, e.g. "Name <email>".
+    #[arg(long, global = true)]
+    pub author: Option<String>,
+
+    /// Author date, in any format git accepts.
+    #[arg(long, global = true)]
+    pub date: Option<String>,
+
+    /// Trailer to add to commits, e.g. "Reviewed-by: Name <email>" (repeatable).
+    #[arg(long = "trailer", value_name = "TRAILER", global = true)]
+    pub trailers: Vec<String>,
+
+    /// Credit co-authors on this commit, by roster nickname or "Name <email>", e.g. --pair alice,bob.
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', global = true)]
+    pub pair: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl Cli {
+    /// Which changes to generate from and commit.
+    pub fn change_scope(&self) -> ChangeScope {
+        if self.all {
+            ChangeScope::All
+        } else if !self.paths.is_empty() {
+            ChangeScope::Paths(self.paths.clone())
+        } else {
+            ChangeScope::Staged
+        }
+    }
+
+    /// Generation settings given on the command line.
+    pub fn generation_overrides(&self) -> GenerationSettings {
+        GenerationSettings {
+            temperature: self.temperature,
+            top_p: self.top_p,
+            max_tokens: self.max_tokens,
+            timeout_secs: self.timeout,
+            stop: if self.stop.is_empty() {
+                None
+            } else {
+                Some(self.stop.clone())
+            },
+        }
+    }
+
+    /// Commit settings given on the command line.
+    pub fn commit_overrides(&self) -> CommitSettings {
+        let sign = match &self.gpg_sign {
+            _ if self.no_gpg_sign => Some(SignSetting::Enabled(false)),
+            Some(key) if key.is_empty() => Some(SignSetting::Enabled(true)),
+            Some(key) => Some(SignSetting::Key(key.clone())),
+            None => None,
+        };
+        CommitSettings {
+            signoff: self.signoff.then_some(true),
+            sign,
+            no_verify: self.no_verify.then_some(true),
+            author: self.author.clone(),
+            date: self.date.clone(),
+            trailers: self.trailers.clone(),
+        }
+    }
+
+    /// Network settings given on the command line.
+    pub fn network_overrides(&self) -> NetworkSettings {
+        NetworkSettings {
+            connect_timeout_secs: self.connect_timeout,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Run the interactive first-time setup to configure the tool.
     Setup,
-    /// List all configured providers and models.
-    List,
+    /// List available models, queried live from each configured provider.
+    List {
+        /// Ignore cached model lists and query the providers again.
+        #[arg(long)]
+        refresh: bool,
+    },
     /// Show current configuration.
     Config,
+    /// Diagnose problems with git, configuration, API keys and network access.
+    Doctor {
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+        /// Skip the authenticated call to each provider.
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Check a commit message against the Conventional Commits format and the
+    /// ticket rules, e.g. from a `commit-msg` hook.
+    Lint {
+        /// File with the message, e.g. `.git/COMMIT_EDITMSG`. Reads stdin when omitted or `-`.
+        file: Option<PathBuf>,
+        /// Branch to take ticket IDs from instead of the checked-out one.
+        #[arg(long)]
+        branch: Option<String>,
+    },
+    /// Split the staged changes into several commits, each with its own message.
+    Split {
+        /// Show the proposed commits without creating them.
+        #[arg(long)]
+        dry_run: bool,
+        /// Create the proposed commits without asking for confirmation.
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+    /// Write a pull request title and description for the current branch.
+    Pr {
+        /// Branch the pull request merges into. Defaults to `origin/HEAD`, or `main`.
+        #[arg(long)]
+        base: Option<String>,
+        /// Print the title and body as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Write one conventional message for squashing the commits in a range.
+    SquashMsg {
+        /// Commits to squash, e.g. `main..feature`. A single branch means `HEAD..<branch>`.
+        range: String,
+        /// Write the message to `.git/SQUASH_MSG`, where `git commit` picks it up
+        /// after `git merge --squash`.
+        #[arg(long)]
+        write: bool,
+    },
+    /// Suggest the next semantic version from the commits since the latest version tag.
+    Bump {
+        /// Make a pre-release with this identifier, e.g. `rc` for `1.2.0-rc.1`.
+        #[arg(long, value_name = "ID")]
+        pre: Option<String>,
+        /// Set the version in Cargo.toml, Cargo.lock and package.json.
+        #[arg(long)]
+        write: bool,
+        /// Create an annotated tag for the version. With --write, the
+        /// manifests are committed first.
+        #[arg(long)]
+        tag: bool,
+        /// Print the result as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Generate a Keep a Changelog section from the conventional commits in a range.
+    Changelog {
+        /// Start of the range, not included. Defaults to the latest tag.
+        #[arg(long)]
+        from: Option<String>,
+        /// End of the range.
+        #[arg(long, default_value = "HEAD")]
+        to: String,
+        /// Version for the section heading. Defaults to the `--to` tag, or "Unreleased".
+        #[arg(long)]
+        release: Option<String>,
+        /// Add the section to a changelog file instead of printing it.
+        #[arg(long, value_name = "FILE", num_args = 0..=1, default_missing_value = "CHANGELOG.md")]
+        prepend: Option<PathBuf>,
+        /// Ask the model to rewrite the entries for readers of the changelog.
+        #[arg(long)]
+        polish: bool,
+    },
+    /// Credit co-authors on every commit until the pairing session stops.
+    Pair {
+        #[command(subcommand)]
+        action: Option<PairAction>,
+    },
+}
+
+#[derive(Subcommand, Debug, PartialEq, Eq)]
+pub enum PairAction {
+    /// Start pairing, replacing any current session.
+    Start {
+        /// Roster nicknames or "Name <email>", separated by spaces or commas.
+        #[arg(required = true, value_delimiter = ',')]
+        names: Vec<String>,
+    },
+    /// Stop pairing.
+    Stop,
+    /// Show who the current session credits (the default).
+    Status,
 }
 
 #[cfg(test)]
@@ -71,6 +331,58 @@ mod tests {
         assert_eq!(cli.model, Some("gpt-4o".to_string()));
     }
 
+    #[test]
+    fn test_cli_change_scope() {
+        assert_eq!(
+            Cli::parse_from(["prog"]).change_scope(),
+            ChangeScope::Staged
+        );
+        assert_eq!(
+            Cli::parse_from(["prog", "-a"]).change_scope(),
+            ChangeScope::All
+        );
+        assert_eq!(
+            Cli::parse_from(["prog", "--dry-run", "--", "src/api", "README.md"]).change_scope(),
+            ChangeScope::Paths(vec!["src/api".to_string(), "README.md".to_string()])
+        );
+        assert!(Cli::try_parse_from(["prog", "--all", "--", "src"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "--all", "--stdin"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "src"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_diff_sources() {
+        let cli = Cli::parse_from(["prog", "--diff-file", "patch.diff", "--dry-run"]);
+        assert_eq!(cli.diff_file, Some(PathBuf::from("patch.diff")));
+        assert!(!cli.stdin);
+        assert!(Cli::parse_from(["prog", "--stdin"]).stdin);
+        assert!(Cli::try_parse_from(["prog", "--stdin", "--diff-file", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_output() {
+        assert_eq!(Cli::parse_from(["prog"]).output, OutputFormat::Text);
+        assert_eq!(
+            Cli::parse_from(["prog", "--output", "json"]).output,
+            OutputFormat::Json
+        );
+        assert!(Cli::try_parse_from(["prog", "--output", "yaml"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_candidates() {
+        assert_eq!(Cli::parse_from(["prog"]).candidates, 1);
+        assert_eq!(Cli::parse_from(["prog", "--candidates", "3"]).candidates, 3);
+        assert!(Cli::try_parse_from(["prog", "--candidates", "0"]).is_err());
+        assert!(Cli::try_parse_from(["prog", "--candidates", "11"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_history() {
+        assert_eq!(Cli::parse_from(["prog"]).history, None);
+        assert_eq!(Cli::parse_from(["prog", "--history", "0"]).history, Some(0));
+    }
+
     #[test]
     fn test_cli_parse_dry_run_and_force() {
         let args = vec!["prog", "--dry-run", "--force"];
@@ -79,6 +391,236 @@ mod tests {
         assert!(cli.force);
     }
 
+    #[test]
+    fn test_cli_parse_generation_overrides() {
+        let args = vec![
+            "prog",
+            "--temperature",
+            "0.7",
+            "--max-tokens",
+            "300",
+            "--stop",
+            "END",
+            "--stop",
+            "###",
+        ];
+        let cli = Cli::parse_from(args);
+        let overrides = cli.generation_overrides();
+        assert_eq!(overrides.temperature, Some(0.7));
+        assert_eq!(overrides.max_tokens, Some(300));
+        assert!(overrides.top_p.is_none());
+        assert_eq!(
+            overrides.stop,
+            Some(vec!["END".to_string(), "###".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_commit_overrides() {
+        let cli = Cli::parse_from(["commitcraft", "-y"]);
+        assert_eq!(cli.commit_overrides(), CommitSettings::default());
+
+        let cli = Cli::parse_from([
+            "commitcraft",
+            "--signoff",
+            "-S",
+            "--no-verify",
+            "--author",
+            "Ann <ann@example.com>",
+            "--trailer",
+            "Refs: #1",
+            "--",
+            "src",
+        ]);
+        let overrides = cli.commit_overrides();
+        assert_eq!(overrides.signoff, Some(true));
+        assert_eq!(overrides.sign, Some(SignSetting::Enabled(true)));
+        assert_eq!(overrides.no_verify, Some(true));
+        assert_eq!(overrides.author.as_deref(), Some("Ann <ann@example.com>"));
+        assert_eq!(overrides.trailers, vec!["Refs: #1"]);
+        assert_eq!(cli.paths, vec!["src"]);
+
+        let cli = Cli::parse_from(["commitcraft", "-S=ABC123", "--", "src"]);
+        assert_eq!(
+            cli.commit_overrides().sign,
+            Some(SignSetting::Key("ABC123".to_string()))
+        );
+        assert_eq!(cli.paths, vec!["src"]);
+        let cli = Cli::parse_from(["commitcraft", "--no-gpg-sign"]);
+        assert_eq!(
+            cli.commit_overrides().sign,
+            Some(SignSetting::Enabled(false))
+        );
+        assert!(Cli::try_parse_from(["commitcraft", "-S", "--no-gpg-sign"]).is_err());
+
+        // The flags also apply to the commits of subcommands
+        let cli = Cli::parse_from(["commitcraft", "split", "--signoff"]);
+        assert!(cli.signoff);
+    }
+
+    #[test]
+    fn test_cli_parse_list_refresh() {
+        let cli = Cli::parse_from(vec!["prog", "list", "--refresh"]);
+        match cli.command {
+            Some(Commands::List { refresh }) => assert!(refresh),
+            _ => panic!("Expected List command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_doctor_json() {
+        let cli = Cli::parse_from(vec!["prog", "doctor", "--json"]);
+        match cli.command {
+            Some(Commands::Doctor { json, offline }) => {
+                assert!(json);
+                assert!(!offline);
+            }
+            _ => panic!("Expected Doctor command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_split() {
+        let cli = Cli::parse_from(vec!["prog", "split", "--dry-run", "-p", "openai"]);
+        assert_eq!(cli.provider, Some("openai".to_string()));
+        match cli.command {
+            Some(Commands::Split { dry_run, yes }) => {
+                assert!(dry_run);
+                assert!(!yes);
+            }
+            _ => panic!("Expected Split command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_pr() {
+        let cli = Cli::parse_from(vec!["prog", "pr", "--base", "develop", "--json"]);
+        match cli.command {
+            Some(Commands::Pr { base, json }) => {
+                assert_eq!(base.as_deref(), Some("develop"));
+                assert!(json);
+            }
+            _ => panic!("Expected Pr command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_squash_msg() {
+        let cli = Cli::parse_from(vec!["prog", "squash-msg", "main..feature", "--write"]);
+        match cli.command {
+            Some(Commands::SquashMsg { range, write }) => {
+                assert_eq!(range, "main..feature");
+                assert!(write);
+            }
+            _ => panic!("Expected SquashMsg command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_bump() {
+        let cli = Cli::parse_from(vec!["prog", "bump", "--pre", "rc", "--write", "--tag"]);
+        match cli.command {
+            Some(Commands::Bump {
+                pre,
+                write,
+                tag,
+                json,
+            }) => {
+                assert_eq!(pre.as_deref(), Some("rc"));
+                assert!(write);
+                assert!(tag);
+                assert!(!json);
+            }
+            _ => panic!("Expected Bump command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_pair() {
+        let cli = Cli::parse_from(vec![
+            "prog",
+            "pair",
+            "start",
+            "alice,bob",
+            "Carol <c@x.org>",
+        ]);
+        match cli.command {
+            Some(Commands::Pair { action }) => assert_eq!(
+                action,
+                Some(PairAction::Start {
+                    names: vec![
+                        "alice".to_string(),
+                        "bob".to_string(),
+                        "Carol <c@x.org>".to_string()
+                    ]
+                })
+            ),
+            _ => panic!("Expected Pair command"),
+        }
+        let cli = Cli::parse_from(vec!["prog", "pair"]);
+        assert!(matches!(cli.command, Some(Commands::Pair { action: None })));
+        let cli = Cli::parse_from(vec!["prog", "pair", "stop"]);
+        assert!(matches!(
+            cli.command,
+            Some(Commands::Pair {
+                action: Some(PairAction::Stop)
+            })
+        ));
+
+        let cli = Cli::parse_from(vec!["prog", "--pair", "alice,bob", "--pair", "carol"]);
+        assert_eq!(cli.pair, vec!["alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn test_cli_parse_changelog() {
+        let cli = Cli::parse_from(vec!["prog", "changelog", "--from", "v1.0.0", "--prepend"]);
+        match cli.command {
+            Some(Commands::Changelog {
+                from,
+                to,
+                release,
+                prepend,
+                polish,
+            }) => {
+                assert_eq!(from.as_deref(), Some("v1.0.0"));
+                assert_eq!(to, "HEAD");
+                assert_eq!(release, None);
+                assert_eq!(prepend, Some(PathBuf::from("CHANGELOG.md")));
+                assert!(!polish);
+            }
+            _ => panic!("Expected Changelog command"),
+        }
+
+        let cli = Cli::parse_from(vec![
+            "prog",
+            "changelog",
+            "--prepend",
+            "NEWS.md",
+            "--polish",
+        ]);
+        match cli.command {
+            Some(Commands::Changelog {
+                prepend, polish, ..
+            }) => {
+                assert_eq!(prepend, Some(PathBuf::from("NEWS.md")));
+                assert!(polish);
+            }
+            _ => panic!("Expected Changelog command"),
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_lint() {
+        let cli = Cli::parse_from(vec!["prog", "lint", ".git/COMMIT_EDITMSG"]);
+        match cli.command {
+            Some(Commands::Lint { file, branch }) => {
+                assert_eq!(file, Some(PathBuf::from(".git/COMMIT_EDITMSG")));
+                assert_eq!(branch, None);
+            }
+            _ => panic!("Expected Lint command"),
+        }
+    }
+
     #[test]
     fn test_cli_parse_setup_command() {
         let args = vec!["prog", "setup"];
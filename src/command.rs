@@ -0,0 +1,360 @@
+use crate::git::{ChangeScope, CommitOptions, Signing};
+
+/// A `git commit` invocation parsed from a user-edited command line.
+///
+/// The command is never run through a shell: only the message and the flags
+/// below are extracted, and the commit itself goes through [`crate::git::commit_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitCommand {
+    pub message: String,
+    pub options: CommitOptions,
+}
+
+/// Quotes an argument for POSIX shells using single quotes, so nothing in it
+/// is expanded when the command is pasted into a terminal.
+pub fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+".contains(c))
+    {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Formats a `git commit` command for the message with every argument quoted.
+/// The title and body are passed as separate `-m` arguments.
+pub fn format_git_command(message: &str, options: &CommitOptions) -> String {
+    let mut args = vec!["git".to_string(), "commit".to_string()];
+    args.extend(options.flags().iter().map(|flag| quote_arg(flag)));
+
+    let message = message.trim();
+    let (title, body) = match message.split_once('\n') {
+        Some((title, body)) => (title.trim(), body.trim()),
+        None => (message, ""),
+    };
+    args.push("-m".to_string());
+    args.push(quote_arg(title));
+    if !body.is_empty() {
+        args.push("-m".to_string());
+        args.push(quote_arg(body));
+    }
+    if let ChangeScope::Paths(paths) = &options.scope {
+        args.push("--".to_string());
+        args.extend(paths.iter().map(|path| quote_arg(path)));
+    }
+
+    args.join(" ")
+}
+
+/// Splits a command line into arguments using POSIX quoting rules.
+///
+/// Quotes and backslashes are honoured, but nothing is expanded: `$`,
+/// backticks and globs inside quotes are kept literally, and unquoted shell
+/// syntax such as `;`, `|`, `$(` or backticks is rejected.
+pub fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("Unterminated double quote".to_string()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("Unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                match chars.next() {
+                    Some('\n') => {}
+                    Some(c) => current.push(c),
+                    None => return Err("Trailing backslash".to_string()),
+                }
+            }
+            ';' | '|' | '&' | '<' | '>' | '`' | '(' | ')' => {
+                return Err(format!("Shell syntax '{}' is not supported", c));
+            }
+            '$' if chars
+                .peek()
+                .is_some_and(|next| *next == '(' || *next == '{') =>
+            {
+                return Err("Shell expansions are not supported".to_string());
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+/// Parses an edited `git commit` command. Only `-m`/`--message`,
+/// `-e`/`--edit`, `-a`/`--all`, the signing, signoff, hook, author, date and
+/// trailer options, and paths after `--` are accepted; anything else is
+/// rejected.
+pub fn parse_commit_command(line: &str) -> Result<CommitCommand, String> {
+    let args = split_args(line)?;
+    match args.as_slice() {
+        [git, commit, ..] if git == "git" && commit == "commit" => {}
+        _ => return Err("Only 'git commit' commands can be run".to_string()),
+    }
+
+    let mut paragraphs = Vec::new();
+    let mut options = CommitOptions::default();
+    let mut all = false;
+    let mut paths = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-m" | "--message" => match rest.next() {
+                Some(message) => paragraphs.push(message.clone()),
+                None => return Err(format!("Option '{}' requires a message", arg)),
+            },
+            "-e" | "--edit" => options.review = true,
+            "-a" | "--all" => all = true,
+            "-s" | "--signoff" => options.signoff = true,
+            "-S" | "--gpg-sign" => options.signing = Signing::Sign(None),
+            "--no-gpg-sign" => options.signing = Signing::Never,
+            "-n" | "--no-verify" => options.no_verify = true,
+            "--author" | "--date" | "--trailer" => match rest.next() {
+                Some(value) => set_value(&mut options, arg, value),
+                None => return Err(format!("Option '{}' requires a value", arg)),
+            },
+            "--" => paths.extend(rest.by_ref().cloned()),
+            _ => {
+                if let Some(message) = arg.strip_prefix("--message=") {
+                    paragraphs.push(message.to_string());
+                } else if let Some(message) = arg.strip_prefix("-m") {
+                    paragraphs.push(message.to_string());
+                } else if let Some(key) = arg
+                    .strip_prefix("--gpg-sign=")
+                    .or_else(|| arg.strip_prefix("-S"))
+                {
+                    options.signing = Signing::Sign(Some(key.to_string()));
+                } else if let Some((name @ ("--author" | "--date" | "--trailer"), value)) =
+                    arg.split_once('=')
+                {
+                    set_value(&mut options, name, value);
+                } else {
+                    return Err(format!("Unsupported git commit option '{}'", arg));
+                }
+            }
+        }
+    }
+
+    let message = paragraphs
+        .iter()
+        .map(|paragraph| paragraph.trim())
+        .filter(|paragraph| !paragraph.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if message.is_empty() {
+        return Err("The command has no commit message".to_string());
+    }
+
+    options.scope = match (all, paths.is_empty()) {
+        (true, false) => return Err("Paths can't be combined with '-a'".to_string()),
+        (true, true) => ChangeScope::All,
+        (false, false) => ChangeScope::Paths(paths),
+        (false, true) => ChangeScope::Staged,
+    };
+
+    Ok(CommitCommand { message, options })
+}
+
+/// Sets the option `--author`, `--date` or `--trailer` takes.
+fn set_value(options: &mut CommitOptions, name: &str, value: &str) {
+    match name {
+        "--author" => options.author = Some(value.to_string()),
+        "--date" => options.date = Some(value.to_string()),
+        _ => options.trailers.push(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MALICIOUS: &[&str] = &[
+        "feat: add `touch /tmp/pwned` support",
+        "fix: handle $(rm -rf ~) paths",
+        "chore: it's \"quoted\" and ${HOME} and $PATH",
+        "docs: semicolons; pipes | and && chains > file",
+        "refactor: split\n\nBody with `id` and $(whoami) and 'quotes'.",
+    ];
+
+    #[test]
+    fn test_format_then_parse_roundtrips_malicious_messages() {
+        let scopes = [
+            ChangeScope::Staged,
+            ChangeScope::All,
+            ChangeScope::Paths(vec!["src/$(id)".to_string(), "a b.rs".to_string()]),
+        ];
+        for message in MALICIOUS {
+            for review in [false, true] {
+                for scope in &scopes {
+                    let options = CommitOptions {
+                        review,
+                        scope: scope.clone(),
+                        ..Default::default()
+                    };
+                    let command = format_git_command(message, &options);
+                    let parsed = parse_commit_command(&command)
+                        .unwrap_or_else(|e| panic!("failed to parse {:?}: {}", command, e));
+                    assert_eq!(parsed.message, *message);
+                    assert_eq!(parsed.options, options);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_git_command_single_quotes_everything() {
+        assert_eq!(
+            format_git_command("fix: $(whoami) `id`", &CommitOptions::default()),
+            "git commit -m 'fix: $(whoami) `id`'"
+        );
+        assert_eq!(
+            format_git_command(
+                "fix: it's",
+                &CommitOptions {
+                    review: true,
+                    scope: ChangeScope::Paths(vec!["src/api".to_string()]),
+                    ..Default::default()
+                }
+            ),
+            "git commit -e -m 'fix: it'\\''s' -- src/api"
+        );
+    }
+
+    #[test]
+    fn test_commit_flags_roundtrip() {
+        let options = CommitOptions {
+            scope: ChangeScope::All,
+            signoff: true,
+            signing: Signing::Sign(Some("ABC123".to_string())),
+            no_verify: true,
+            author: Some("Ann Lee <ann@example.com>".to_string()),
+            date: Some("2025-02-01 10:00".to_string()),
+            trailers: vec!["Reviewed-by: Bo <bo@example.com>".to_string()],
+            ..Default::default()
+        };
+        let command = format_git_command("fix: a", &options);
+        assert_eq!(
+            command,
+            "git commit -a --signoff --gpg-sign=ABC123 --no-verify '--author=Ann Lee <ann@example.com>' '--date=2025-02-01 10:00' '--trailer=Reviewed-by: Bo <bo@example.com>' -m 'fix: a'"
+        );
+        assert_eq!(parse_commit_command(&command).unwrap().options, options);
+
+        let parsed = parse_commit_command(
+            "git commit -s -S -n --author 'Ann <a@b.c>' --trailer 'Refs: #1' -m x",
+        )
+        .unwrap();
+        assert!(parsed.options.signoff && parsed.options.no_verify);
+        assert_eq!(parsed.options.signing, Signing::Sign(None));
+        assert_eq!(parsed.options.author.as_deref(), Some("Ann <a@b.c>"));
+        assert_eq!(parsed.options.trailers, vec!["Refs: #1"]);
+        let parsed = parse_commit_command("git commit --no-gpg-sign -SKEY -m x").unwrap();
+        assert_eq!(
+            parsed.options.signing,
+            Signing::Sign(Some("KEY".to_string()))
+        );
+        assert!(parse_commit_command("git commit -m x --date").is_err());
+    }
+
+    #[test]
+    fn test_split_args_keeps_quoted_expansions_literal() {
+        assert_eq!(
+            split_args(r#"git commit -m "fix: \$(id) and `id`""#).unwrap(),
+            vec!["git", "commit", "-m", "fix: $(id) and `id`"]
+        );
+        assert_eq!(
+            split_args("git commit -m 'a b'\"c\"d").unwrap(),
+            vec!["git", "commit", "-m", "a bcd"]
+        );
+    }
+
+    #[test]
+    fn test_split_args_rejects_unquoted_shell_syntax() {
+        for line in [
+            "git commit -m x; rm -rf ~",
+            "git commit -m $(whoami)",
+            "git commit -m `whoami`",
+            "git commit -m x | sh",
+            "git commit -m x && curl evil",
+            "git commit -m x > /etc/passwd",
+            "git commit -m ${HOME}",
+        ] {
+            assert!(split_args(line).is_err(), "accepted {:?}", line);
+        }
+        assert!(split_args("git commit -m 'unterminated").is_err());
+    }
+
+    #[test]
+    fn test_parse_commit_command_rejects_other_commands() {
+        assert!(parse_commit_command("rm -rf /").is_err());
+        assert!(parse_commit_command("git push --force").is_err());
+        assert!(parse_commit_command("sh -c 'git commit -m x'").is_err());
+        assert!(parse_commit_command("git commit --amend -m x").is_err());
+        assert!(parse_commit_command("git commit -m x --template=/tmp/t").is_err());
+        assert!(parse_commit_command("git commit").is_err());
+        assert!(parse_commit_command("git commit -m").is_err());
+        assert!(parse_commit_command("git commit -a -m x -- src").is_err());
+    }
+
+    #[test]
+    fn test_parse_commit_command_options() {
+        let parsed =
+            parse_commit_command("git commit --edit -m'feat: a' --message='Body text'").unwrap();
+        assert_eq!(parsed.message, "feat: a\n\nBody text");
+        assert!(parsed.options.review);
+        assert_eq!(parsed.options.scope, ChangeScope::Staged);
+
+        let parsed = parse_commit_command("git commit --all -m x").unwrap();
+        assert_eq!(parsed.options.scope, ChangeScope::All);
+        let parsed = parse_commit_command("git commit -m x -- src -e").unwrap();
+        assert_eq!(
+            parsed.options.scope,
+            ChangeScope::Paths(vec!["src".to_string(), "-e".to_string()])
+        );
+        assert!(!parsed.options.review);
+    }
+}
@@ -3,7 +3,13 @@ use question::{Answer, Question};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::git::CommitSettings;
+use crate::providers::http::NetworkSettings;
+use crate::providers::GenerationSettings;
+use crate::scopes::ScopeSettings;
+use crate::tickets::TicketSettings;
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Config {
@@ -14,6 +20,60 @@ pub struct Config {
     pub models: Models,
     #[serde(default)]
     pub aliases: HashMap<String, String>,
+    #[serde(default)]
+    pub generation: Generation,
+    #[serde(default)]
+    pub network: NetworkSettings,
+    #[serde(default)]
+    pub history: History,
+    #[serde(default)]
+    pub tickets: TicketSettings,
+    #[serde(default)]
+    pub scopes: ScopeSettings,
+    #[serde(default)]
+    pub commit: CommitSettings,
+    /// Co-authors by nickname, as `Name <email>`.
+    #[serde(default)]
+    pub coauthors: HashMap<String, String>,
+}
+
+impl Config {
+    /// The API key configured for a provider.
+    pub fn api_key(&self, provider: &str) -> Option<&String> {
+        match provider {
+            "openai" => self.api_keys.openai.as_ref(),
+            "gemini" => self.api_keys.gemini.as_ref(),
+            "anthropic" => self.api_keys.anthropic.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The default model configured for a provider.
+    pub fn default_model(&self, provider: &str) -> Option<&String> {
+        match provider {
+            "openai" => self.models.openai.as_ref(),
+            "gemini" => self.models.gemini.as_ref(),
+            "anthropic" => self.models.anthropic.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Resolves the generation settings for a provider and the model (or alias)
+    /// the user selected. Alias settings take precedence over provider settings.
+    pub fn generation_settings(&self, provider: &str, model_or_alias: &str) -> GenerationSettings {
+        let provider_settings = match provider {
+            "openai" => self.generation.openai.clone(),
+            "gemini" => self.generation.gemini.clone(),
+            "anthropic" => self.generation.anthropic.clone(),
+            _ => None,
+        }
+        .unwrap_or_default();
+
+        match self.generation.aliases.get(model_or_alias) {
+            Some(alias_settings) => provider_settings.merge(alias_settings),
+            None => provider_settings,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -30,6 +90,36 @@ pub struct Models {
     pub anthropic: Option<String>,
 }
 
+/// Per-provider and per-alias generation parameters.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Generation {
+    pub openai: Option<GenerationSettings>,
+    pub gemini: Option<GenerationSettings>,
+    pub anthropic: Option<GenerationSettings>,
+    #[serde(default)]
+    pub aliases: HashMap<String, GenerationSettings>,
+}
+
+/// Recent commit subjects sent to the model as style examples.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct History {
+    /// How many recent commit subjects to include; 0 disables them.
+    pub examples: usize,
+    /// Prefer commits that touched the changed files, falling back to the
+    /// whole history when none did.
+    pub filter_paths: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            examples: 10,
+            filter_paths: false,
+        }
+    }
+}
+
 impl Default for Models {
     fn default() -> Self {
         Self {
@@ -46,11 +136,21 @@ fn get_config_path() -> Result<PathBuf, String> {
     Ok(config_dir.config_dir().to_path_buf())
 }
 
+/// Path of the configuration file.
+pub fn config_file_path() -> Result<PathBuf, String> {
+    Ok(get_config_path()?.join("config.toml"))
+}
+
+/// Parses the contents of a configuration file.
+pub fn parse_config(content: &str) -> Result<Config, String> {
+    toml::from_str(content).map_err(|e| format!("Failed to parse config file: {}", e))
+}
+
 pub fn load_config() -> Result<Config, String> {
-    let config_path = get_config_path()?.join("config.toml");
+    let config_path = config_file_path()?;
     if !config_path.exists() {
-        println!("{}", "Configuration file not found.".yellow());
-        println!(
+        eprintln!("{}", "Configuration file not found.".yellow());
+        eprintln!(
             "Please run '{}' to get started.",
             "commitcraft setup".bold().cyan()
         );
@@ -60,7 +160,32 @@ pub fn load_config() -> Result<Config, String> {
     let content = fs::read_to_string(&config_path)
         .map_err(|e| format!("Failed to read config file: {}", e))?;
 
-    toml::from_str(&content).map_err(|e| format!("Failed to parse config file: {}", e))
+    parse_config(&content)
+}
+
+/// The settings file a repository can check in at its root.
+pub const REPO_CONFIG_FILE: &str = ".commitcraft.toml";
+
+/// Settings a repository sets for everyone committing to it.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct RepoConfig {
+    #[serde(default)]
+    pub commit: CommitSettings,
+    /// The team's co-author roster, which the user's own entries extend.
+    #[serde(default)]
+    pub coauthors: HashMap<String, String>,
+}
+
+/// Reads the [`REPO_CONFIG_FILE`] of the repository at `root`. Without one,
+/// the defaults apply.
+pub fn load_repo_config(root: &Path) -> Result<RepoConfig, String> {
+    let path = root.join(REPO_CONFIG_FILE);
+    if !path.exists() {
+        return Ok(RepoConfig::default());
+    }
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", REPO_CONFIG_FILE, e))?;
+    toml::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", REPO_CONFIG_FILE, e))
 }
 
 fn show_setup_welcome() {
@@ -172,7 +297,7 @@ pub fn run_setup() -> Result<(), String> {
 }
 
 fn save_config(config: &Config) -> Result<(), String> {
-    let config_path = get_config_path()?.join("config.toml");
+    let config_path = config_file_path()?;
     let config_dir = config_path.parent().unwrap();
 
     fs::create_dir_all(config_dir)
@@ -214,8 +339,10 @@ mod tests {
 
     #[test]
     fn test_config_serialization() {
-        let mut config = Config::default();
-        config.default_provider = Some("openai".to_string());
+        let mut config = Config {
+            default_provider: Some("openai".to_string()),
+            ..Default::default()
+        };
         config.api_keys.openai = Some("sk-test".to_string());
         config.models.openai = Some("gpt-4".to_string());
         config
@@ -232,6 +359,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_generation_settings_resolution() {
+        let toml = r#"
+            [generation.openai]
+            temperature = 0.4
+            max_tokens = 500
+
+            [generation.aliases.creative]
+            temperature = 0.9
+            stop = ["---"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+
+        let provider = config.generation_settings("openai", "gpt-4o");
+        assert_eq!(provider.temperature, Some(0.4));
+        assert_eq!(provider.max_tokens, Some(500));
+
+        let alias = config.generation_settings("openai", "creative");
+        assert_eq!(alias.temperature, Some(0.9));
+        assert_eq!(alias.max_tokens, Some(500));
+        assert_eq!(alias.stop, Some(vec!["---".to_string()]));
+
+        let other = config.generation_settings("gemini", "gemini-1.5-flash-latest");
+        assert_eq!(other, GenerationSettings::default());
+    }
+
+    #[test]
+    fn test_provider_accessors() {
+        let mut config = Config::default();
+        config.api_keys.gemini = Some("key".to_string());
+        assert_eq!(config.api_key("gemini"), Some(&"key".to_string()));
+        assert!(config.api_key("openai").is_none());
+        assert!(config.api_key("unknown").is_none());
+        assert_eq!(
+            config.default_model("anthropic"),
+            Some(&"claude-3-5-haiku-20241022".to_string())
+        );
+    }
+
+    #[test]
+    fn test_network_settings_parse() {
+        let toml = r#"
+            [network]
+            connect_timeout_secs = 3
+            timeout_secs = 45
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.network.connect_timeout_secs, Some(3));
+        assert_eq!(config.network.timeout_secs, Some(45));
+        assert_eq!(Config::default().network, NetworkSettings::default());
+    }
+
+    #[test]
+    fn test_history_settings_parse() {
+        assert_eq!(Config::default().history, History::default());
+        let config: Config = toml::from_str("[history]\nfilter_paths = true\n").unwrap();
+        assert_eq!(config.history.examples, 10);
+        assert!(config.history.filter_paths);
+        let config: Config = toml::from_str("[history]\nexamples = 0\n").unwrap();
+        assert_eq!(config.history.examples, 0);
+    }
+
+    #[test]
+    fn test_ticket_settings_parse() {
+        use crate::tickets::TicketPlacement;
+
+        assert!(Config::default().tickets.patterns.is_empty());
+        let toml = r#"
+            [tickets]
+            patterns = ['([A-Z]+-\d+)']
+            placement = "title"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.tickets.patterns, vec![r"([A-Z]+-\d+)".to_string()]);
+        assert_eq!(config.tickets.placement, TicketPlacement::Title);
+        assert_eq!(config.tickets.footer_token, "Refs");
+    }
+
+    #[test]
+    fn test_commit_settings_parse() {
+        use crate::git::SignSetting;
+
+        assert_eq!(Config::default().commit, CommitSettings::default());
+        let toml = r#"
+            [commit]
+            signoff = true
+            sign = "ABC123"
+            trailers = ["Reviewed-by: Bo <bo@example.com>"]
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.commit.signoff, Some(true));
+        assert_eq!(
+            config.commit.sign,
+            Some(SignSetting::Key("ABC123".to_string()))
+        );
+        assert_eq!(config.commit.trailers.len(), 1);
+
+        let repo: RepoConfig = toml::from_str(
+            "[commit]
+sign = false
+",
+        )
+        .unwrap();
+        assert_eq!(repo.commit.sign, Some(SignSetting::Enabled(false)));
+    }
+
+    #[test]
+    fn test_coauthors_parse() {
+        let toml = r#"
+            [coauthors]
+            alice = "Alice Smith <alice@example.com>"
+            bob = "Bob Jones <bob@example.com>"
+        "#;
+        let config: Config = toml::from_str(toml).unwrap();
+        assert_eq!(config.coauthors.len(), 2);
+        assert_eq!(config.coauthors["alice"], "Alice Smith <alice@example.com>");
+        let repo: RepoConfig = toml::from_str(toml).unwrap();
+        assert_eq!(repo.coauthors.len(), 2);
+        assert!(Config::default().coauthors.is_empty());
+    }
+
     #[test]
     fn test_api_keys_default() {
         let keys = ApiKeys::default();
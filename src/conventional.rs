@@ -0,0 +1,273 @@
+use serde::Serialize;
+
+/// A `token: value` (or `token #value`) trailer at the end of a commit message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Footer {
+    pub token: String,
+    pub value: String,
+}
+
+impl Footer {
+    pub fn is_breaking_change(&self) -> bool {
+        self.token == "BREAKING CHANGE" || self.token == "BREAKING-CHANGE"
+    }
+}
+
+/// A commit message split into its Conventional Commits parts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConventionalCommit {
+    #[serde(rename = "type")]
+    pub commit_type: String,
+    pub scope: Option<String>,
+    /// Set by a `!` after the type/scope or a `BREAKING CHANGE` footer.
+    pub breaking: bool,
+    pub subject: String,
+    pub body: Option<String>,
+    pub footers: Vec<Footer>,
+}
+
+impl ConventionalCommit {
+    /// The breaking change description from the footer, or the subject when
+    /// the change is only marked with `!`.
+    pub fn breaking_description(&self) -> Option<&str> {
+        if !self.breaking {
+            return None;
+        }
+        self.footers
+            .iter()
+            .find(|footer| footer.is_breaking_change())
+            .map(|footer| footer.value.as_str())
+            .or(Some(self.subject.as_str()))
+    }
+}
+
+/// Parses a `type(scope)!: subject` header.
+pub fn parse_header(header: &str) -> Result<(String, Option<String>, bool, String), String> {
+    let (prefix, subject) = header
+        .split_once(':')
+        .ok_or("Commit title must follow format: type(scope): description")?;
+
+    let (prefix, breaking) = match prefix.strip_suffix('!') {
+        Some(prefix) => (prefix, true),
+        None => (prefix, false),
+    };
+
+    let (commit_type, scope) = match prefix.split_once('(') {
+        Some((commit_type, rest)) => {
+            let scope = rest
+                .strip_suffix(')')
+                .ok_or("Commit scope must be closed with ')'")?;
+            if scope.trim().is_empty() {
+                return Err("Commit scope cannot be empty".to_string());
+            }
+            (commit_type, Some(scope.trim().to_string()))
+        }
+        None => (prefix, None),
+    };
+
+    if commit_type.is_empty()
+        || !commit_type
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid commit type '{}'", commit_type));
+    }
+
+    let subject = subject.trim();
+    if subject.is_empty() {
+        return Err("Commit description after colon cannot be empty".to_string());
+    }
+
+    Ok((
+        commit_type.to_lowercase(),
+        scope,
+        breaking,
+        subject.to_string(),
+    ))
+}
+
+/// Parses a footer line such as `Refs: #12`, `Closes #12` or
+/// `BREAKING CHANGE: drops v1`.
+pub fn parse_footer(line: &str) -> Option<Footer> {
+    let (token, value) = if let Some(value) = line.strip_prefix("BREAKING CHANGE: ") {
+        ("BREAKING CHANGE", value)
+    } else if let Some((token, value)) = line.split_once(": ") {
+        (token, value)
+    } else if let Some((token, value)) = line.split_once(" #") {
+        return is_footer_token(token).then(|| Footer {
+            token: token.to_string(),
+            value: format!("#{}", value),
+        });
+    } else {
+        return None;
+    };
+
+    (token == "BREAKING CHANGE" || is_footer_token(token)).then(|| Footer {
+        token: token.to_string(),
+        value: value.trim().to_string(),
+    })
+}
+
+fn is_footer_token(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Splits a message body into the body text and its trailing footers.
+///
+/// The last paragraph is treated as footers when its first line is a footer;
+/// following lines that are not footers continue the previous value.
+pub fn split_footers(body: &str) -> (Option<String>, Vec<Footer>) {
+    let body = body.trim();
+    let (text, last) = match body.rsplit_once("\n\n") {
+        Some((text, last)) => (text.trim(), last),
+        None => ("", body),
+    };
+
+    let mut lines = last.lines();
+    let Some(first) = lines.next().and_then(parse_footer) else {
+        return ((!body.is_empty()).then(|| body.to_string()), Vec::new());
+    };
+
+    let mut footers = vec![first];
+    for line in lines {
+        match parse_footer(line) {
+            Some(footer) => footers.push(footer),
+            None => {
+                if let Some(previous) = footers.last_mut() {
+                    previous.value.push('\n');
+                    previous.value.push_str(line.trim());
+                }
+            }
+        }
+    }
+
+    ((!text.is_empty()).then(|| text.to_string()), footers)
+}
+
+/// Adds a `token: value` footer line to a message body: to its footer
+/// paragraph if it has one, otherwise as a new paragraph.
+pub fn append_footer(body: &str, footer: &str) -> String {
+    let body = body.trim_end();
+    let (_, footers) = split_footers(body);
+    if body.is_empty() {
+        footer.to_string()
+    } else if footers.is_empty() {
+        format!("{}\n\n{}", body, footer)
+    } else {
+        format!("{}\n{}", body, footer)
+    }
+}
+
+/// Parses a full commit message.
+pub fn parse(message: &str) -> Result<ConventionalCommit, String> {
+    let message = message.trim();
+    let (header, body) = match message.split_once('\n') {
+        Some((header, body)) => (header.trim(), body),
+        None => (message, ""),
+    };
+    let (commit_type, scope, bang, subject) = parse_header(header)?;
+    let (body, footers) = split_footers(body);
+    let breaking = bang || footers.iter().any(Footer::is_breaking_change);
+
+    Ok(ConventionalCommit {
+        commit_type,
+        scope,
+        breaking,
+        subject,
+        body,
+        footers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("feat(api)!: drop v1").unwrap(),
+            (
+                "feat".to_string(),
+                Some("api".to_string()),
+                true,
+                "drop v1".to_string()
+            )
+        );
+        assert_eq!(
+            parse_header("fix: typo").unwrap(),
+            ("fix".to_string(), None, false, "typo".to_string())
+        );
+        assert!(parse_header("no colon here").is_err());
+        assert!(parse_header("feat(api: x").is_err());
+        assert!(parse_header("feat(): x").is_err());
+        assert!(parse_header("fe at: x").is_err());
+        assert!(parse_header("feat:  ").is_err());
+    }
+
+    #[test]
+    fn test_parse_footer() {
+        assert_eq!(
+            parse_footer("Refs: PROJ-12"),
+            Some(Footer {
+                token: "Refs".to_string(),
+                value: "PROJ-12".to_string()
+            })
+        );
+        assert_eq!(parse_footer("Closes #34").unwrap().value, "#34");
+        assert!(parse_footer("BREAKING CHANGE: drops v1")
+            .unwrap()
+            .is_breaking_change());
+        assert!(parse_footer("Some sentence: with a colon").is_none());
+        assert!(parse_footer("Just text").is_none());
+    }
+
+    #[test]
+    fn test_parse_full_message() {
+        let message = "feat(auth): add OAuth\n\nAdds the flow.\n\nMore detail.\n\nRefs: #12\nBREAKING CHANGE: sessions are\n  invalidated\nCo-authored-by: A <a@example.com>";
+        let commit = parse(message).unwrap();
+        assert_eq!(commit.commit_type, "feat");
+        assert_eq!(commit.scope.as_deref(), Some("auth"));
+        assert!(commit.breaking);
+        assert_eq!(
+            commit.body.as_deref(),
+            Some("Adds the flow.\n\nMore detail.")
+        );
+        assert_eq!(commit.footers.len(), 3);
+        assert_eq!(commit.footers[1].value, "sessions are\ninvalidated");
+        assert_eq!(
+            commit.breaking_description(),
+            Some("sessions are\ninvalidated")
+        );
+    }
+
+    #[test]
+    fn test_parse_message_without_footers() {
+        let commit = parse("fix: typo\n\nFixes a typo in the docs.").unwrap();
+        assert_eq!(commit.body.as_deref(), Some("Fixes a typo in the docs."));
+        assert!(commit.footers.is_empty());
+        assert!(!commit.breaking);
+        assert_eq!(commit.breaking_description(), None);
+
+        let commit = parse("refactor!: rename module").unwrap();
+        assert_eq!(commit.body, None);
+        assert_eq!(commit.breaking_description(), Some("rename module"));
+    }
+
+    #[test]
+    fn test_parse_footers_only_body() {
+        let commit = parse("chore: bump\n\nRefs: #1").unwrap();
+        assert_eq!(commit.body, None);
+        assert_eq!(commit.footers.len(), 1);
+    }
+
+    #[test]
+    fn test_append_footer() {
+        assert_eq!(append_footer("", "Refs: #1"), "Refs: #1");
+        assert_eq!(append_footer("Body.\n", "Refs: #1"), "Body.\n\nRefs: #1");
+        assert_eq!(
+            append_footer("Body.\n\nCloses #4", "Refs: #1"),
+            "Body.\n\nCloses #4\nRefs: #1"
+        );
+    }
+}
@@ -0,0 +1,117 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Reads a diff from `reader`, e.g. stdin. `source` names it in errors.
+pub fn read_diff(mut reader: impl Read, source: &str) -> Result<String, String> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read diff from {}: {}", source, e))?;
+    check_diff(String::from_utf8_lossy(&bytes).into_owned(), source)
+}
+
+/// Reads a diff from a patch file.
+pub fn read_diff_file(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read diff file '{}': {}", path.display(), e))?;
+    check_diff(
+        String::from_utf8_lossy(&bytes).into_owned(),
+        &path.display().to_string(),
+    )
+}
+
+fn check_diff(diff: String, source: &str) -> Result<String, String> {
+    let diff = diff.trim();
+    if diff.is_empty() {
+        return Err(format!("The diff from {} is empty.", source));
+    }
+    Ok(diff.to_string())
+}
+
+/// Names of the files changed by a unified diff, in order of appearance.
+///
+/// Uses the `+++` target path, or the `---` source path for deleted files.
+pub fn changed_files(diff: &str) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    let mut source = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("--- ") {
+            source = strip_diff_prefix(path);
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if let Some(file) = strip_diff_prefix(path).or(source.take()) {
+                if !files.contains(&file) {
+                    files.push(file);
+                }
+            }
+        }
+    }
+    files
+}
+
+/// Removes the `a/`/`b/` prefix and any trailing timestamp from a diff path;
+/// `/dev/null` yields `None`.
+pub(crate) fn strip_diff_prefix(path: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1 +1,2 @@
+ pub mod cli;
++pub mod diff;
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+diff --git a/new.txt b/new.txt
+new file mode 100644
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1 @@
++hello
+";
+
+    #[test]
+    fn test_changed_files() {
+        assert_eq!(
+            changed_files(DIFF),
+            vec!["src/lib.rs", "old.txt", "new.txt"]
+        );
+    }
+
+    #[test]
+    fn test_changed_files_plain_diff() {
+        let diff = "--- file.c\t2024-01-01 00:00:00\n+++ file.c\t2024-01-02 00:00:00\n@@ -1 +1 @@\n-a\n+b\n";
+        assert_eq!(changed_files(diff), vec!["file.c"]);
+    }
+
+    #[test]
+    fn test_read_diff() {
+        assert_eq!(read_diff(DIFF.as_bytes(), "stdin").unwrap(), DIFF.trim());
+        let error = read_diff(" \n".as_bytes(), "stdin").unwrap_err();
+        assert_eq!(error, "The diff from stdin is empty.");
+    }
+
+    #[test]
+    fn test_read_diff_file_missing() {
+        let error = read_diff_file(Path::new("/nonexistent/patch.diff")).unwrap_err();
+        assert!(error.starts_with("Failed to read diff file '/nonexistent/patch.diff'"));
+    }
+}
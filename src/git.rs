@@ -1,116 +1,722 @@
 use colored::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str;
 
-/// Checks if the current directory is a Git repository.
-pub fn is_git_repository() -> bool {
-    Command::new("git")
-        .arg("rev-parse")
-        .arg("--is-inside-work-tree")
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()
-        .is_ok_and(|status| status.success())
+use crate::backend;
+use crate::conventional;
+use crate::submodule::{self, Bump, BumpLog};
+
+/// The hash of git's empty tree, used as the diff base before the first commit.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Which changes a commit message is generated from and a commit records.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ChangeScope {
+    /// The staged changes.
+    #[default]
+    Staged,
+    /// Every modification to tracked files, like `git commit -a`.
+    All,
+    /// The current contents of the given paths, like `git commit -- <paths>`.
+    Paths(Vec<String>),
 }
 
-/// Gets the diff of staged files.
-pub fn get_staged_diff() -> Result<String, String> {
-    let output = Command::new("git")
-        .arg("diff")
-        .arg("--staged")
-        .output()
-        .map_err(|e| format!("Failed to execute 'git diff --staged': {}", e))?;
+/// Whether commits are signed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Signing {
+    /// As git is configured, e.g. with `commit.gpgSign`.
+    #[default]
+    Configured,
+    /// `-S`, with a key or git's configured one. Whether GPG or SSH signs
+    /// is up to git's `gpg.format`.
+    Sign(Option<String>),
+    /// `--no-gpg-sign`.
+    Never,
+}
 
-    if !output.status.success() {
-        let stderr = str::from_utf8(&output.stderr).unwrap_or("Unknown git error");
-        return Err(format!("'git diff --staged' failed: {}", stderr));
+/// Options for the commits commitcraft creates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommitOptions {
+    /// Open the editor before committing (`git commit -e`).
+    pub review: bool,
+    pub scope: ChangeScope,
+    /// Add a `Signed-off-by` trailer (`--signoff`).
+    pub signoff: bool,
+    pub signing: Signing,
+    /// Skip the pre-commit and commit-msg hooks (`--no-verify`).
+    pub no_verify: bool,
+    /// `--author`, e.g. `Name <email>`.
+    pub author: Option<String>,
+    /// `--date`, in any format git accepts.
+    pub date: Option<String>,
+    /// Trailers git adds to the message (`--trailer`), e.g. `Reviewed-by: Name <email>`.
+    pub trailers: Vec<String>,
+}
+
+impl CommitOptions {
+    /// The `git commit` flags for everything but the message and the paths.
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if self.review {
+            flags.push("-e".to_string());
+        }
+        if self.scope == ChangeScope::All {
+            flags.push("-a".to_string());
+        }
+        if self.signoff {
+            flags.push("--signoff".to_string());
+        }
+        match &self.signing {
+            Signing::Configured => {}
+            Signing::Sign(None) => flags.push("-S".to_string()),
+            Signing::Sign(Some(key)) => flags.push(format!("--gpg-sign={}", key)),
+            Signing::Never => flags.push("--no-gpg-sign".to_string()),
+        }
+        if self.no_verify {
+            flags.push("--no-verify".to_string());
+        }
+        if let Some(author) = &self.author {
+            flags.push(format!("--author={}", author));
+        }
+        if let Some(date) = &self.date {
+            flags.push(format!("--date={}", date));
+        }
+        for trailer in &self.trailers {
+            flags.push(format!("--trailer={}", trailer));
+        }
+        flags
     }
 
-    let diff = str::from_utf8(&output.stdout)
-        .map_err(|e| format!("Failed to parse git diff output: {}", e))?
-        .trim();
+    /// Takes the trailers and the `Signed-off-by` line of `signer` out of the
+    /// flags, to be added to the message itself so the message reviewed is
+    /// the one committed. Without a `signer`, `--signoff` is left to git.
+    pub fn take_trailers(&mut self, signer: Option<&str>) -> Trailers {
+        // Git signs off before adding the other trailers
+        let mut lines = Vec::new();
+        if let (true, Some(signer)) = (self.signoff, signer) {
+            lines.push(format!("Signed-off-by: {}", signer));
+            self.signoff = false;
+        }
+        lines.append(&mut self.trailers);
+        Trailers { lines }
+    }
+}
 
-    if diff.is_empty() {
-        return Err("There are no staged files to commit. Try running 'git add'.".to_string());
+/// Trailers added to every commit message, e.g. `Reviewed-by: Name <email>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trailers {
+    pub lines: Vec<String>,
+}
+
+impl Trailers {
+    /// Adds each trailer `description` doesn't have yet.
+    pub fn apply(&self, description: &mut String) {
+        for line in &self.lines {
+            let (_, footers) = conventional::split_footers(description);
+            let present = line.split_once(':').is_some_and(|(token, value)| {
+                footers.iter().any(|footer| {
+                    footer.token.eq_ignore_ascii_case(token.trim())
+                        && footer.value.trim() == value.trim()
+                })
+            });
+            if !present {
+                *description = conventional::append_footer(description, line);
+            }
+        }
     }
+}
 
-    Ok(diff.to_string())
+/// `sign = true`, `sign = false` or `sign = "<key>"` in the config.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum SignSetting {
+    Enabled(bool),
+    Key(String),
 }
 
-/// Gets the names of staged files.
-pub fn get_staged_files() -> Result<Vec<String>, String> {
+/// Commit option defaults from the config file, the repository's
+/// `.commitcraft.toml` or the command line, layered with [`merge`](Self::merge).
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct CommitSettings {
+    pub signoff: Option<bool>,
+    pub sign: Option<SignSetting>,
+    pub no_verify: Option<bool>,
+    pub author: Option<String>,
+    pub date: Option<String>,
+    /// Trailers added to every commit, after those of earlier layers.
+    pub trailers: Vec<String>,
+}
+
+impl CommitSettings {
+    /// Returns a copy of `self` with every field set in `other` taking
+    /// precedence. Trailers of both are kept, without duplicates.
+    pub fn merge(&self, other: &CommitSettings) -> CommitSettings {
+        let mut trailers = self.trailers.clone();
+        for trailer in &other.trailers {
+            if !trailers.contains(trailer) {
+                trailers.push(trailer.clone());
+            }
+        }
+        CommitSettings {
+            signoff: other.signoff.or(self.signoff),
+            sign: other.sign.clone().or_else(|| self.sign.clone()),
+            no_verify: other.no_verify.or(self.no_verify),
+            author: other.author.clone().or_else(|| self.author.clone()),
+            date: other.date.clone().or_else(|| self.date.clone()),
+            trailers,
+        }
+    }
+
+    /// Checks that every trailer is a `Token: value` line.
+    pub fn validate(&self) -> Result<(), String> {
+        for trailer in &self.trailers {
+            let valid = trailer.split_once(':').is_some_and(|(token, value)| {
+                !token.is_empty()
+                    && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    && !value.trim().is_empty()
+            });
+            if !valid {
+                return Err(format!(
+                    "Invalid trailer '{}'; expected 'Token: value'",
+                    trailer
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Commit options with these settings, for changes in `scope`.
+    pub fn options(&self, review: bool, scope: ChangeScope) -> CommitOptions {
+        CommitOptions {
+            review,
+            scope,
+            signoff: self.signoff.unwrap_or(false),
+            signing: match &self.sign {
+                None => Signing::Configured,
+                Some(SignSetting::Enabled(true)) => Signing::Sign(None),
+                Some(SignSetting::Enabled(false)) => Signing::Never,
+                Some(SignSetting::Key(key)) => Signing::Sign(Some(key.clone())),
+            },
+            no_verify: self.no_verify.unwrap_or(false),
+            author: self.author.clone(),
+            date: self.date.clone(),
+            trailers: self.trailers.clone(),
+        }
+    }
+}
+
+/// Runs git with `args` and returns its stdout.
+pub(crate) fn git_output(args: &[&str]) -> Result<String, String> {
+    // Paths and contents that aren't UTF-8 are kept readable
+    git_output_bytes(args).map(|stdout| String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Runs git with `args` and returns its stdout as is, for output that is
+/// fed back to git.
+fn git_output_bytes(args: &[&str]) -> Result<Vec<u8>, String> {
+    let command = format!("git {}", args.join(" "));
     let output = Command::new("git")
-        .arg("diff")
-        .arg("--staged")
-        .arg("--name-only")
+        .args(args)
         .output()
-        .map_err(|e| format!("Failed to execute 'git diff --staged --name-only': {}", e))?;
+        .map_err(|e| format!("Failed to execute '{}': {}", command, e))?;
 
     if !output.status.success() {
-        let stderr = str::from_utf8(&output.stderr).unwrap_or("Unknown git error");
-        return Err(format!(
-            "'git diff --staged --name-only' failed: {}",
-            stderr
-        ));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("'{}' failed: {}", command, stderr.trim()));
     }
 
-    let files = str::from_utf8(&output.stdout)
-        .map_err(|e| format!("Failed to parse git diff output: {}", e))?
+    Ok(output.stdout)
+}
+
+/// The commit to diff tracked changes against: `HEAD`, or the empty tree in
+/// a repository without commits.
+pub(crate) fn diff_base() -> &'static str {
+    let has_head = Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if has_head {
+        "HEAD"
+    } else {
+        EMPTY_TREE
+    }
+}
+
+pub(crate) fn lines(output: &str) -> Vec<String> {
+    output
         .lines()
         .filter(|line| !line.trim().is_empty())
         .map(|line| line.to_string())
-        .collect();
+        .collect()
+}
 
-    Ok(files)
+/// Checks if the current directory is a Git repository.
+pub fn is_git_repository() -> bool {
+    backend::call(|backend| backend.is_repository()).unwrap_or(false)
 }
 
-/// Gets basic repository information.
-pub fn get_repo_info() -> Result<(String, String), String> {
-    // Get current branch
-    let branch_output = Command::new("git")
-        .arg("rev-parse")
-        .arg("--abbrev-ref")
-        .arg("HEAD")
+/// Checks that there's a working tree to commit from, explaining what the
+/// current directory is instead when there isn't.
+pub fn require_work_tree() -> Result<(), String> {
+    if is_git_repository() {
+        return Ok(());
+    }
+    let output = match git_output(&["rev-parse", "--is-bare-repository", "--absolute-git-dir"]) {
+        Ok(output) => output,
+        Err(_) => return Err("Not inside a git repository.".to_string()),
+    };
+    let flags = lines(&output);
+    match flags.as_slice() {
+        [bare, git_dir] if bare == "true" => Err(format!(
+            "{} is a bare repository, which has no working tree to commit from. \
+             Run commitcraft in a clone or a worktree of it, or set GIT_WORK_TREE.",
+            git_dir
+        )),
+        _ => Err("Inside the git directory. Run commitcraft from the working tree.".to_string()),
+    }
+}
+
+/// Gets the installed git version, e.g. `git version 2.43.0`.
+pub fn git_version() -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("--version")
         .output()
-        .map_err(|e| format!("Failed to get current branch: {}", e))?;
+        .map_err(|e| format!("Failed to execute 'git --version': {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("'git --version' failed: {}", stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Gets the diff of staged files.
+pub fn get_staged_diff() -> Result<String, String> {
+    let diff = backend::call(|backend| backend.diff(&ChangeScope::Staged))?;
+    if diff.trim().is_empty() {
+        return Err(
+            "There are no staged files to commit. Try running 'git add', \
+             use 'commitcraft --all' to include all tracked changes, \
+             or 'commitcraft -- <path>...' to commit specific paths."
+                .to_string(),
+        );
+    }
+    Ok(expand_submodules(diff.trim()))
+}
+
+/// Gets the diff of every modification to tracked files, staged or not.
+pub fn get_tracked_diff() -> Result<String, String> {
+    let diff = backend::call(|backend| backend.diff(&ChangeScope::All))?;
+    if diff.trim().is_empty() {
+        return Err("There are no changes to tracked files to commit.".to_string());
+    }
+    Ok(expand_submodules(diff.trim()))
+}
+
+/// Gets the diff of the current contents of `paths`, staged or not.
+pub fn get_paths_diff(paths: &[String]) -> Result<String, String> {
+    let scope = ChangeScope::Paths(paths.to_vec());
+    let diff = backend::call(|backend| backend.diff(&scope))?;
+    if diff.trim().is_empty() {
+        return Err(format!(
+            "There are no changes to commit in {}.",
+            paths.join(", ")
+        ));
+    }
+    Ok(expand_submodules(diff.trim()))
+}
 
-    let branch = str::from_utf8(&branch_output.stdout)
-        .map_err(|e| format!("Failed to parse branch name: {}", e))?
-        .trim()
-        .to_string();
+/// Variables pointing git at the superproject, which must not reach git
+/// commands run inside a submodule.
+const REPOSITORY_VARS: [&str; 6] = [
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_INDEX_FILE",
+    "GIT_COMMON_DIR",
+    "GIT_OBJECT_DIRECTORY",
+    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+];
 
-    // Get repository root directory name
-    let root_output = Command::new("git")
-        .arg("rev-parse")
-        .arg("--show-toplevel")
+/// Reads the commits of `bump` from the submodule's checkout under `root`.
+/// `None` when it isn't checked out or doesn't have them.
+fn submodule_log(root: &Path, bump: &Bump) -> Option<BumpLog> {
+    let (old, new) = (bump.old.as_deref()?, bump.new.as_deref()?);
+    let dir = root.join(&bump.path);
+    // An uninitialized submodule is an empty directory, where git would
+    // find the superproject instead
+    if !dir.join(".git").exists() {
+        return None;
+    }
+    let mut command = Command::new("git");
+    for var in REPOSITORY_VARS {
+        command.env_remove(var);
+    }
+    let range = format!("{}...{}", old, new);
+    let output = command
+        .arg("-C")
+        .arg(&dir)
+        .args(["log", "--left-right", "--format=%m%s", &range, "--"])
         .output()
-        .map_err(|e| format!("Failed to get repository root: {}", e))?;
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let mut log = BumpLog::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(subject) = line.strip_prefix('>') {
+            log.added.push(subject.to_string());
+        } else if let Some(subject) = line.strip_prefix('<') {
+            log.removed.push(subject.to_string());
+        }
+    }
+    Some(log)
+}
 
-    let root_path = str::from_utf8(&root_output.stdout)
-        .map_err(|e| format!("Failed to parse repository root: {}", e))?
-        .trim();
+/// Follows each submodule bump in `diff` with the submodule's commits
+/// between the recorded hashes, which on their own tell the model nothing.
+fn expand_submodules(diff: &str) -> String {
+    if !diff.contains("Subproject commit ") {
+        return diff.to_string();
+    }
+    match repo_root() {
+        Ok(root) => submodule::expand(diff, |bump| submodule_log(&root, bump)),
+        Err(_) => diff.to_string(),
+    }
+}
 
-    let repo_name = std::path::Path::new(root_path)
-        .file_name()
-        .and_then(|name| name.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+/// Gets the diff for the given scope.
+pub fn get_diff(scope: &ChangeScope) -> Result<String, String> {
+    match scope {
+        ChangeScope::Staged => get_staged_diff(),
+        ChangeScope::All => get_tracked_diff(),
+        ChangeScope::Paths(paths) => get_paths_diff(paths),
+    }
+}
 
+/// Gets the names of staged files.
+pub fn get_staged_files() -> Result<Vec<String>, String> {
+    get_changed_files(&ChangeScope::Staged)
+}
+
+/// Gets the names of tracked files with modifications, staged or not.
+pub fn get_tracked_files() -> Result<Vec<String>, String> {
+    get_changed_files(&ChangeScope::All)
+}
+
+/// Gets the names of modified files within `paths`, staged or not.
+pub fn get_paths_files(paths: &[String]) -> Result<Vec<String>, String> {
+    get_changed_files(&ChangeScope::Paths(paths.to_vec()))
+}
+
+/// Gets the names of the files changed in the given scope.
+pub fn get_changed_files(scope: &ChangeScope) -> Result<Vec<String>, String> {
+    backend::call(|backend| backend.changed_files(scope))
+}
+
+/// Gets the subjects of the last `count` non-merge commits, newest first.
+/// With `paths`, only commits that touched them are included.
+pub fn get_recent_subjects(count: usize, paths: &[String]) -> Result<Vec<String>, String> {
+    backend::call(|backend| backend.recent_subjects(count, paths))
+}
+
+/// A commit read from the log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoggedCommit {
+    /// The abbreviated hash.
+    pub hash: String,
+    pub message: String,
+}
+
+/// Gets the non-merge commits in `from..to`, newest first. Without `from`,
+/// every commit reachable from `to` is included.
+pub fn get_log(from: Option<&str>, to: &str) -> Result<Vec<LoggedCommit>, String> {
+    backend::call(|backend| backend.log(from, to))
+}
+
+/// Gets the most recent tag reachable from `rev`, including a tag on `rev`
+/// itself.
+pub fn latest_tag(rev: &str) -> Option<String> {
+    git_output(&["describe", "--tags", "--abbrev=0", rev])
+        .ok()
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+}
+
+/// Gets the diff of `head` against its merge base with `base`, i.e. the
+/// changes merging `head` into `base` brings in.
+pub fn get_branch_diff(base: &str, head: &str) -> Result<String, String> {
+    let range = format!("{}...{}", base, head);
+    git_output(&["diff", "--no-color", "--no-ext-diff", &range, "--"])
+        .map(|diff| expand_submodules(&diff))
+}
+
+/// Resolves a path inside the git directory, e.g. `SQUASH_MSG`.
+pub fn git_path(name: &str) -> Result<PathBuf, String> {
+    git_output(&["rev-parse", "--git-path", name]).map(|path| PathBuf::from(path.trim()))
+}
+
+/// Resolves a path inside the git directory shared by all worktrees.
+pub fn common_git_path(name: &str) -> Result<PathBuf, String> {
+    backend::call(|backend| backend.common_dir()).map(|dir| dir.join(name))
+}
+
+/// Gets the committer's configured `user.email`.
+pub fn user_email() -> Option<String> {
+    git_output(&["config", "user.email"])
+        .ok()
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty())
+}
+
+/// The committer as `Name <email>`, as git signs off commits.
+pub fn committer() -> Option<String> {
+    let ident = git_output(&["var", "GIT_COMMITTER_IDENT"]).ok()?;
+    // The identity is followed by a timestamp and time zone
+    ident
+        .rsplit_once('>')
+        .map(|(identity, _)| format!("{}>", identity.trim()))
+}
+
+/// An operation git stopped in the middle of, which the next commit concludes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// `git merge`, with the hashes of the commits being merged.
+    Merge { heads: Vec<String> },
+    /// `git revert` of `commit`.
+    Revert { commit: String },
+    /// `git cherry-pick` of `commit`.
+    CherryPick { commit: String },
+    /// `git rebase`, which records its own commits when continued.
+    Rebase,
+}
+
+impl Operation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Merge { .. } => "merge",
+            Operation::Revert { .. } => "revert",
+            Operation::CherryPick { .. } => "cherry-pick",
+            Operation::Rebase => "rebase",
+        }
+    }
+}
+
+/// Detects a merge, revert, cherry-pick or rebase in progress from the files
+/// git keeps in the repository while it waits for the user.
+pub fn operation_in_progress() -> Option<Operation> {
+    let read_heads = |name: &str| {
+        git_path(name)
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|content| lines(&content))
+            .filter(|heads| !heads.is_empty())
+    };
+    if let Some(heads) = read_heads("MERGE_HEAD") {
+        return Some(Operation::Merge { heads });
+    }
+    if let Some(mut heads) = read_heads("REVERT_HEAD") {
+        return Some(Operation::Revert {
+            commit: heads.remove(0),
+        });
+    }
+    if let Some(mut heads) = read_heads("CHERRY_PICK_HEAD") {
+        return Some(Operation::CherryPick {
+            commit: heads.remove(0),
+        });
+    }
+    // `rebase-apply` is shared with `git am`, which marks itself as applying
+    let exists = |name: &str| git_path(name).is_ok_and(|path| path.exists());
+    if exists("rebase-merge") || exists("rebase-apply/rebasing") {
+        return Some(Operation::Rebase);
+    }
+    None
+}
+
+/// Reads the message git prepared for the commit concluding a merge, revert
+/// or cherry-pick, comments included.
+pub fn prepared_message() -> Option<String> {
+    git_path("MERGE_MSG")
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .filter(|message| !message.trim().is_empty())
+}
+
+/// Gets the full message of the commit `rev`.
+pub fn commit_message(rev: &str) -> Result<String, String> {
+    git_output(&["log", "-1", "--format=%B", rev, "--"]).map(|message| message.trim().to_string())
+}
+
+/// Gets the branch `origin/HEAD` points to, e.g. `origin/main`.
+pub fn remote_default_branch() -> Option<String> {
+    git_output(&["symbolic-ref", "--short", "-q", "refs/remotes/origin/HEAD"])
+        .ok()
+        .map(|branch| branch.trim().to_string())
+        .filter(|branch| !branch.is_empty())
+}
+
+/// Gets the names of the tags reachable from `rev`.
+pub fn get_merged_tags(rev: &str) -> Result<Vec<String>, String> {
+    git_output(&["tag", "--merged", rev]).map(|output| lines(&output))
+}
+
+/// Creates an annotated tag on `HEAD`.
+pub fn create_tag(name: &str, message: &str) -> Result<(), String> {
+    git_output(&["tag", "-a", name, "-m", message]).map(|_| ())
+}
+
+/// Whether `name` is a tag.
+pub fn is_tag(name: &str) -> bool {
+    git_output(&[
+        "rev-parse",
+        "--quiet",
+        "--verify",
+        &format!("refs/tags/{}", name),
+    ])
+    .is_ok()
+}
+
+/// Gets the committer date of `rev` as `YYYY-MM-DD`.
+pub fn commit_date(rev: &str) -> Result<String, String> {
+    git_output(&["log", "-1", "--format=%cs", rev, "--"]).map(|date| date.trim().to_string())
+}
+
+/// Gets the staged changes as a patch that `git apply` can replay, with
+/// renames detected and binary changes included. The patch is kept as
+/// bytes, so content that isn't UTF-8 is replayed unchanged.
+pub fn get_staged_patch() -> Result<Vec<u8>, String> {
+    git_output_bytes(&[
+        "diff",
+        "--staged",
+        "--no-color",
+        "--no-ext-diff",
+        "--binary",
+        "--find-renames",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+    ])
+}
+
+/// The tree of `HEAD`, or the empty tree in a repository without commits.
+pub fn head_tree() -> Result<String, String> {
+    match diff_base() {
+        "HEAD" => git_output(&["rev-parse", "--verify", "HEAD^{tree}"])
+            .map(|tree| tree.trim().to_string()),
+        empty => Ok(empty.to_string()),
+    }
+}
+
+/// Writes the index to a tree object and returns its hash.
+pub fn write_index_tree() -> Result<String, String> {
+    git_output(&["write-tree"]).map(|tree| tree.trim().to_string())
+}
+
+/// Replaces the index with the contents of `tree`, leaving the working
+/// tree untouched.
+pub fn read_index_tree(tree: &str) -> Result<(), String> {
+    git_output(&["read-tree", tree]).map(|_| ())
+}
+
+/// Applies `patch` to the index only, like `git apply --cached`.
+pub fn apply_to_index(patch: &[u8]) -> Result<(), String> {
+    let mut child = Command::new("git")
+        .args(["apply", "--cached", "--whitespace=nowarn", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn git apply process: {}", e))?;
+
+    let mut stdin = child.stdin.take().expect("Failed to open stdin");
+    let patch_clone = patch.to_vec();
+    std::thread::spawn(move || {
+        use std::io::Write;
+        let _ = stdin.write_all(&patch_clone);
+    });
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait for git apply process: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "'git apply --cached' failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// Gets the name of the checked-out branch, or `None` on a detached `HEAD`.
+pub fn current_branch() -> Option<String> {
+    backend::call(|backend| backend.current_branch())
+        .ok()
+        .flatten()
+}
+
+/// Gets the top-level directory of the working tree.
+pub fn repo_root() -> Result<PathBuf, String> {
+    backend::call(|backend| backend.repo_root())
+}
+
+/// The name of the project whose git directory shared by all worktrees is
+/// `common_dir`: the main worktree's directory for a `.git`, so linked
+/// worktrees get the project's name rather than their own directory's, and
+/// otherwise the directory's name without `.git`, as for bare repositories
+/// and submodules.
+fn project_name(common_dir: &Path) -> Option<String> {
+    let name = common_dir.file_name()?.to_str()?;
+    if name == ".git" {
+        return common_dir
+            .parent()?
+            .file_name()?
+            .to_str()
+            .map(str::to_string);
+    }
+    let stripped = name
+        .strip_suffix(".git")
+        .filter(|stripped| !stripped.is_empty());
+    Some(stripped.unwrap_or(name).to_string())
+}
+
+/// Gets basic repository information: the name of the project and the
+/// checked-out branch, or `HEAD` when detached.
+pub fn get_repo_info() -> Result<(String, String), String> {
+    let root = repo_root()?;
+    let repo_name = backend::call(|backend| backend.common_dir())
+        .ok()
+        .and_then(|dir| project_name(&dir))
+        .or_else(|| root.file_name()?.to_str().map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+    let branch = current_branch().unwrap_or_else(|| "HEAD".to_string());
     Ok((repo_name, branch))
 }
 
 /// Commits the generated message.
 pub fn commit(message: &str, review: bool) -> Result<(), String> {
-    let mut command = Command::new("git");
-    command.arg("commit");
+    commit_with(
+        message,
+        &CommitOptions {
+            review,
+            ..Default::default()
+        },
+    )
+}
 
-    if review {
-        command.arg("-e"); // Open editor
+/// Commits the changes selected by `options.scope` with the message passed on stdin.
+pub fn commit_with(message: &str, options: &CommitOptions) -> Result<(), String> {
+    let mut command = Command::new("git");
+    command.arg("commit").args(options.flags());
+    command.arg("-F").arg("-");
+    if let ChangeScope::Paths(paths) = &options.scope {
+        command.arg("--").args(paths);
     }
 
     let mut child = command
-        .arg("-F")
-        .arg("-")
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -153,6 +759,114 @@ mod tests {
         let _ = is_git_repository();
     }
 
+    #[test]
+    fn test_commit_settings_merge_and_options() {
+        let config = CommitSettings {
+            signoff: Some(true),
+            sign: Some(SignSetting::Enabled(true)),
+            trailers: vec!["Reviewed-by: Bo <bo@example.com>".to_string()],
+            ..Default::default()
+        };
+        let cli = CommitSettings {
+            sign: Some(SignSetting::Enabled(false)),
+            author: Some("Ann <ann@example.com>".to_string()),
+            trailers: vec![
+                "Reviewed-by: Bo <bo@example.com>".to_string(),
+                "Refs: #1".to_string(),
+            ],
+            ..Default::default()
+        };
+        let options = config.merge(&cli).options(true, ChangeScope::All);
+        assert_eq!(
+            options.flags(),
+            vec![
+                "-e",
+                "-a",
+                "--signoff",
+                "--no-gpg-sign",
+                "--author=Ann <ann@example.com>",
+                "--trailer=Reviewed-by: Bo <bo@example.com>",
+                "--trailer=Refs: #1",
+            ]
+        );
+        assert!(CommitSettings::default()
+            .options(false, ChangeScope::Staged)
+            .flags()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_take_trailers() {
+        let settings = CommitSettings {
+            signoff: Some(true),
+            trailers: vec!["Reviewed-by: Bo <bo@example.com>".to_string()],
+            ..Default::default()
+        };
+        let mut options = settings.options(false, ChangeScope::Staged);
+        let trailers = options.take_trailers(Some("Ann <ann@example.com>"));
+        assert!(options.flags().is_empty());
+        assert_eq!(
+            trailers.lines,
+            vec![
+                "Signed-off-by: Ann <ann@example.com>",
+                "Reviewed-by: Bo <bo@example.com>"
+            ]
+        );
+
+        let mut description = "Adds refunds.\n\nreviewed-by: Bo <bo@example.com>".to_string();
+        trailers.apply(&mut description);
+        assert_eq!(
+            description,
+            "Adds refunds.\n\nreviewed-by: Bo <bo@example.com>\nSigned-off-by: Ann <ann@example.com>"
+        );
+        let mut empty = String::new();
+        trailers.apply(&mut empty);
+        assert_eq!(
+            empty,
+            "Signed-off-by: Ann <ann@example.com>\nReviewed-by: Bo <bo@example.com>"
+        );
+
+        // Git signs off when the committer is unknown
+        let mut options = settings.options(false, ChangeScope::Staged);
+        let trailers = options.take_trailers(None);
+        assert_eq!(options.flags(), vec!["--signoff"]);
+        assert_eq!(trailers.lines, vec!["Reviewed-by: Bo <bo@example.com>"]);
+    }
+
+    #[test]
+    fn test_commit_settings_validate() {
+        let settings = |trailer: &str| CommitSettings {
+            trailers: vec![trailer.to_string()],
+            ..Default::default()
+        };
+        assert!(settings("Signed-off-by: Ann <ann@example.com>")
+            .validate()
+            .is_ok());
+        assert!(settings("no colon").validate().is_err());
+        assert!(settings("Bad token: x").validate().is_err());
+        assert!(settings("Refs:").validate().is_err());
+    }
+
+    #[test]
+    fn test_project_name() {
+        let name = |dir: &str| project_name(Path::new(dir));
+        assert_eq!(name("/src/shop/.git").as_deref(), Some("shop"));
+        assert_eq!(name("/srv/shop.git").as_deref(), Some("shop"));
+        assert_eq!(
+            name("/src/shop/.git/modules/vendor").as_deref(),
+            Some("vendor")
+        );
+        assert_eq!(name("/home/ann/.dotfiles").as_deref(), Some(".dotfiles"));
+        assert_eq!(name("/srv/.git.git").as_deref(), Some(".git"));
+        assert_eq!(name("/").as_deref(), None);
+    }
+
+    #[test]
+    fn test_git_version() {
+        let version = git_version().expect("git should be installed for tests");
+        assert!(version.starts_with("git version"));
+    }
+
     #[test]
     fn test_get_staged_diff_no_staged_files() {
         // When there are no staged files, should return an error
@@ -1,7 +1,25 @@
+pub mod backend;
+pub mod bump;
+pub mod changelog;
 pub mod cli;
+pub mod command;
 pub mod config;
+pub mod conventional;
+pub mod diff;
+pub mod doctor;
 pub mod git;
+pub mod models;
+pub mod operation;
+pub mod output;
+pub mod pair;
+pub mod pr;
 pub mod providers;
+pub mod review;
+pub mod scopes;
+pub mod split;
+pub mod squash;
+pub mod submodule;
+pub mod tickets;
 
 // Re-export commonly used types for convenience
-pub use providers::{AIProvider, GeneratedCommit};
+pub use providers::{AIProvider, Conversation, GeneratedCommit};
@@ -1,16 +1,40 @@
 use clap::Parser;
 use colored::*;
 use question::{Answer, Question};
-use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+use rustyline::{Cmd, DefaultEditor, KeyCode, KeyEvent, Modifiers};
 use spinners::{Spinner, Spinners};
-use std::process::Command;
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-use commitcraft::{cli, config, git, providers};
+use commitcraft::{
+    bump, changelog, cli, command, config, diff, doctor, git, models, operation, output, pair, pr,
+    providers, review, scopes, split, squash, tickets,
+};
 
-use cli::{Cli, Commands};
+use cli::{Cli, Commands, OutputFormat, PairAction};
+use git::CommitSettings;
+use pair::Pairing;
 use providers::{
-    anthropic::AnthropicProvider, gemini::GeminiProvider, openai::OpenAIProvider, AIProvider,
+    build_provider, AIProvider, Conversation, GeneratedCommit, GenerationSettings, ScopeConstraint,
 };
+use review::ReviewAction;
+use scopes::ScopeRules;
+use tickets::TicketRefs;
+
+/// Exit code used when the user interrupts the program with Ctrl-C.
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// Set while a generation request is in flight and handles Ctrl-C itself.
+static GENERATING: AtomicBool = AtomicBool::new(false);
+
+/// Set while split commits are created. Ctrl-C then only sets
+/// [`INTERRUPTED`], so the original index is restored before exiting.
+static COMMITTING: AtomicBool = AtomicBool::new(false);
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
 fn show_welcome() {
     println!(
@@ -40,12 +64,22 @@ async fn main() {
     env_logger::init();
 
     let cli_args = Cli::parse();
+    let json_output = cli_args.output == OutputFormat::Json;
 
     // Show welcome banner for main functionality (not for subcommands)
-    if cli_args.command.is_none() {
+    if cli_args.command.is_none() && !json_output {
         show_welcome();
     }
 
+    // Progress notes go to stderr in JSON mode so stdout stays parseable
+    let note = |line: String| {
+        if json_output {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    };
+
     // Handle commands
     match cli_args.command {
         Some(Commands::Setup) => {
@@ -59,13 +93,58 @@ async fn main() {
             show_config();
             return;
         }
-        Some(Commands::List) => {
-            list_providers_and_models();
+        Some(Commands::Doctor { json, offline }) => {
+            run_doctor(json, offline).await;
+            return;
+        }
+        Some(Commands::List { refresh }) => {
+            list_providers_and_models(refresh).await;
+            return;
+        }
+        Some(Commands::Lint { file, branch }) => {
+            run_lint(file, branch);
+            return;
+        }
+        Some(Commands::Split { dry_run, yes }) => {
+            run_split(&cli_args, dry_run, yes).await;
+            return;
+        }
+        Some(Commands::Pr { ref base, json }) => {
+            run_pr(&cli_args, base.clone(), json).await;
+            return;
+        }
+        Some(Commands::SquashMsg { ref range, write }) => {
+            run_squash_msg(&cli_args, range, write).await;
+            return;
+        }
+        Some(Commands::Bump {
+            ref pre,
+            write,
+            tag,
+            json,
+        }) => {
+            run_bump(&cli_args, pre.as_deref(), write, tag, json);
+            return;
+        }
+        Some(Commands::Changelog {
+            ref from,
+            ref to,
+            ref release,
+            ref prepend,
+            polish,
+        }) => {
+            run_changelog(&cli_args, from, to, release, prepend.as_deref(), polish).await;
+            return;
+        }
+        Some(Commands::Pair { ref action }) => {
+            run_pair(action.as_ref());
             return;
         }
         None => {}
     }
 
+    install_interrupt_handler();
+
     // Load configuration
     let config = match config::load_config() {
         Ok(c) => c,
@@ -75,15 +154,66 @@ async fn main() {
         }
     };
 
-    // Check if in a git repository
-    if !git::is_git_repository() {
-        eprintln!("{}", "Error: Not inside a git repository.".red().bold());
+    // A diff read from a file or stdin isn't what the index holds, so its
+    // message is only printed, never committed. That works outside a
+    // repository too.
+    let external_diff = cli_args.stdin || cli_args.diff_file.is_some();
+    let commits = !(cli_args.dry_run || json_output || cli_args.show_command);
+    if external_diff && commits {
+        eprintln!(
+            "{} A diff from --stdin or --diff-file doesn't describe the staged changes, so its message can't be committed. Use --dry-run, --output json or --show-command.",
+            "Error:".red().bold()
+        );
         std::process::exit(1);
     }
+    let work_tree = git::require_work_tree();
+    let in_repo = work_tree.is_ok();
+    if let Err(e) = work_tree {
+        if !external_diff {
+            eprintln!("{}", format!("Error: {}", e).red().bold());
+            std::process::exit(1);
+        }
+    }
+
+    let change_scope = cli_args.change_scope();
+    let mut commit_options =
+        commit_settings(&cli_args, &config, in_repo).options(cli_args.review, change_scope.clone());
+    let trailers = take_trailers(&mut commit_options);
+
+    // A merge, revert or cherry-pick in progress is described from what git
+    // recorded rather than from its diff, which for a merge can be huge
+    let in_progress = if in_repo && !external_diff {
+        git::operation_in_progress()
+    } else {
+        None
+    };
+    let prepared = match &in_progress {
+        Some(operation) => operation_message(operation).unwrap_or_else(|e| {
+            eprintln!("{} {}", "Warning:".yellow().bold(), e);
+            None
+        }),
+        None => None,
+    };
+    if in_progress == Some(git::Operation::Rebase) {
+        eprintln!(
+            "{} A rebase is in progress. Run 'git rebase --continue' after committing.",
+            "Note:".yellow().bold()
+        );
+    }
 
-    // Get staged diff
-    let diff = match git::get_staged_diff() {
+    // Get the diff from a file, stdin or the selected changes
+    let diff = if let Some(path) = &cli_args.diff_file {
+        diff::read_diff_file(path)
+    } else if cli_args.stdin {
+        diff::read_diff(std::io::stdin().lock(), "stdin")
+    } else {
+        git::get_diff(&change_scope)
+    };
+    // Git's prepared message doesn't need a diff, e.g. after
+    // `git merge -s ours --no-commit` nothing is staged
+    let diff = match diff {
         Ok(d) => d,
+        Err(_) if prepared.is_some() => String::new(),
         Err(e) => {
             eprintln!("{} {}", "Error:".red().bold(), e);
             std::process::exit(1);
@@ -92,15 +222,21 @@ async fn main() {
 
     // Show verbose output if requested
     if cli_args.verbose {
-        println!("{}", "Analyzing the following diff:".bold());
-        println!("{}", "─".repeat(50));
-        println!("{}", diff.dimmed());
-        println!("{}", "─".repeat(50));
+        note("Analyzing the following diff:".bold().to_string());
+        note("─".repeat(50));
+        note(diff.dimmed().to_string());
+        note("─".repeat(50));
     }
 
+    let changed_files = if external_diff {
+        Ok(diff::changed_files(&diff))
+    } else {
+        git::get_changed_files(&change_scope)
+    };
+
     // Get file context if requested
     let file_context = if cli_args.include_files {
-        match git::get_staged_files() {
+        match &changed_files {
             Ok(files) => Some(format!("Files modified: {}", files.join(", "))),
             Err(_) => None,
         }
@@ -110,8 +246,10 @@ async fn main() {
 
     // Get repository context
     let repo_context = match git::get_repo_info() {
-        Ok((repo_name, branch)) => Some(format!("Repository: {} (branch: {})", repo_name, branch)),
-        Err(_) => None,
+        Ok((repo_name, branch)) if in_repo => {
+            Some(format!("Repository: {} (branch: {})", repo_name, branch))
+        }
+        _ => None,
     };
 
     // Enhance diff with context
@@ -128,90 +266,175 @@ async fn main() {
         diff
     };
 
-    // Determine provider and model
-    let provider_name = cli_args
-        .provider
-        .or(config.default_provider)
-        .unwrap_or_else(|| "gemini".to_string());
-
-    let model_name_or_alias = cli_args.model.unwrap_or_else(|| {
-        match provider_name.as_str() {
-            "openai" => config.models.openai,
-            "gemini" => config.models.gemini,
-            "anthropic" => config.models.anthropic,
-            _ => None,
+    // A prepared message is used without a provider, which is only needed
+    // to regenerate it
+    let selected = select_provider(&cli_args, &config);
+    let (provider_name, model_name) = match &selected {
+        Ok(selected) => {
+            note(format!(
+                "Using provider: {} ({})",
+                selected.name.cyan(),
+                selected.model.cyan()
+            ));
+            (selected.name.clone(), selected.model.clone())
         }
-        .unwrap_or_else(|| "default".to_string())
-    });
+        Err(_) => ("git".to_string(), String::new()),
+    };
+    let provider = selected.map(|selected| selected.provider);
 
-    let model_name = config
-        .aliases
-        .get(&model_name_or_alias)
-        .unwrap_or(&model_name_or_alias);
+    // Recent subjects show the model the repository's scopes and phrasing.
+    // An external diff may come from elsewhere, so they are left out for it.
+    let examples = if external_diff {
+        Vec::new()
+    } else {
+        style_examples(&cli_args, &config, || changed_files.clone())
+    };
+    if cli_args.verbose && !examples.is_empty() {
+        note(format!(
+            "Using {} recent commit subjects as style examples.",
+            examples.len()
+        ));
+    }
 
-    // Get API key for the selected provider
-    let api_key = match provider_name.as_str() {
-        "openai" => config.api_keys.openai,
-        "gemini" => config.api_keys.gemini,
-        "anthropic" => config.api_keys.anthropic,
-        _ => None,
+    // The scope is decided from the changed files so it doesn't vary between runs
+    let scope = changed_files
+        .ok()
+        .and_then(|files| scope_rules(&config, in_repo).infer(&files));
+    if cli_args.verbose {
+        match &scope {
+            Some(ScopeConstraint::Use(scope)) => note(format!("Using scope: {}", scope)),
+            Some(ScopeConstraint::Omit) => {
+                note("The changes span several scopes; leaving the scope out.".to_string())
+            }
+            None => {}
+        }
     }
-    .ok_or_else(|| {
-        format!(
-            "API key for provider '{}' not found. Please run 'commitcraft setup'.",
-            provider_name
-        )
-    })
-    .unwrap_or_else(|e| {
-        eprintln!("{} {}", "Configuration Error:".red().bold(), e);
-        std::process::exit(1);
-    });
 
-    // Instantiate the provider
-    let provider: Box<dyn AIProvider> = match provider_name.as_str() {
-        "openai" => Box::new(OpenAIProvider::new(api_key, model_name.to_string())),
-        "gemini" => Box::new(GeminiProvider::new(api_key, model_name.to_string())),
-        "anthropic" => Box::new(AnthropicProvider::new(api_key, model_name.to_string())),
-        _ => {
-            eprintln!(
-                "{} Unknown provider '{}'",
-                "Error:".red().bold(),
-                provider_name
-            );
+    let conversation = Conversation::new(enhanced_diff)
+        .with_style_examples(examples)
+        .with_scope(scope);
+    let started = Instant::now();
+    let mut candidates = match (prepared.clone(), &provider) {
+        (Some(prepared), _) => {
+            if let Some(operation) = &in_progress {
+                note(format!(
+                    "A {} is in progress; using the message git recorded for it.",
+                    operation.name()
+                ));
+            }
+            vec![prepared]
+        }
+        (None, Ok(provider)) => {
+            match generate_with_spinner(
+                provider.as_ref(),
+                &conversation,
+                cli_args.candidates.into(),
+                !json_output,
+            )
+            .await
+            {
+                Ok(candidates) => candidates,
+                Err(e) => {
+                    eprintln!("{} {}", "API Error:".red().bold(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        (None, Err(e)) => {
+            eprintln!("{} {}", "Configuration Error:".red().bold(), e);
             std::process::exit(1);
         }
     };
 
-    println!(
-        "Using provider: {} ({})",
-        provider_name.cyan(),
-        model_name.cyan()
-    );
-
-    let mut sp = Spinner::new(Spinners::Dots, "Generating commit message...".into());
-
-    let commit_msg = match provider.generate_commit_message(&enhanced_diff).await {
-        Ok(msg) => {
-            sp.stop_with_message("✓ Message generated successfully!".into());
+    // Ticket IDs from the branch are added here rather than left to the model
+    let tickets = ticket_refs(&config, in_repo);
+    if cli_args.verbose && !tickets.ids.is_empty() {
+        note(format!("Referencing tickets: {}", tickets.ids.join(", ")));
+    }
+    let pairing = pairing(&cli_args, &config, in_repo);
+    if cli_args.verbose && !pairing.coauthors.is_empty() {
+        let names: Vec<String> = pairing.coauthors.iter().map(ToString::to_string).collect();
+        note(format!("Crediting co-authors: {}", names.join(", ")));
+    }
+    // Git's prepared titles keep their scope, but every message, regenerated
+    // ones included, references the tickets and credits the co-authors
+    for candidate in &mut candidates {
+        if prepared.is_none() {
+            let original = enforce_scope(&mut candidate.title, conversation.scope.as_ref());
+            if let Some(original) = original {
+                if cli_args.verbose {
+                    note(format!(
+                        "Corrected the scope of '{}' to '{}'.",
+                        original, candidate.title
+                    ));
+                }
+            }
+        }
+        tickets.apply(&mut candidate.title, &mut candidate.description);
+        pairing.apply(&mut candidate.description);
+        trailers.apply(&mut candidate.description);
+    }
+    // Candidates that differ only in scope or footers are the same once
+    // normalised, so repeats are dropped afterwards
+    let candidates = providers::dedupe_candidates(candidates);
 
-            // Validate the generated commit message
-            if let Err(validation_error) = msg.validate() {
-                eprintln!("{} {}", "Warning:".yellow().bold(), validation_error);
+    // Validate the generated commit messages. Merge titles are git's own.
+    if !matches!(in_progress, Some(git::Operation::Merge { .. })) {
+        for (index, candidate) in candidates.iter().enumerate() {
+            if let Err(validation_error) = candidate.validate() {
+                let label = if candidates.len() > 1 {
+                    format!("candidate {}: ", index + 1)
+                } else {
+                    String::new()
+                };
+                eprintln!(
+                    "{} {}{}",
+                    "Warning:".yellow().bold(),
+                    label,
+                    validation_error
+                );
                 eprintln!(
                     "The generated message may not follow conventional commits format exactly."
                 );
             }
+        }
+    }
+
+    let generation_time = started.elapsed();
 
-            msg
+    if json_output {
+        let output =
+            output::JsonOutput::new(&provider_name, &model_name, &candidates, generation_time)
+                .and_then(|output| {
+                    serde_json::to_string_pretty(&output)
+                        .map_err(|e| format!("Failed to serialize output: {}", e))
+                });
+        match output {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                eprintln!("{} {}", "Error:".red().bold(), e);
+                std::process::exit(1);
+            }
         }
-        Err(e) => {
-            sp.stop_with_message("✗ Error generating message.".into());
-            eprintln!("{} {}", "API Error:".red().bold(), e);
-            std::process::exit(1);
+        return;
+    }
+
+    if cli_args.dry_run && candidates.len() > 1 {
+        println!("\n{}", "Generated Commit Messages:".bold());
+        for (index, candidate) in candidates.iter().enumerate() {
+            let message = review::compose_message(&candidate.title, &candidate.description);
+            println!(
+                "\n{}\n---\n{}\n---",
+                format!("{}.", index + 1).bold(),
+                message.green()
+            );
         }
-    };
+        return;
+    }
 
-    let commit_str = commit_msg.to_string();
+    // Non-interactive modes use the first candidate
+    let commit_msg = candidates[0].clone();
+    let commit_str = review::compose_message(&commit_msg.title, &commit_msg.description);
 
     // Handle different modes
     if cli_args.dry_run {
@@ -224,7 +447,7 @@ async fn main() {
     }
 
     if cli_args.show_command {
-        let git_command = format_git_command(&commit_str, cli_args.review);
+        let git_command = command::format_git_command(&commit_str, &commit_options);
         println!("\n{}", "Generated git command:".bold());
         println!("{}", git_command.cyan());
         return;
@@ -232,65 +455,398 @@ async fn main() {
 
     if cli_args.legacy {
         // Use the old confirmation-based flow
-        legacy_commit_flow(&commit_str, cli_args.force, cli_args.review);
+        legacy_commit_flow(&commit_str, cli_args.force, &commit_options);
         return;
     }
 
     if cli_args.yes {
         // Skip interactive editing, commit immediately
-        if let Err(e) = execute_git_commit(&commit_str, cli_args.review) {
+        if let Err(e) = execute_git_commit(&commit_str, &commit_options) {
             eprintln!("{} {}", "Error during commit:".red().bold(), e);
             std::process::exit(1);
         }
         return;
     }
 
-    // Default: Interactive command editing
-    interactive_commit_flow(&commit_str, cli_args.review);
+    // Default: Interactive review, after picking one of several candidates
+    let commit_msg = if candidates.len() > 1 {
+        match pick_candidate(candidates) {
+            Some(candidate) => candidate,
+            None => {
+                println!("{}", "Commit cancelled.".yellow());
+                return;
+            }
+        }
+    } else {
+        commit_msg
+    };
+    interactive_commit_flow(
+        commit_msg,
+        commit_options,
+        provider.as_deref().map_err(String::as_str),
+        conversation,
+        &tickets,
+        &pairing,
+        &trailers,
+    )
+    .await;
 }
 
-/// Format the git commit command with proper escaping
-fn format_git_command(message: &str, review: bool) -> String {
-    let review_flag = if review { " -e" } else { "" };
-
-    // For multi-line messages, we need to handle them properly
-    if message.contains('\n') {
-        // Use heredoc-style for multi-line messages
-        format!("git commit{} -F- <<'EOF'\n{}\nEOF", review_flag, message)
+/// The commit settings of the config file, the repository's
+/// `.commitcraft.toml` and the command line, later ones taking precedence.
+/// Invalid settings end the program.
+fn commit_settings(cli_args: &Cli, config: &config::Config, in_repo: bool) -> CommitSettings {
+    let repo = if in_repo {
+        git::repo_root()
+            .and_then(|root| config::load_repo_config(&root))
+            .unwrap_or_else(|e| {
+                eprintln!("{} {}", "Configuration Error:".red().bold(), e);
+                std::process::exit(1);
+            })
     } else {
-        // Simple single-line message
-        format!(
-            "git commit{} -m \"{}\"",
-            review_flag,
-            message.replace('"', "\\\"")
-        )
+        config::RepoConfig::default()
+    };
+    let settings = config
+        .commit
+        .merge(&repo.commit)
+        .merge(&cli_args.commit_overrides());
+    if let Err(e) = settings.validate() {
+        eprintln!("{} {}", "Configuration Error:".red().bold(), e);
+        std::process::exit(1);
     }
+    settings
 }
 
-/// Interactive command editing flow (new default)
-fn interactive_commit_flow(commit_message: &str, review: bool) {
-    println!("\n{}", "📝 Generated commit message:".bold());
-    println!("{}", "─".repeat(50));
-    println!("{}", commit_message.green());
-    println!("{}", "─".repeat(50));
+/// The message for the commit concluding `operation`, or `None` when the
+/// message is generated as usual.
+fn operation_message(operation: &git::Operation) -> Result<Option<GeneratedCommit>, String> {
+    match operation {
+        git::Operation::Merge { heads } => {
+            let prepared = git::prepared_message()
+                .unwrap_or_else(|| format!("Merge commit '{}'", heads.join("', '")));
+            let mut merged = Vec::new();
+            for head in heads {
+                merged.extend(git::get_log(Some("HEAD"), head)?);
+            }
+            Ok(Some(operation::merge_message(&prepared, &merged)))
+        }
+        git::Operation::Revert { commit } => {
+            let message = git::commit_message(commit)?;
+            Ok(Some(operation::revert_message(commit, &message)))
+        }
+        git::Operation::CherryPick { commit } => {
+            let prepared = match git::prepared_message() {
+                Some(prepared) => prepared,
+                None => git::commit_message(commit)?,
+            };
+            Ok(Some(operation::prepared_message(&prepared)))
+        }
+        git::Operation::Rebase => Ok(None),
+    }
+}
+
+/// The provider and model selected for a run.
+struct SelectedProvider {
+    provider: Box<dyn AIProvider>,
+    name: String,
+    model: String,
+}
+
+/// Builds the provider and model given on the command line, falling back to
+/// the configured defaults and resolving model aliases.
+fn select_provider(cli_args: &Cli, config: &config::Config) -> Result<SelectedProvider, String> {
+    let network = config.network.merge(&cli_args.network_overrides());
+    let provider_name = cli_args
+        .provider
+        .clone()
+        .or(config.default_provider.clone())
+        .unwrap_or_else(|| "gemini".to_string());
+
+    let model_name_or_alias = cli_args.model.clone().unwrap_or_else(|| {
+        match provider_name.as_str() {
+            "openai" => config.models.openai.clone(),
+            "gemini" => config.models.gemini.clone(),
+            "anthropic" => config.models.anthropic.clone(),
+            _ => None,
+        }
+        .unwrap_or_else(|| "default".to_string())
+    });
+
+    let model_name = config
+        .aliases
+        .get(&model_name_or_alias)
+        .unwrap_or(&model_name_or_alias)
+        .clone();
+
+    let settings = config
+        .generation_settings(&provider_name, &model_name_or_alias)
+        .merge(&cli_args.generation_overrides());
 
-    let git_command = if commit_message.contains('\n') {
-        // For multi-line, show a simplified version for editing
-        let title = commit_message.lines().next().unwrap_or(commit_message);
+    // Get API key for the selected provider
+    let api_key = match provider_name.as_str() {
+        "openai" => config.api_keys.openai.clone(),
+        "gemini" => config.api_keys.gemini.clone(),
+        "anthropic" => config.api_keys.anthropic.clone(),
+        _ => None,
+    }
+    .ok_or_else(|| {
         format!(
-            "git commit{} -m \"{}\"",
-            if review { " -e" } else { "" },
-            title
+            "API key for provider '{}' not found. Please run 'commitcraft setup'.",
+            provider_name
         )
+    })?;
+
+    let provider = build_provider(&provider_name, api_key, &model_name, settings, &network)?;
+    Ok(SelectedProvider {
+        provider,
+        name: provider_name,
+        model: model_name,
+    })
+}
+
+/// The ticket IDs of the checked-out branch. Exits if a configured pattern
+/// is invalid.
+fn ticket_refs(config: &config::Config, in_repo: bool) -> TicketRefs {
+    let branch = in_repo.then(git::current_branch).flatten();
+    TicketRefs::from_branch(&config.tickets, branch.as_deref().unwrap_or_default()).unwrap_or_else(
+        |e| {
+            eprintln!("{} {}", "Configuration Error:".red().bold(), e);
+            std::process::exit(1);
+        },
+    )
+}
+
+/// The co-author roster of the repository's `.commitcraft.toml`, with the
+/// config file's entries taking precedence.
+fn coauthor_roster(
+    config: &config::Config,
+    in_repo: bool,
+) -> Result<HashMap<String, String>, String> {
+    let mut roster = if in_repo {
+        git::repo_root()
+            .and_then(|root| config::load_repo_config(&root))?
+            .coauthors
     } else {
-        format_git_command(commit_message, review)
+        HashMap::new()
     };
+    roster.extend(config.coauthors.clone());
+    Ok(roster)
+}
 
-    println!(
-        "\n{}",
-        "Edit the command below (or press Enter to execute):".bold()
-    );
+/// Takes the trailers out of `options` to add them to the messages instead,
+/// signing off as the committer.
+fn take_trailers(options: &mut git::CommitOptions) -> git::Trailers {
+    let signer = if options.signoff {
+        git::committer()
+    } else {
+        None
+    };
+    options.take_trailers(signer.as_deref())
+}
 
+/// The co-authors of the pairing session and `--pair`. Exits if one of them
+/// can't be resolved.
+fn pairing(cli_args: &Cli, config: &config::Config, in_repo: bool) -> Pairing {
+    let resolve = || -> Result<Pairing, String> {
+        let session = if in_repo {
+            pair::load_session(&git::common_git_path(pair::SESSION_FILE)?)?
+        } else {
+            Vec::new()
+        };
+        let pair = pair::resolve(&cli_args.pair, &coauthor_roster(config, in_repo)?)?;
+        Ok(Pairing::new(session, pair, git::user_email().as_deref()))
+    };
+    resolve().unwrap_or_else(|e| {
+        eprintln!("{} {}", "Configuration Error:".red().bold(), e);
+        std::process::exit(1);
+    })
+}
+
+/// The scope rules of the repository, with workspace members read from its
+/// root. Exits if a configured glob is invalid.
+fn scope_rules(config: &config::Config, in_repo: bool) -> ScopeRules {
+    let root = in_repo
+        .then(|| git::repo_root().ok())
+        .flatten()
+        .unwrap_or_else(|| PathBuf::from("."));
+    ScopeRules::load(&config.scopes, &root).unwrap_or_else(|e| {
+        eprintln!("{} {}", "Configuration Error:".red().bold(), e);
+        std::process::exit(1);
+    })
+}
+
+/// Rewrites the scope of `title` when the model didn't follow `scope`, and
+/// returns the original title if it changed.
+fn enforce_scope(title: &mut String, scope: Option<&ScopeConstraint>) -> Option<String> {
+    let enforced = scopes::enforce(title, scope?);
+    (enforced != *title).then(|| std::mem::replace(title, enforced))
+}
+
+/// Recent commit subjects to send as style examples. With `filter_paths`
+/// set, commits that touched the changed files are preferred. Failing to
+/// read the history only means sending no examples.
+fn style_examples(
+    cli_args: &Cli,
+    config: &config::Config,
+    changed_files: impl FnOnce() -> Result<Vec<String>, String>,
+) -> Vec<String> {
+    let count = cli_args.history.unwrap_or(config.history.examples);
+    if count == 0 {
+        return Vec::new();
+    }
+    if config.history.filter_paths {
+        let files = changed_files().unwrap_or_default();
+        if !files.is_empty() {
+            match git::get_recent_subjects(count, &files) {
+                Ok(subjects) if !subjects.is_empty() => return subjects,
+                _ => {}
+            }
+        }
+    }
+    git::get_recent_subjects(count, &[]).unwrap_or_default()
+}
+
+/// Exits with [`EXIT_INTERRUPTED`] on Ctrl-C outside of generation requests
+/// and split commits.
+///
+/// Listening for Ctrl-C replaces the default SIGINT behaviour for the rest of
+/// the process, so prompts need this to stay interruptible.
+fn install_interrupt_handler() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if COMMITTING.load(Ordering::SeqCst) {
+                INTERRUPTED.store(true, Ordering::SeqCst);
+            } else if !GENERATING.load(Ordering::SeqCst) {
+                println!();
+                std::process::exit(EXIT_INTERRUPTED);
+            }
+        }
+    });
+}
+
+/// Runs a generation request for `count` candidates, behind a spinner unless
+/// `show_spinner` is false.
+async fn generate_with_spinner(
+    provider: &dyn AIProvider,
+    conversation: &Conversation,
+    count: usize,
+    show_spinner: bool,
+) -> Result<Vec<GeneratedCommit>, String> {
+    let text = if count > 1 {
+        format!("Generating {} commit messages...", count)
+    } else {
+        "Generating commit message...".to_string()
+    };
+    let request = async {
+        if count > 1 {
+            provider.generate_candidates(conversation, count).await
+        } else {
+            provider
+                .generate_commit_message(conversation)
+                .await
+                .map(|commit| vec![commit])
+        }
+    };
+    with_spinner(
+        request,
+        text,
+        "✓ Message generated successfully!",
+        "✗ Error generating message.",
+        show_spinner,
+    )
+    .await
+}
+
+/// Awaits a provider request behind a spinner unless `show_spinner` is
+/// false. Ctrl-C drops the in-flight request, stops the spinner and exits
+/// with [`EXIT_INTERRUPTED`].
+async fn with_spinner<T>(
+    request: impl std::future::Future<Output = Result<T, String>>,
+    text: String,
+    success: &str,
+    failure: &str,
+    show_spinner: bool,
+) -> Result<T, String> {
+    GENERATING.store(true, Ordering::SeqCst);
+    let mut sp = show_spinner.then(|| Spinner::new(Spinners::Dots, text));
+
+    let result = tokio::select! {
+        result = request => result,
+        _ = tokio::signal::ctrl_c() => {
+            match sp.as_mut() {
+                Some(sp) => sp.stop_with_message("✗ Generation cancelled.".into()),
+                None => eprintln!("Generation cancelled."),
+            }
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+    };
+
+    if let Some(sp) = sp.as_mut() {
+        match &result {
+            Ok(_) => sp.stop_with_message(success.into()),
+            Err(_) => sp.stop_with_message(failure.into()),
+        }
+    }
+    GENERATING.store(false, Ordering::SeqCst);
+    result
+}
+
+/// Shows a numbered list of candidates and returns the one chosen, or `None`
+/// if the user cancels.
+fn pick_candidate(mut candidates: Vec<GeneratedCommit>) -> Option<GeneratedCommit> {
+    println!("\n{}", "📝 Generated commit messages:".bold());
+    for (index, candidate) in candidates.iter().enumerate() {
+        println!("{}", "─".repeat(50));
+        let status = match candidate.validate() {
+            Ok(()) => "✓".green(),
+            Err(_) => "!".yellow(),
+        };
+        println!(
+            "{} {} {}",
+            format!("{}.", index + 1).bold(),
+            status,
+            candidate.title.green()
+        );
+        if !candidate.description.trim().is_empty() {
+            println!("{}", candidate.description.trim().dimmed());
+        }
+    }
+    println!("{}", "─".repeat(50));
+
+    let mut rl = DefaultEditor::new().ok()?;
+    loop {
+        let prompt = format!(
+            "Choose a message [1-{}] (Enter for 1, q to cancel): ",
+            candidates.len()
+        );
+        match rl.readline(&prompt) {
+            Ok(input) if input.trim().eq_ignore_ascii_case("q") => return None,
+            Ok(input) => match review::parse_choice(&input, candidates.len()) {
+                Some(index) => return Some(candidates.swap_remove(index)),
+                None => println!(
+                    "{} Unknown choice '{}'.",
+                    "Error:".red().bold(),
+                    input.trim()
+                ),
+            },
+            Err(_) => return None,
+        }
+    }
+}
+
+/// Interactive review screen (new default). The user can edit the title and
+/// body, open `$EDITOR`, edit an equivalent `git commit` command, or
+/// regenerate with an optional hint; accepting commits exactly the message
+/// shown.
+async fn interactive_commit_flow(
+    mut commit: GeneratedCommit,
+    mut options: git::CommitOptions,
+    provider: Result<&dyn AIProvider, &str>,
+    mut conversation: Conversation,
+    tickets: &TicketRefs,
+    pairing: &Pairing,
+    trailers: &git::Trailers,
+) {
     let mut rl = match DefaultEditor::new() {
         Ok(editor) => editor,
         Err(e) => {
@@ -300,61 +856,801 @@ fn interactive_commit_flow(commit_message: &str, review: bool) {
                 e
             );
             println!("Falling back to legacy mode...");
-            legacy_commit_flow(commit_message, false, review);
+            legacy_commit_flow(
+                &review::compose_message(&commit.title, &commit.description),
+                false,
+                &options,
+            );
             return;
         }
     };
 
-    match rl.readline_with_initial("$ ", (&git_command, "")) {
-        Ok(edited_command) => {
-            let edited_command = edited_command.trim();
-            if edited_command.is_empty() {
-                println!("{}", "Commit cancelled.".yellow());
-                return;
+    loop {
+        let message = review::compose_message(&commit.title, &commit.description);
+
+        println!("\n{}", "📝 Generated commit message:".bold());
+        println!("{}", "─".repeat(50));
+        println!("{}", message.green());
+        println!("{}", "─".repeat(50));
+        if let Err(validation_error) = commit.validate() {
+            println!("{} {}", "Warning:".yellow().bold(), validation_error);
+        }
+        println!("{}", review::ACTION_HINT.dimmed());
+
+        let action = match rl.readline("> ") {
+            Ok(input) => match review::parse_action(&input) {
+                Some(action) => action,
+                None => {
+                    println!(
+                        "{} Unknown choice '{}'.",
+                        "Error:".red().bold(),
+                        input.trim()
+                    );
+                    continue;
+                }
+            },
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => ReviewAction::Cancel,
+            Err(e) => {
+                eprintln!("{} Failed to read input: {}", "Error:".red().bold(), e);
+                std::process::exit(1);
             }
+        };
 
-            // Execute the edited command
-            println!("\n{} {}", "Executing:".bold(), edited_command.cyan());
-            match execute_shell_command(edited_command) {
-                Ok(output) => {
-                    println!("{}", "✓ Commit successful!".green().bold());
-                    if !output.trim().is_empty() {
-                        println!("{}", output);
-                    }
+        match action {
+            ReviewAction::Accept => {
+                if commit.title.trim().is_empty() {
+                    println!("{} The commit title is empty.", "Error:".red().bold());
+                    continue;
                 }
-                Err(e) => {
-                    eprintln!("{} {}", "Error:".red().bold(), e);
+                if let Err(e) = execute_git_commit(&message, &options) {
+                    eprintln!("{} {}", "Error during commit:".red().bold(), e);
                     std::process::exit(1);
                 }
+                return;
+            }
+            ReviewAction::EditTitle => {
+                match rl.readline_with_initial("Title: ", (&commit.title, "")) {
+                    Ok(title) => commit.title = title.trim().to_string(),
+                    Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {}
+                    Err(e) => eprintln!("{} Failed to read input: {}", "Error:".red().bold(), e),
+                }
             }
+            ReviewAction::EditBody => match edit_body(&commit.description) {
+                Ok(Some(body)) => commit.description = body,
+                Ok(None) => {}
+                Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+            },
+            ReviewAction::OpenEditor => match review::git_editor().and_then(|editor| {
+                let path = git::git_path("COMMIT_EDITMSG")?;
+                review::edit_in_editor(&message, &editor, &path)
+            }) {
+                Ok(edited) => {
+                    let (title, body) = review::split_message(&edited);
+                    commit.title = title;
+                    commit.description = body;
+                }
+                Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+            },
+            ReviewAction::EditCommand => {
+                let git_command = command::format_git_command(&message, &options);
+                match rl.readline_with_initial("$ ", (&git_command, "")) {
+                    Ok(edited) => match command::parse_commit_command(&edited) {
+                        Ok(parsed) => {
+                            let (title, body) = review::split_message(&parsed.message);
+                            commit.title = title;
+                            commit.description = body;
+                            options = parsed.options;
+                        }
+                        Err(e) => eprintln!("{} {}", "Error:".red().bold(), e),
+                    },
+                    Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => {}
+                    Err(e) => eprintln!("{} Failed to read input: {}", "Error:".red().bold(), e),
+                }
+            }
+            ReviewAction::Regenerate => {
+                let provider = match provider {
+                    Ok(provider) => provider,
+                    Err(e) => {
+                        eprintln!("{} {}", "Configuration Error:".red().bold(), e);
+                        continue;
+                    }
+                };
+                // A hint is sent with the current message as a follow-up turn;
+                // an empty hint simply asks again.
+                match rl.readline("Hint (optional, e.g. \"scope is api\", \"shorter\"): ") {
+                    Ok(hint) if !hint.trim().is_empty() => {
+                        conversation.push_feedback(message, hint.trim())
+                    }
+                    Ok(_) => {}
+                    Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => continue,
+                    Err(e) => {
+                        eprintln!("{} Failed to read input: {}", "Error:".red().bold(), e);
+                        continue;
+                    }
+                }
+                match generate_with_spinner(provider, &conversation, 1, true).await {
+                    Ok(mut regenerated) => {
+                        commit = regenerated.remove(0);
+                        enforce_scope(&mut commit.title, conversation.scope.as_ref());
+                        tickets.apply(&mut commit.title, &mut commit.description);
+                        pairing.apply(&mut commit.description);
+                        trailers.apply(&mut commit.description);
+                    }
+                    Err(e) => eprintln!("{} {}", "API Error:".red().bold(), e),
+                }
+            }
+            ReviewAction::Cancel => {
+                println!("{}", "Commit cancelled.".yellow());
+                return;
+            }
+        }
+    }
+}
+
+/// Edits a multi-line body in the terminal. Enter inserts a newline and
+/// Ctrl-D or Alt-Enter finishes; Ctrl-C keeps the previous body.
+fn edit_body(body: &str) -> Result<Option<String>, String> {
+    let mut rl =
+        DefaultEditor::new().map_err(|e| format!("Failed to create interactive editor: {}", e))?;
+    rl.bind_sequence(KeyEvent(KeyCode::Enter, Modifiers::NONE), Cmd::Newline);
+    rl.bind_sequence(KeyEvent(KeyCode::Enter, Modifiers::ALT), Cmd::AcceptLine);
+    rl.bind_sequence(KeyEvent::ctrl('D'), Cmd::AcceptLine);
+
+    println!(
+        "{}",
+        "Edit the body (Enter for a new line, Ctrl-D or Alt-Enter to finish, Ctrl-C to discard):"
+            .dimmed()
+    );
+    match rl.readline_with_initial("", (body, "")) {
+        Ok(edited) => Ok(Some(edited.trim().to_string())),
+        Err(ReadlineError::Interrupted) => Ok(None),
+        Err(e) => Err(format!("Failed to read input: {}", e)),
+    }
+}
+
+/// Asks the provider to group the staged hunks into commits, shows the plan
+/// and creates the commits once approved.
+async fn run_split(cli_args: &Cli, dry_run: bool, yes: bool) {
+    install_interrupt_handler();
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    git::require_work_tree().unwrap_or_else(|e| exit_with(e));
+    let config = config::load_config().unwrap_or_else(|_| std::process::exit(1));
+
+    let staged = git::get_staged_patch()
+        .map(|patch| split::StagedHunks::parse(&patch))
+        .unwrap_or_else(|e| exit_with(e));
+    if staged.is_empty() {
+        exit_with("There are no staged changes to split. Try running 'git add'.".to_string());
+    }
+    if staged.len() == 1 {
+        println!(
+            "Only one hunk is staged, so there is nothing to split. Run 'commitcraft' to commit it."
+        );
+        return;
+    }
+
+    let selected = select_provider(cli_args, &config).unwrap_or_else(|e| exit_with(e));
+    println!(
+        "Using provider: {} ({})",
+        selected.name.cyan(),
+        selected.model.cyan()
+    );
+
+    let examples = style_examples(cli_args, &config, git::get_staged_files);
+    let conversation = Conversation::new(staged.prompt()).with_style_examples(examples);
+    let mut plan = with_spinner(
+        selected.provider.plan_split(&conversation),
+        format!("Grouping {} hunks into commits...", staged.len()),
+        "✓ Plan generated successfully!",
+        "✗ Error generating plan.",
+        true,
+    )
+    .await
+    .unwrap_or_else(|e| exit_with(format!("API Error: {}", e)));
+    let left_out = split::check_plan(&plan, staged.len())
+        .unwrap_or_else(|e| exit_with(format!("The proposed plan is invalid: {}", e)));
+    let tickets = ticket_refs(&config, true);
+    let pairing = pairing(cli_args, &config, true);
+    let mut options =
+        commit_settings(cli_args, &config, true).options(false, git::ChangeScope::Staged);
+    let trailers = take_trailers(&mut options);
+    let rules = scope_rules(&config, true);
+    for commit in &mut plan.commits {
+        let files: Vec<String> = staged
+            .hunks()
+            .iter()
+            .filter(|hunk| commit.hunks.contains(&hunk.number))
+            .map(|hunk| hunk.path.clone())
+            .collect();
+        enforce_scope(&mut commit.title, rules.infer(&files).as_ref());
+        tickets.apply(&mut commit.title, &mut commit.description);
+        pairing.apply(&mut commit.description);
+        trailers.apply(&mut commit.description);
+    }
+
+    println!("\n{}", "📝 Proposed commits:".bold());
+    for (index, commit) in plan.commits.iter().enumerate() {
+        println!("{}", "─".repeat(50));
+        let status = match providers::GeneratedCommit::from(commit).validate() {
+            Ok(()) => "✓".green(),
+            Err(_) => "!".yellow(),
+        };
+        println!(
+            "{} {} {}",
+            format!("{}.", index + 1).bold(),
+            status,
+            commit.title.green()
+        );
+        if !commit.description.trim().is_empty() {
+            println!("{}", commit.description.trim().dimmed());
+        }
+        for hunk in staged
+            .hunks()
+            .iter()
+            .filter(|hunk| commit.hunks.contains(&hunk.number))
+        {
+            let kind = hunk
+                .kind
+                .map(|kind| format!(" ({})", kind))
+                .unwrap_or_default();
+            println!(
+                "   {} hunk {}: {}{}",
+                "•".cyan(),
+                hunk.number,
+                hunk.path,
+                kind
+            );
         }
-        Err(rustyline::error::ReadlineError::Interrupted) => {
-            println!("{}", "Commit cancelled.".yellow());
+    }
+    println!("{}", "─".repeat(50));
+    if !left_out.is_empty() {
+        let numbers: Vec<String> = left_out.iter().map(usize::to_string).collect();
+        println!(
+            "{} Hunks {} are not in any commit and will stay staged.",
+            "Note:".yellow().bold(),
+            numbers.join(", ")
+        );
+    }
+
+    if dry_run {
+        return;
+    }
+    if !yes {
+        let answer = Question::new(&format!(
+            "Create these {} commits? (Y/n)",
+            plan.commits.len()
+        ))
+        .yes_no()
+        .default(Answer::YES)
+        .ask()
+        .expect("Couldn't ask question.");
+        if answer == Answer::NO {
+            println!("{}", "Split cancelled.".yellow());
+            return;
         }
-        Err(e) => {
-            eprintln!("{} Failed to read input: {}", "Error:".red().bold(), e);
-            std::process::exit(1);
+    }
+
+    COMMITTING.store(true, Ordering::SeqCst);
+    let result = split::create_commits(&staged, &plan, &options, &INTERRUPTED);
+    COMMITTING.store(false, Ordering::SeqCst);
+    match result {
+        Ok(created) => println!("{} Created {} commits.", "✓".green(), created),
+        Err(e) if INTERRUPTED.load(Ordering::SeqCst) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+        Err(e) => exit_with(e),
+    }
+}
+
+/// Prints a pull request title and body for the commits in `base..HEAD`.
+async fn run_pr(cli_args: &Cli, base: Option<String>, json: bool) {
+    install_interrupt_handler();
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    git::require_work_tree().unwrap_or_else(|e| exit_with(e));
+    let config = config::load_config().unwrap_or_else(|_| std::process::exit(1));
+
+    let base = base
+        .or_else(git::remote_default_branch)
+        .unwrap_or_else(|| "main".to_string());
+    let commits = git::get_log(Some(&base), "HEAD").unwrap_or_else(|e| exit_with(e));
+    if commits.is_empty() {
+        exit_with(format!(
+            "There are no commits on this branch that aren't on '{}'.",
+            base
+        ));
+    }
+    let diff = git::get_branch_diff(&base, "HEAD").unwrap_or_else(|e| exit_with(e));
+
+    let template = git::repo_root()
+        .ok()
+        .and_then(|root| pr::find_template(&root));
+    let headings = template
+        .as_deref()
+        .map(pr::template_headings)
+        .filter(|headings| !headings.is_empty())
+        .unwrap_or_else(|| pr::DEFAULT_HEADINGS.iter().map(|h| h.to_string()).collect());
+
+    let selected = select_provider(cli_args, &config).unwrap_or_else(|e| exit_with(e));
+    eprintln!(
+        "Using provider: {} ({})",
+        selected.name.cyan(),
+        selected.model.cyan()
+    );
+    let conversation =
+        Conversation::plain(pr::prompt(&commits, &headings, template.as_deref(), &diff));
+    // The result is usually piped into another command, so keep stdout clean
+    let mut pull_request = with_spinner(
+        selected.provider.generate_pull_request(&conversation),
+        format!("Describing {} commits against {}...", commits.len(), base),
+        "✓ Pull request written!",
+        "✗ Error writing pull request.",
+        std::io::stdout().is_terminal(),
+    )
+    .await
+    .unwrap_or_else(|e| exit_with(format!("API Error: {}", e)));
+    pull_request.title = pull_request.title.trim().to_string();
+    pull_request.body = pr::fit_template(&pull_request.body, &headings);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&pull_request).unwrap_or_default()
+        );
+    } else {
+        println!("{}\n\n{}", pull_request.title, pull_request.body);
+    }
+}
+
+/// Prints one message for squashing the commits in `range`, keeping their
+/// breaking change and co-author footers.
+async fn run_squash_msg(cli_args: &Cli, range: &str, write: bool) {
+    install_interrupt_handler();
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    git::require_work_tree().unwrap_or_else(|e| exit_with(e));
+    let config = config::load_config().unwrap_or_else(|_| std::process::exit(1));
+
+    let (base, head) = squash::parse_range(range);
+    let commits = git::get_log(Some(&base), &head).unwrap_or_else(|e| exit_with(e));
+    if commits.is_empty() {
+        exit_with(format!(
+            "There are no commits in {}..{} to squash.",
+            base, head
+        ));
+    }
+    let diff = git::get_branch_diff(&base, &head).unwrap_or_else(|e| exit_with(e));
+
+    let selected = select_provider(cli_args, &config).unwrap_or_else(|e| exit_with(e));
+    eprintln!(
+        "Using provider: {} ({})",
+        selected.name.cyan(),
+        selected.model.cyan()
+    );
+    let changed_files = diff::changed_files(&diff);
+    let examples = style_examples(cli_args, &config, || Ok(changed_files.clone()));
+    let scope = scope_rules(&config, true).infer(&changed_files);
+    let conversation = Conversation::plain(squash::prompt(&commits, &diff))
+        .with_style_examples(examples)
+        .with_scope(scope);
+    let mut commit = with_spinner(
+        selected.provider.generate_commit_message(&conversation),
+        format!("Summarizing {} commits...", commits.len()),
+        "✓ Commit message generated successfully!",
+        "✗ Error generating commit message.",
+        std::io::stdout().is_terminal(),
+    )
+    .await
+    .unwrap_or_else(|e| exit_with(format!("API Error: {}", e)));
+    enforce_scope(&mut commit.title, conversation.scope.as_ref());
+    commit.description =
+        squash::apply_footers(&commit.description, &squash::preserved_footers(&commits));
+    if let Err(e) = commit.validate() {
+        eprintln!("{} {}", "Warning:".yellow().bold(), e);
+    }
+
+    let message = format!("{}\n\n{}\n", commit.title.trim(), commit.description.trim());
+    if write {
+        let path = git::git_path("SQUASH_MSG").unwrap_or_else(|e| exit_with(e));
+        std::fs::write(&path, &message)
+            .unwrap_or_else(|e| exit_with(format!("Failed to write '{}': {}", path.display(), e)));
+        eprintln!("{} Wrote {}.", "✓".green(), path.display());
+    } else {
+        print!("{}", message);
+    }
+}
+
+/// Suggests the next version from the commits since the latest version tag,
+/// and optionally writes it to the manifests and tags it.
+fn run_bump(cli_args: &Cli, pre: Option<&str>, write: bool, tag: bool, json: bool) {
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    git::require_work_tree().unwrap_or_else(|e| exit_with(e));
+
+    let tags: Vec<(String, bump::Version)> = git::get_merged_tags("HEAD")
+        .unwrap_or_else(|e| exit_with(e))
+        .into_iter()
+        .filter_map(|tag| bump::Version::parse(&tag).map(|version| (tag, version)))
+        .collect();
+    let stable = tags
+        .iter()
+        .filter(|(_, version)| version.pre.is_none())
+        .max_by(|a, b| a.1.cmp(&b.1));
+    let latest_pre = tags
+        .iter()
+        .filter(|(_, version)| version.pre.is_some())
+        .filter(|(_, version)| stable.is_none_or(|(_, stable)| version > stable))
+        .max_by(|a, b| a.1.cmp(&b.1));
+
+    let commits =
+        git::get_log(stable.map(|(tag, _)| tag.as_str()), "HEAD").unwrap_or_else(|e| exit_with(e));
+    let changes = bump::Changes::from_commits(&commits);
+    let Some(level) = changes.level() else {
+        let tag = stable.map(|(tag, _)| tag.as_str()).unwrap_or("HEAD");
+        eprintln!("No commits since {}, so there is nothing to release.", tag);
+        return;
+    };
+    let next = bump::next_version(
+        stable.map(|(_, version)| version),
+        latest_pre.map(|(_, version)| version),
+        level,
+        pre,
+    );
+    // New tags follow the naming of the latest one
+    let latest_tag = latest_pre.or(stable).map(|(tag, _)| tag.as_str());
+    let prefix = if latest_tag.is_none_or(|tag| tag.starts_with('v')) {
+        "v"
+    } else {
+        ""
+    };
+    let tag_name = format!("{}{}", prefix, next);
+
+    if json {
+        let report = serde_json::json!({
+            "current": stable.map(|(_, version)| version.to_string()),
+            "tag": stable.map(|(tag, _)| tag),
+            "next": next.to_string(),
+            "next_tag": tag_name,
+            "bump": level,
+            "commits": changes,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+    } else {
+        match stable {
+            Some((tag, _)) => println!("Latest release: {}", tag.cyan()),
+            None => println!("Latest release: {}", "none".dimmed()),
+        }
+        if let Some((tag, _)) = latest_pre {
+            println!("Latest pre-release: {}", tag.cyan());
+        }
+        println!(
+            "Commits since: {} ({} breaking, {} features, {} fixes, {} other)",
+            commits.len(),
+            changes.breaking,
+            changes.features,
+            changes.fixes,
+            changes.other
+        );
+        println!(
+            "Next version: {} ({})",
+            next.to_string().green().bold(),
+            level
+        );
+    }
+
+    let mut written = Vec::new();
+    if write {
+        let root = git::repo_root().unwrap_or_else(|e| exit_with(e));
+        written = write_version(&root, &next).unwrap_or_else(|e| exit_with(e));
+        if written.is_empty() {
+            eprintln!(
+                "{} No Cargo.toml or package.json with a version was found.",
+                "Note:".yellow().bold()
+            );
+        } else if !json {
+            let names: Vec<String> = written
+                .iter()
+                .filter_map(|path| path.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .collect();
+            println!("{} Updated {}.", "✓".green(), names.join(", "));
         }
     }
+
+    if tag {
+        if !written.is_empty() {
+            // Without a config file only the repository and command line settings apply
+            let config = match config::config_file_path() {
+                Ok(path) if path.exists() => config::load_config().unwrap_or_else(|e| exit_with(e)),
+                _ => config::Config::default(),
+            };
+            let paths = written
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+            let options = commit_settings(cli_args, &config, true)
+                .options(false, git::ChangeScope::Paths(paths));
+            git::commit_with(&format!("chore(release): {}", next), &options)
+                .unwrap_or_else(|e| exit_with(e));
+        }
+        git::create_tag(&tag_name, &tag_name).unwrap_or_else(|e| exit_with(e));
+        if !json {
+            println!("{} Tagged {}.", "✓".green(), tag_name.cyan());
+        }
+    }
+}
+
+/// Writes `version` to the manifests at `root` and returns the files changed.
+fn write_version(root: &std::path::Path, version: &bump::Version) -> Result<Vec<PathBuf>, String> {
+    let read = |path: &std::path::Path| match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read '{}': {}", path.display(), e)),
+    };
+    let mut updates = Vec::new();
+
+    let cargo_toml = root.join("Cargo.toml");
+    if let Some(content) = read(&cargo_toml)? {
+        if let Some((updated, previous)) = bump::set_cargo_version(&content, version)? {
+            updates.push((cargo_toml, updated));
+            let cargo_lock = root.join("Cargo.lock");
+            if let Some(lock) = read(&cargo_lock)? {
+                let updated = bump::set_lock_version(&lock, &previous, version)?;
+                if updated != lock {
+                    updates.push((cargo_lock, updated));
+                }
+            }
+        }
+    }
+    let package_json = root.join("package.json");
+    if let Some(content) = read(&package_json)? {
+        if let Some(updated) = bump::set_package_json_version(&content, version)? {
+            updates.push((package_json, updated));
+        }
+    }
+
+    // Everything is parsed before anything is written
+    for (path, content) in &updates {
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    }
+    Ok(updates.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Prints or prepends the changelog section for the commits in `from..to`.
+async fn run_changelog(
+    cli_args: &Cli,
+    from: &Option<String>,
+    to: &str,
+    release: &Option<String>,
+    prepend: Option<&std::path::Path>,
+    polish: bool,
+) {
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    git::require_work_tree().unwrap_or_else(|e| exit_with(e));
+
+    // By default the range starts at the tag before `to`
+    let from = from.clone().or_else(|| {
+        if git::is_tag(to) {
+            git::latest_tag(&format!("{}^", to))
+        } else {
+            git::latest_tag(to)
+        }
+    });
+    let commits = git::get_log(from.as_deref(), to).unwrap_or_else(|e| exit_with(e));
+    let range = match &from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
+    };
+
+    let mut section = changelog::Release::from_commits(&commits);
+    if section.skipped > 0 {
+        eprintln!(
+            "{} Left out {} commits in {} that don't follow Conventional Commits.",
+            "Note:".yellow().bold(),
+            section.skipped,
+            range
+        );
+    }
+    if section.entries.is_empty() {
+        eprintln!("No conventional commits found in {}.", range);
+        return;
+    }
+    section.version = release
+        .clone()
+        .or_else(|| git::is_tag(to).then(|| changelog::version_from_tag(to).to_string()));
+    if section.version.is_some() {
+        section.date = git::commit_date(to).ok();
+    }
+
+    if polish {
+        install_interrupt_handler();
+        let config = config::load_config().unwrap_or_else(|_| std::process::exit(1));
+        let selected = select_provider(cli_args, &config).unwrap_or_else(|e| exit_with(e));
+        // Printed changelogs are often redirected, so keep stdout clean
+        let show_spinner = prepend.is_some() || std::io::stdout().is_terminal();
+        let polished = with_spinner(
+            selected
+                .provider
+                .polish_changelog(&Conversation::plain(section.polish_prompt())),
+            format!("Polishing {} entries...", section.entries.len()),
+            "✓ Entries polished!",
+            "✗ Error polishing entries.",
+            show_spinner,
+        )
+        .await
+        .unwrap_or_else(|e| exit_with(format!("API Error: {}", e)));
+        section
+            .apply_polish(polished.entries)
+            .unwrap_or_else(|e| exit_with(e));
+    }
+
+    let rendered = section.render();
+    match prepend {
+        Some(path) => {
+            let existing = match std::fs::read_to_string(path) {
+                Ok(existing) => existing,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => exit_with(format!("Failed to read '{}': {}", path.display(), e)),
+            };
+            std::fs::write(path, changelog::prepend(&existing, &rendered)).unwrap_or_else(|e| {
+                exit_with(format!("Failed to write '{}': {}", path.display(), e))
+            });
+            println!(
+                "{} Added {} to {}.",
+                "✓".green(),
+                section.heading().trim_start_matches("## "),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
 }
 
-/// Execute a shell command and return output
-fn execute_shell_command(command: &str) -> Result<String, String> {
-    let output = Command::new("sh")
-        .arg("-c")
-        .arg(command)
-        .output()
-        .map_err(|e| format!("Failed to execute command: {}", e))?;
+/// Checks a commit message from `file` or stdin and exits with 1 if it
+/// breaks the Conventional Commits format or the ticket rules. Messages git
+/// writes itself, such as merges and fixups, are accepted as they are.
+fn run_lint(file: Option<PathBuf>, branch: Option<String>) {
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    let message = match file.filter(|path| path.as_os_str() != "-") {
+        Some(path) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read '{}': {}", path.display(), e)),
+        None => {
+            let mut message = String::new();
+            std::io::stdin()
+                .read_to_string(&mut message)
+                .map(|_| message)
+                .map_err(|e| format!("Failed to read stdin: {}", e))
+        }
+    }
+    .unwrap_or_else(|e| exit_with(e));
+    let message = review::strip_comments(&message);
+    let (title, body) = review::split_message(&message);
+
+    let generated_by_git = ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "];
+    if generated_by_git
+        .iter()
+        .any(|prefix| title.starts_with(prefix))
+    {
+        return;
+    }
+
+    // Without a config file there are no ticket rules, only the format check
+    let config = match config::config_file_path() {
+        Ok(path) if path.exists() => config::load_config().unwrap_or_else(|e| exit_with(e)),
+        _ => config::Config::default(),
+    };
+    let branch = branch
+        .or_else(|| git::is_git_repository().then(git::current_branch).flatten())
+        .unwrap_or_default();
+    let tickets =
+        TicketRefs::from_branch(&config.tickets, &branch).unwrap_or_else(|e| exit_with(e));
 
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    let mut errors = Vec::new();
+    if title.is_empty() {
+        errors.push("The commit message is empty".to_string());
     } else {
-        Err(String::from_utf8_lossy(&output.stderr).to_string())
+        let commit = GeneratedCommit {
+            title,
+            description: body,
+            usage: None,
+        };
+        if let Err(e) = commit.validate() {
+            errors.push(e);
+        }
+    }
+    errors.extend(tickets.check(&message));
+
+    if errors.is_empty() {
+        println!("{} Commit message is valid.", "✓".green());
+        return;
+    }
+    for error in &errors {
+        eprintln!("{} {}", "✗".red(), error);
+    }
+    std::process::exit(1);
+}
+
+/// Starts, stops or shows the pairing session of the repository.
+fn run_pair(action: Option<&PairAction>) {
+    let exit_with = |e: String| -> ! {
+        eprintln!("{} {}", "Error:".red().bold(), e);
+        std::process::exit(1);
+    };
+
+    git::require_work_tree().unwrap_or_else(|e| exit_with(e));
+    let path = git::common_git_path(pair::SESSION_FILE).unwrap_or_else(|e| exit_with(e));
+    let session = pair::load_session(&path).unwrap_or_else(|e| exit_with(e));
+    let names = |coauthors: &[pair::CoAuthor]| {
+        coauthors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match action {
+        Some(PairAction::Start { names: nicknames }) => {
+            // Without a config file only the repository's roster applies
+            let config = match config::config_file_path() {
+                Ok(path) if path.exists() => config::load_config().unwrap_or_else(|e| exit_with(e)),
+                _ => config::Config::default(),
+            };
+            let roster = coauthor_roster(&config, true).unwrap_or_else(|e| exit_with(e));
+            let coauthors = pair::resolve(nicknames, &roster).unwrap_or_else(|e| exit_with(e));
+            if coauthors.is_empty() {
+                exit_with("Name at least one co-author to pair with.".to_string());
+            }
+            pair::save_session(&path, &coauthors).unwrap_or_else(|e| exit_with(e));
+            println!("{} Pairing with {}.", "✓".green(), names(&coauthors));
+            println!("Commits get Co-authored-by trailers until 'commitcraft pair stop'.");
+        }
+        Some(PairAction::Stop) => {
+            if !path.exists() {
+                println!("Not pairing.");
+                return;
+            }
+            std::fs::remove_file(&path)
+                .unwrap_or_else(|e| exit_with(format!("Failed to stop pairing: {}", e)));
+            println!("{} Stopped pairing with {}.", "✓".green(), names(&session));
+        }
+        Some(PairAction::Status) | None => {
+            if session.is_empty() {
+                println!("Not pairing. Start with 'commitcraft pair start <names>'.");
+            } else {
+                println!("Pairing with {}.", names(&session));
+            }
+        }
     }
 }
 
 /// Legacy commit flow (old behavior)
-fn legacy_commit_flow(commit_message: &str, force: bool, review: bool) {
+fn legacy_commit_flow(commit_message: &str, force: bool, options: &git::CommitOptions) {
     println!(
         "\n{}\n---\n{}\n---",
         "Proposed Commit:".bold(),
@@ -374,15 +1670,15 @@ fn legacy_commit_flow(commit_message: &str, force: bool, review: bool) {
         }
     }
 
-    if let Err(e) = execute_git_commit(commit_message, review) {
+    if let Err(e) = execute_git_commit(commit_message, options) {
         eprintln!("{} {}", "Error during commit:".red().bold(), e);
         std::process::exit(1);
     }
 }
 
 /// Execute git commit with the given message
-fn execute_git_commit(message: &str, review: bool) -> Result<(), String> {
-    git::commit(message, review)
+fn execute_git_commit(message: &str, options: &git::CommitOptions) -> Result<(), String> {
+    git::commit_with(message, options)
 }
 
 fn show_config() {
@@ -445,6 +1741,22 @@ fn show_config() {
                 }
             }
 
+            // Style examples
+            println!("\n📜 Style Examples:");
+            if config.history.examples == 0 {
+                println!("  {}", "Disabled".yellow());
+            } else {
+                println!(
+                    "  Last {} commit subjects{}",
+                    config.history.examples.to_string().cyan(),
+                    if config.history.filter_paths {
+                        ", preferring commits to the changed files"
+                    } else {
+                        ""
+                    }
+                );
+            }
+
             println!("\n💡 Run '{}' to reconfigure", "commitcraft setup".bold());
         }
         Err(e) => {
@@ -457,25 +1769,55 @@ fn show_config() {
     }
 }
 
-fn list_providers_and_models() {
+async fn list_providers_and_models(refresh: bool) {
+    let config = config::load_config().unwrap_or_default();
+    let mut cache = models::ModelCache::load();
+    let now = models::unix_now();
+
     println!("{}", "🤖 Available Providers & Models".bold().cyan());
     println!("{}", "─".repeat(50));
 
-    println!("\n{}:", "OpenAI".bold().green());
-    println!("  • gpt-4o (latest, most capable)");
-    println!("  • gpt-4o-mini (fast and efficient)");
-    println!("  • gpt-4-turbo");
-    println!("  • gpt-3.5-turbo");
+    for provider_name in models::PROVIDERS {
+        let (discovered, source, error) =
+            discover_models(&config, &mut cache, provider_name, refresh, now).await;
 
-    println!("\n{}:", "Google Gemini".bold().blue());
-    println!("  • gemini-1.5-pro-latest (most capable)");
-    println!("  • gemini-1.5-flash-latest (fast, default)");
-    println!("  • gemini-1.0-pro");
+        let label = match provider_name {
+            "openai" => "OpenAI".bold().green(),
+            "gemini" => "Google Gemini".bold().blue(),
+            _ => "Anthropic Claude".bold().purple(),
+        };
+        println!("\n{} {}:", label, format!("({})", source).dimmed());
+        if let Some(error) = error {
+            println!(
+                "  {}",
+                format!("⚠ Could not query models: {}", error).yellow()
+            );
+        }
 
-    println!("\n{}:", "Anthropic Claude".bold().purple());
-    println!("  • claude-3-5-sonnet-20241022 (latest, most capable)");
-    println!("  • claude-3-haiku-20240307 (fast, default)");
-    println!("  • claude-3-opus-20240229 (most powerful)");
+        let default_model = config.default_model(provider_name).map(String::as_str);
+        for model in
+            models::merge_models(provider_name, &discovered, default_model, &config.aliases)
+        {
+            let mut line = format!("  • {}", model.id);
+            if let Some(description) = model.description {
+                line.push_str(&format!(" ({})", description));
+            }
+            if model.is_default {
+                line.push_str(&format!(" {}", "[default]".green()));
+            }
+            if !model.aliases.is_empty() {
+                line.push_str(&format!(
+                    " {}",
+                    format!("[alias: {}]", model.aliases.join(", ")).yellow()
+                ));
+            }
+            println!("{}", line);
+        }
+    }
+
+    if let Err(e) = cache.save() {
+        log::warn!("{}", e);
+    }
 
     println!("\n{}:", "Usage Examples".bold().yellow());
     println!("  commitcraft --provider openai --model gpt-4o");
@@ -484,3 +1826,106 @@ fn list_providers_and_models() {
 
     println!("\n💡 Set up aliases with '{}'", "commitcraft setup".bold());
 }
+
+/// Finds the models available for a provider, preferring a fresh cache entry,
+/// then the provider's models endpoint, then a stale cache entry.
+async fn discover_models(
+    config: &config::Config,
+    cache: &mut models::ModelCache,
+    provider_name: &str,
+    refresh: bool,
+    now: u64,
+) -> (Vec<String>, models::ModelSource, Option<String>) {
+    if !refresh {
+        if let Some(cached) = cache.fresh(provider_name, now, models::CACHE_TTL_SECS) {
+            return (cached.to_vec(), models::ModelSource::Cached, None);
+        }
+    }
+
+    let Some(api_key) = config.api_key(provider_name) else {
+        return (Vec::new(), models::ModelSource::Fallback, None);
+    };
+    let model_name = config
+        .default_model(provider_name)
+        .cloned()
+        .unwrap_or_default();
+
+    let result = match build_provider(
+        provider_name,
+        api_key.clone(),
+        &model_name,
+        GenerationSettings::default(),
+        &config.network,
+    ) {
+        Ok(provider) => provider.list_models().await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(discovered) => {
+            cache.store(provider_name, discovered.clone(), now);
+            (discovered, models::ModelSource::Live, None)
+        }
+        Err(e) => match cache.any(provider_name) {
+            Some(stale) => (stale.to_vec(), models::ModelSource::Stale, Some(e)),
+            None => (Vec::new(), models::ModelSource::Fallback, Some(e)),
+        },
+    }
+}
+
+async fn run_doctor(json: bool, offline: bool) {
+    let report = doctor::run(!offline).await;
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                eprintln!(
+                    "{} Failed to serialize report: {}",
+                    "Error:".red().bold(),
+                    e
+                );
+                std::process::exit(1);
+            }
+        }
+    } else {
+        println!("{}", "🩺 CommitCraft Doctor".bold().cyan());
+        println!("{}", "─".repeat(50));
+
+        let mut category = "";
+        for check in &report.checks {
+            if check.category != category {
+                category = check.category;
+                println!("\n{}:", category.to_uppercase().bold());
+            }
+            let symbol = match check.status {
+                doctor::CheckStatus::Ok => "✓".green(),
+                doctor::CheckStatus::Warn => "!".yellow(),
+                doctor::CheckStatus::Fail => "✗".red(),
+                doctor::CheckStatus::Skip => "-".dimmed(),
+            };
+            let latency = check
+                .latency_ms
+                .map(|ms| format!(" ({} ms)", ms).dimmed().to_string())
+                .unwrap_or_default();
+            println!(
+                "  {} {}: {}{}",
+                symbol,
+                check.name.bold(),
+                check.detail,
+                latency
+            );
+        }
+
+        println!();
+        if report.has_failures() {
+            println!("{}", "Some checks failed.".red().bold());
+        } else {
+            println!("{}", "Everything looks good!".green().bold());
+        }
+    }
+
+    if report.has_failures() {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How long discovered model lists are reused before querying the API again.
+pub const CACHE_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// Providers in the order they are listed.
+pub const PROVIDERS: [&str; 3] = ["openai", "gemini", "anthropic"];
+
+/// A model bundled with the binary, used when the API can't be reached.
+pub struct CatalogEntry {
+    pub provider: &'static str,
+    pub model: &'static str,
+    pub description: &'static str,
+}
+
+/// Fallback catalogue of well-known models.
+pub const FALLBACK_CATALOG: &[CatalogEntry] = &[
+    CatalogEntry {
+        provider: "openai",
+        model: "gpt-4.1-nano",
+        description: "fastest and cheapest",
+    },
+    CatalogEntry {
+        provider: "openai",
+        model: "gpt-4.1-mini",
+        description: "fast and capable",
+    },
+    CatalogEntry {
+        provider: "openai",
+        model: "gpt-4.1",
+        description: "most capable",
+    },
+    CatalogEntry {
+        provider: "openai",
+        model: "gpt-4o",
+        description: "multimodal flagship",
+    },
+    CatalogEntry {
+        provider: "openai",
+        model: "gpt-4o-mini",
+        description: "fast and efficient",
+    },
+    CatalogEntry {
+        provider: "gemini",
+        model: "gemini-1.5-flash-latest",
+        description: "fast and efficient",
+    },
+    CatalogEntry {
+        provider: "gemini",
+        model: "gemini-1.5-pro-latest",
+        description: "most capable",
+    },
+    CatalogEntry {
+        provider: "gemini",
+        model: "gemini-2.0-flash",
+        description: "next-generation flash",
+    },
+    CatalogEntry {
+        provider: "anthropic",
+        model: "claude-3-5-haiku-20241022",
+        description: "fastest Claude",
+    },
+    CatalogEntry {
+        provider: "anthropic",
+        model: "claude-3-5-sonnet-20241022",
+        description: "balanced, highly capable",
+    },
+    CatalogEntry {
+        provider: "anthropic",
+        model: "claude-3-opus-20240229",
+        description: "most powerful Claude 3",
+    },
+];
+
+/// Where a provider's model list came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelSource {
+    /// Queried from the provider's models endpoint just now.
+    Live,
+    /// Reused from a cache entry younger than the TTL.
+    Cached,
+    /// Reused from an expired cache entry because the API call failed.
+    Stale,
+    /// Only the bundled catalogue is available.
+    Fallback,
+}
+
+impl std::fmt::Display for ModelSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ModelSource::Live => "live",
+            ModelSource::Cached => "cached",
+            ModelSource::Stale => "stale cache",
+            ModelSource::Fallback => "bundled list",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+/// A model as shown by `commitcraft list`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedModel {
+    pub id: String,
+    pub description: Option<&'static str>,
+    pub is_default: bool,
+    pub aliases: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CachedModels {
+    /// Unix timestamp (seconds) of the successful fetch.
+    pub fetched_at: u64,
+    pub models: Vec<String>,
+}
+
+/// On-disk cache of model lists discovered per provider.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct ModelCache {
+    #[serde(default)]
+    pub providers: HashMap<String, CachedModels>,
+}
+
+impl ModelCache {
+    fn path() -> Result<PathBuf, String> {
+        let dirs = directories::ProjectDirs::from("com", "commitcraft", "commitcraft")
+            .ok_or("Could not determine cache directory.")?;
+        Ok(dirs.cache_dir().join("models.json"))
+    }
+
+    /// Loads the cache, treating a missing or unreadable file as empty.
+    pub fn load() -> Self {
+        Self::path()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create cache directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize model cache: {}", e))?;
+        fs::write(&path, json).map_err(|e| format!("Failed to write model cache: {}", e))
+    }
+
+    /// Returns the cached models for `provider` if they are younger than `ttl_secs`.
+    pub fn fresh(&self, provider: &str, now: u64, ttl_secs: u64) -> Option<&[String]> {
+        self.providers
+            .get(provider)
+            .filter(|cached| now.saturating_sub(cached.fetched_at) < ttl_secs)
+            .map(|cached| cached.models.as_slice())
+    }
+
+    /// Returns the cached models for `provider` regardless of age.
+    pub fn any(&self, provider: &str) -> Option<&[String]> {
+        self.providers
+            .get(provider)
+            .map(|cached| cached.models.as_slice())
+    }
+
+    pub fn store(&mut self, provider: &str, models: Vec<String>, now: u64) {
+        self.providers.insert(
+            provider.to_string(),
+            CachedModels {
+                fetched_at: now,
+                models,
+            },
+        );
+    }
+}
+
+/// Current Unix time in seconds.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Merges discovered models with the bundled catalogue for one provider and
+/// marks the configured default model and any aliases pointing at a model.
+///
+/// Catalogue models come first in catalogue order, followed by the remaining
+/// discovered models sorted by id. The configured default is always listed.
+pub fn merge_models(
+    provider: &str,
+    discovered: &[String],
+    default_model: Option<&str>,
+    aliases: &HashMap<String, String>,
+) -> Vec<ListedModel> {
+    let mut ids: Vec<(String, Option<&'static str>)> = FALLBACK_CATALOG
+        .iter()
+        .filter(|entry| entry.provider == provider)
+        .map(|entry| (entry.model.to_string(), Some(entry.description)))
+        .collect();
+
+    let mut extra: Vec<&String> = discovered
+        .iter()
+        .filter(|id| !ids.iter().any(|(known, _)| known == *id))
+        .collect();
+    extra.sort();
+    extra.dedup();
+    ids.extend(extra.into_iter().map(|id| (id.clone(), None)));
+
+    if let Some(default_model) = default_model {
+        if !ids.iter().any(|(id, _)| id == default_model) {
+            ids.push((default_model.to_string(), None));
+        }
+    }
+
+    ids.into_iter()
+        .map(|(id, description)| {
+            let mut model_aliases: Vec<String> = aliases
+                .iter()
+                .filter(|(_, target)| **target == id)
+                .map(|(alias, _)| alias.clone())
+                .collect();
+            model_aliases.sort();
+            ListedModel {
+                is_default: default_model == Some(id.as_str()),
+                aliases: model_aliases,
+                description,
+                id,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_models_fallback_only() {
+        let models = merge_models(
+            "anthropic",
+            &[],
+            Some("claude-3-5-haiku-20241022"),
+            &HashMap::new(),
+        );
+        assert_eq!(models[0].id, "claude-3-5-haiku-20241022");
+        assert!(models[0].is_default);
+        assert!(models.iter().skip(1).all(|model| !model.is_default));
+    }
+
+    #[test]
+    fn test_merge_models_adds_discovered_and_marks_aliases() {
+        let discovered = vec![
+            "gpt-4o".to_string(),
+            "o3-mini".to_string(),
+            "gpt-5".to_string(),
+        ];
+        let mut aliases = HashMap::new();
+        aliases.insert("smart".to_string(), "gpt-4o".to_string());
+        aliases.insert("fast".to_string(), "gemini-1.5-flash-latest".to_string());
+
+        let models = merge_models("openai", &discovered, Some("gpt-4.1-nano"), &aliases);
+        let ids: Vec<&str> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "gpt-4.1-nano",
+                "gpt-4.1-mini",
+                "gpt-4.1",
+                "gpt-4o",
+                "gpt-4o-mini",
+                "gpt-5",
+                "o3-mini"
+            ]
+        );
+        let gpt_4o = models.iter().find(|model| model.id == "gpt-4o").unwrap();
+        assert_eq!(gpt_4o.aliases, vec!["smart".to_string()]);
+        assert!(models
+            .iter()
+            .all(|model| !model.aliases.contains(&"fast".to_string())));
+    }
+
+    #[test]
+    fn test_merge_models_keeps_unknown_default() {
+        let models = merge_models("gemini", &[], Some("gemini-custom"), &HashMap::new());
+        let last = models.last().unwrap();
+        assert_eq!(last.id, "gemini-custom");
+        assert!(last.is_default);
+    }
+
+    #[test]
+    fn test_model_cache_ttl() {
+        let mut cache = ModelCache::default();
+        cache.store("openai", vec!["gpt-4o".to_string()], 1_000);
+        assert!(cache
+            .fresh("openai", 1_000 + CACHE_TTL_SECS - 1, CACHE_TTL_SECS)
+            .is_some());
+        assert!(cache
+            .fresh("openai", 1_000 + CACHE_TTL_SECS, CACHE_TTL_SECS)
+            .is_none());
+        assert_eq!(cache.any("openai"), Some(&["gpt-4o".to_string()][..]));
+        assert!(cache.any("gemini").is_none());
+    }
+
+    #[test]
+    fn test_model_cache_roundtrip() {
+        let mut cache = ModelCache::default();
+        cache.store("gemini", vec!["gemini-2.0-flash".to_string()], 42);
+        let json = serde_json::to_string(&cache).unwrap();
+        let loaded: ModelCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.providers["gemini"].fetched_at, 42);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::git::LoggedCommit;
+use crate::providers::GeneratedCommit;
+use crate::review;
+
+/// The most merged commits a merge message lists.
+const MAX_MERGED_COMMITS: usize = 20;
+
+/// The message for reverting `commit`, whose message was `message`: its
+/// header after `revert: `, with git's reference to the hash in the body.
+/// Reverting a revert brings the original header back.
+pub fn revert_message(commit: &str, message: &str) -> GeneratedCommit {
+    let header = message.lines().next().unwrap_or_default().trim();
+    let title = match header.strip_prefix("revert: ") {
+        Some(original) => original.to_string(),
+        None => format!("revert: {}", header),
+    };
+    GeneratedCommit {
+        title,
+        description: format!("This reverts commit {}.", commit),
+        usage: None,
+    }
+}
+
+/// A message git prepared in `MERGE_MSG`, e.g. the picked commit's message
+/// during a cherry-pick.
+pub fn prepared_message(prepared: &str) -> GeneratedCommit {
+    let (title, description) = review::split_message(&review::strip_comments(prepared));
+    GeneratedCommit {
+        title,
+        description,
+        usage: None,
+    }
+}
+
+/// A merge message: git's prepared title, the commits the merge brings in
+/// (newest first, as logged) and the conflicts that were resolved.
+pub fn merge_message(prepared: &str, merged: &[LoggedCommit]) -> GeneratedCommit {
+    let stripped = review::strip_comments(prepared);
+    let title = stripped
+        .trim()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    let mut sections = Vec::new();
+    if !merged.is_empty() {
+        let mut section = String::from("Merged commits:");
+        for commit in merged.iter().rev().take(MAX_MERGED_COMMITS) {
+            let subject = commit.message.lines().next().unwrap_or_default();
+            section.push_str(&format!("\n- {} ({})", subject, commit.hash));
+        }
+        if merged.len() > MAX_MERGED_COMMITS {
+            section.push_str(&format!(
+                "\n- and {} more",
+                merged.len() - MAX_MERGED_COMMITS
+            ));
+        }
+        sections.push(section);
+    }
+    let conflicts = conflicts(prepared);
+    if !conflicts.is_empty() {
+        let mut section = String::from("Conflicts resolved:");
+        for path in conflicts {
+            section.push_str(&format!("\n- {}", path));
+        }
+        sections.push(section);
+    }
+
+    GeneratedCommit {
+        title,
+        description: sections.join("\n\n"),
+        usage: None,
+    }
+}
+
+/// The paths git listed under `Conflicts:` in a prepared message. Recent
+/// versions comment the list out, older ones don't.
+pub fn conflicts(prepared: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut in_list = false;
+    for line in prepared.lines() {
+        let uncommented = line.strip_prefix('#').unwrap_or(line);
+        if uncommented.trim() == "Conflicts:" {
+            in_list = true;
+        } else if in_list {
+            let path = uncommented.trim();
+            if uncommented.starts_with('\t') && !path.is_empty() {
+                paths.push(path.to_string());
+            } else if !path.is_empty() {
+                in_list = false;
+            }
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MERGE_MSG: &str = "Merge branch 'feature'\n\n# Conflicts:\n#\tsrc/lib.rs\n#\tREADME.md\n#\n# It looks like you may be committing a merge.\n";
+
+    #[test]
+    fn test_revert_message() {
+        let revert = revert_message("1a2b3c", "feat(api): add refunds\n\nDetails.");
+        assert_eq!(revert.title, "revert: feat(api): add refunds");
+        assert_eq!(revert.description, "This reverts commit 1a2b3c.");
+        assert!(revert.validate().is_ok());
+
+        let reapply = revert_message("4d5e6f", &revert.title);
+        assert_eq!(reapply.title, "feat(api): add refunds");
+    }
+
+    #[test]
+    fn test_conflicts() {
+        assert_eq!(conflicts(MERGE_MSG), vec!["src/lib.rs", "README.md"]);
+        // Older git versions don't comment the list out
+        assert_eq!(
+            conflicts("Merge branch 'feature'\n\nConflicts:\n\tsrc/lib.rs\n"),
+            vec!["src/lib.rs"]
+        );
+        assert!(conflicts("Merge branch 'feature'\n").is_empty());
+    }
+
+    #[test]
+    fn test_merge_message() {
+        let merged = vec![
+            LoggedCommit {
+                hash: "bbb".to_string(),
+                message: "fix: handle errors\n\nDetails.".to_string(),
+            },
+            LoggedCommit {
+                hash: "aaa".to_string(),
+                message: "feat: add refunds".to_string(),
+            },
+        ];
+        let merge = merge_message(MERGE_MSG, &merged);
+        assert_eq!(merge.title, "Merge branch 'feature'");
+        assert_eq!(
+            merge.description,
+            "Merged commits:\n- feat: add refunds (aaa)\n- fix: handle errors (bbb)\n\nConflicts resolved:\n- src/lib.rs\n- README.md"
+        );
+
+        let merge = merge_message("Merge branch 'feature'\n", &[]);
+        assert_eq!(merge.description, "");
+    }
+
+    #[test]
+    fn test_prepared_message() {
+        let prepared = prepared_message(
+            "fix: handle errors\n\nDetails.\n\n(cherry picked from commit 1a2b3c)\n\n# Conflicts:\n#\tsrc/lib.rs\n",
+        );
+        assert_eq!(prepared.title, "fix: handle errors");
+        assert_eq!(
+            prepared.description,
+            "Details.\n\n(cherry picked from commit 1a2b3c)"
+        );
+    }
+}
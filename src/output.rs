@@ -0,0 +1,208 @@
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::conventional::{self, Footer};
+use crate::providers::{GeneratedCommit, TokenUsage};
+use crate::review;
+
+/// Result of checking a message against the Conventional Commits rules.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Validation {
+    pub valid: bool,
+    pub errors: Vec<String>,
+}
+
+/// A generated message broken into its Conventional Commits parts.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct CommitOutput {
+    /// `None` when the title is not a conventional commit header.
+    #[serde(rename = "type")]
+    pub commit_type: Option<String>,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub subject: String,
+    pub body: Option<String>,
+    pub footers: Vec<Footer>,
+    /// The full message exactly as it would be committed.
+    pub message: String,
+    pub validation: Validation,
+}
+
+impl CommitOutput {
+    pub fn from_commit(commit: &GeneratedCommit) -> Self {
+        let message = review::compose_message(&commit.title, &commit.description);
+        let validation = match commit.validate() {
+            Ok(()) => Validation {
+                valid: true,
+                errors: Vec::new(),
+            },
+            Err(e) => Validation {
+                valid: false,
+                errors: vec![e],
+            },
+        };
+
+        match conventional::parse(&message) {
+            Ok(parsed) => CommitOutput {
+                commit_type: Some(parsed.commit_type),
+                scope: parsed.scope,
+                breaking: parsed.breaking,
+                subject: parsed.subject,
+                body: parsed.body,
+                footers: parsed.footers,
+                message,
+                validation,
+            },
+            Err(_) => {
+                let (body, footers) = conventional::split_footers(&commit.description);
+                CommitOutput {
+                    commit_type: None,
+                    scope: None,
+                    breaking: false,
+                    subject: commit.title.trim().to_string(),
+                    body,
+                    footers,
+                    message,
+                    validation,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct UsageOutput {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Timing {
+    pub generation_ms: u128,
+}
+
+/// The single object printed by `--output json`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct JsonOutput {
+    pub provider: String,
+    pub model: String,
+    /// The first candidate, which non-interactive modes commit.
+    pub commit: CommitOutput,
+    /// Every de-duplicated candidate, including the first.
+    pub candidates: Vec<CommitOutput>,
+    /// Tokens used across all requests, if the provider reported them.
+    pub usage: Option<UsageOutput>,
+    pub timing: Timing,
+}
+
+impl JsonOutput {
+    pub fn new(
+        provider: &str,
+        model: &str,
+        candidates: &[GeneratedCommit],
+        generation_time: Duration,
+    ) -> Result<Self, String> {
+        let first = candidates
+            .first()
+            .ok_or("No commit message was generated")?;
+        let usage =
+            TokenUsage::sum(candidates.iter().filter_map(|c| c.usage.as_ref())).map(|usage| {
+                UsageOutput {
+                    input_tokens: usage.input_tokens,
+                    output_tokens: usage.output_tokens,
+                    total_tokens: usage.total_tokens(),
+                }
+            });
+
+        Ok(JsonOutput {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            commit: CommitOutput::from_commit(first),
+            candidates: candidates.iter().map(CommitOutput::from_commit).collect(),
+            usage,
+            timing: Timing {
+                generation_ms: generation_time.as_millis(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit(title: &str, description: &str, usage: Option<TokenUsage>) -> GeneratedCommit {
+        GeneratedCommit {
+            title: title.to_string(),
+            description: description.to_string(),
+            usage,
+        }
+    }
+
+    #[test]
+    fn test_commit_output_structure() {
+        let output = CommitOutput::from_commit(&commit(
+            "feat(api)!: drop v1 endpoints",
+            "Removes the old endpoints.\n\nBREAKING CHANGE: v1 clients must upgrade\nRefs: #42",
+            None,
+        ));
+        assert_eq!(output.commit_type.as_deref(), Some("feat"));
+        assert_eq!(output.scope.as_deref(), Some("api"));
+        assert!(output.breaking);
+        assert_eq!(output.subject, "drop v1 endpoints");
+        assert_eq!(output.body.as_deref(), Some("Removes the old endpoints."));
+        assert_eq!(output.footers.len(), 2);
+        assert!(output.validation.valid);
+        assert!(output
+            .message
+            .starts_with("feat(api)!: drop v1 endpoints\n\n"));
+    }
+
+    #[test]
+    fn test_commit_output_invalid_title() {
+        let output = CommitOutput::from_commit(&commit("Update stuff", "Body", None));
+        assert_eq!(output.commit_type, None);
+        assert_eq!(output.subject, "Update stuff");
+        assert!(!output.validation.valid);
+        assert_eq!(output.validation.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_json_output() {
+        let usage = TokenUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+        };
+        let candidates = vec![
+            commit("fix: a", "", Some(usage)),
+            commit("fix: b", "", Some(usage)),
+        ];
+        let output =
+            JsonOutput::new("openai", "gpt-4o", &candidates, Duration::from_millis(1500)).unwrap();
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["provider"], "openai");
+        assert_eq!(json["commit"]["type"], "fix");
+        assert_eq!(json["commit"]["subject"], "a");
+        assert_eq!(json["candidates"].as_array().unwrap().len(), 2);
+        assert_eq!(json["usage"]["total_tokens"], 30);
+        assert_eq!(json["timing"]["generation_ms"], 1500);
+
+        assert!(JsonOutput::new("openai", "gpt-4o", &[], Duration::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_json_output_without_usage() {
+        let output = JsonOutput::new(
+            "gemini",
+            "model",
+            &[commit("fix: a", "", None)],
+            Duration::ZERO,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_value(&output).unwrap()["usage"],
+            serde_json::Value::Null
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::conventional;
+
+/// The file in the git directory that holds the current pairing session.
+pub const SESSION_FILE: &str = "commitcraft-pair";
+
+const TRAILER: &str = "Co-authored-by";
+
+/// Someone credited with a `Co-authored-by` trailer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoAuthor {
+    pub name: String,
+    pub email: String,
+}
+
+impl CoAuthor {
+    /// Parses `Name <email>`.
+    pub fn parse(identity: &str) -> Result<Self, String> {
+        let invalid = || format!("'{}' is not a valid 'Name <email>'", identity.trim());
+        let (name, rest) = identity.trim().split_once('<').ok_or_else(invalid)?;
+        let email = rest.strip_suffix('>').ok_or_else(invalid)?.trim();
+        let name = name.trim();
+        let valid_email = email
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && !domain.is_empty())
+            && !email.contains(|c: char| c.is_whitespace() || "<>".contains(c));
+        if name.is_empty() || !valid_email || name.contains(['<', '>']) {
+            return Err(invalid());
+        }
+        Ok(CoAuthor {
+            name: name.to_string(),
+            email: email.to_string(),
+        })
+    }
+
+    fn same_person(&self, email: &str) -> bool {
+        self.email.eq_ignore_ascii_case(email.trim())
+    }
+}
+
+impl fmt::Display for CoAuthor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+/// Resolves roster nicknames, or `Name <email>` for people who aren't in
+/// the roster, into co-authors without duplicates.
+pub fn resolve(
+    names: &[String],
+    roster: &HashMap<String, String>,
+) -> Result<Vec<CoAuthor>, String> {
+    let mut coauthors: Vec<CoAuthor> = Vec::new();
+    for name in names
+        .iter()
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+    {
+        let coauthor = if name.contains('<') {
+            CoAuthor::parse(name)?
+        } else {
+            let identity = roster
+                .get(name)
+                .or_else(|| {
+                    roster
+                        .iter()
+                        .find(|(nickname, _)| nickname.eq_ignore_ascii_case(name))
+                        .map(|(_, identity)| identity)
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown co-author '{}'. Add it to [coauthors] in the config or use 'Name <email>'.",
+                        name
+                    )
+                })?;
+            CoAuthor::parse(identity)
+                .map_err(|e| format!("Invalid [coauthors] entry '{}': {}", name, e))?
+        };
+        if !coauthors
+            .iter()
+            .any(|known| known.same_person(&coauthor.email))
+        {
+            coauthors.push(coauthor);
+        }
+    }
+    Ok(coauthors)
+}
+
+/// Reads the co-authors of the pairing session at `path`, if one is running.
+pub fn load_session(path: &Path) -> Result<Vec<CoAuthor>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read the pairing session: {}", e))?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(CoAuthor::parse)
+        .collect()
+}
+
+/// Starts a pairing session at `path` with `coauthors`, replacing any other.
+pub fn save_session(path: &Path, coauthors: &[CoAuthor]) -> Result<(), String> {
+    let content: String = coauthors
+        .iter()
+        .map(|coauthor| format!("{}\n", coauthor))
+        .collect();
+    fs::write(path, content).map_err(|e| format!("Failed to save the pairing session: {}", e))
+}
+
+/// The co-authors credited on the commits of this run.
+#[derive(Debug, Clone, Default)]
+pub struct Pairing {
+    pub coauthors: Vec<CoAuthor>,
+}
+
+impl Pairing {
+    /// Combines the session and `--pair` co-authors, leaving out the
+    /// committer, whose email is `own_email`.
+    pub fn new(session: Vec<CoAuthor>, pair: Vec<CoAuthor>, own_email: Option<&str>) -> Self {
+        let mut coauthors: Vec<CoAuthor> = Vec::new();
+        for coauthor in session.into_iter().chain(pair) {
+            let own = own_email.is_some_and(|email| coauthor.same_person(email));
+            if !own
+                && !coauthors
+                    .iter()
+                    .any(|known| known.same_person(&coauthor.email))
+            {
+                coauthors.push(coauthor);
+            }
+        }
+        Pairing { coauthors }
+    }
+
+    /// Adds a `Co-authored-by` trailer for each co-author `description`
+    /// doesn't credit yet.
+    pub fn apply(&self, description: &mut String) {
+        let (_, footers) = conventional::split_footers(description);
+        for coauthor in &self.coauthors {
+            let credited = footers.iter().any(|footer| {
+                footer.token.eq_ignore_ascii_case(TRAILER)
+                    && CoAuthor::parse(&footer.value)
+                        .is_ok_and(|known| known.same_person(&coauthor.email))
+            });
+            if !credited {
+                *description =
+                    conventional::append_footer(description, &format!("{}: {}", TRAILER, coauthor));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roster() -> HashMap<String, String> {
+        HashMap::from([
+            (
+                "alice".to_string(),
+                "Alice Smith <alice@example.com>".to_string(),
+            ),
+            ("bob".to_string(), "Bob Jones <bob@example.com>".to_string()),
+            ("broken".to_string(), "Bob".to_string()),
+        ])
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_co_author() {
+        let coauthor = CoAuthor::parse(" Alice Smith <alice@example.com> ").unwrap();
+        assert_eq!(coauthor.name, "Alice Smith");
+        assert_eq!(coauthor.email, "alice@example.com");
+        assert_eq!(coauthor.to_string(), "Alice Smith <alice@example.com>");
+        for invalid in [
+            "Alice",
+            "<alice@example.com>",
+            "Alice <alice>",
+            "Alice <alice@example.com",
+            "Alice <a b@example.com>",
+        ] {
+            assert!(CoAuthor::parse(invalid).is_err(), "accepted {:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let coauthors = resolve(
+            &names(&["alice", "Bob", "Carol <carol@example.com>", "ALICE"]),
+            &roster(),
+        )
+        .unwrap();
+        assert_eq!(
+            coauthors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "Alice Smith <alice@example.com>",
+                "Bob Jones <bob@example.com>",
+                "Carol <carol@example.com>",
+            ]
+        );
+        assert!(resolve(&names(&["dave"]), &roster())
+            .unwrap_err()
+            .contains("Unknown co-author 'dave'"));
+        assert!(resolve(&names(&["broken"]), &roster())
+            .unwrap_err()
+            .contains("[coauthors] entry 'broken'"));
+    }
+
+    #[test]
+    fn test_pairing_skips_committer_and_duplicates() {
+        let all = resolve(&names(&["alice", "bob"]), &roster()).unwrap();
+        let pairing = Pairing::new(all.clone(), all[..1].to_vec(), Some("BOB@example.com"));
+        assert_eq!(pairing.coauthors, all[..1].to_vec());
+    }
+
+    #[test]
+    fn test_apply() {
+        let pairing = Pairing {
+            coauthors: resolve(&names(&["alice", "bob"]), &roster()).unwrap(),
+        };
+        let mut description = "Adds refunds.\n\nRefs: PAY-1".to_string();
+        pairing.apply(&mut description);
+        assert_eq!(
+            description,
+            "Adds refunds.\n\nRefs: PAY-1\nCo-authored-by: Alice Smith <alice@example.com>\nCo-authored-by: Bob Jones <bob@example.com>"
+        );
+        pairing.apply(&mut description);
+        assert_eq!(description.matches(TRAILER).count(), 2);
+
+        let mut description = "co-authored-by: Alice S. <ALICE@example.com>".to_string();
+        pairing.apply(&mut description);
+        assert_eq!(
+            description,
+            "co-authored-by: Alice S. <ALICE@example.com>\nCo-authored-by: Bob Jones <bob@example.com>"
+        );
+
+        let mut empty = String::new();
+        Pairing::default().apply(&mut empty);
+        assert_eq!(empty, "");
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs;
+use std::path::Path;
+
+use crate::git::LoggedCommit;
+
+/// Where GitHub looks for a pull request template, relative to the
+/// repository root. Names are matched case-insensitively.
+const TEMPLATE_PATHS: &[&str] = &[
+    ".github/pull_request_template.md",
+    "pull_request_template.md",
+    "docs/pull_request_template.md",
+];
+
+/// The headings of a body when the repository has no template.
+pub const DEFAULT_HEADINGS: &[&str] = &["## Summary", "## Changes", "## Testing"];
+
+/// Reads the pull request template of the repository at `root`, if any.
+pub fn find_template(root: &Path) -> Option<String> {
+    TEMPLATE_PATHS.iter().find_map(|path| {
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        let entries = fs::read_dir(root.join(dir)).ok()?;
+        entries
+            .filter_map(Result::ok)
+            .find(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .eq_ignore_ascii_case(name)
+            })
+            .and_then(|entry| fs::read_to_string(entry.path()).ok())
+    })
+}
+
+/// The markdown heading lines of a template, outside code blocks.
+pub fn template_headings(template: &str) -> Vec<String> {
+    let mut in_code = false;
+    let mut headings = Vec::new();
+    for line in template.lines() {
+        let line = line.trim_end();
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code && heading_text(line).is_some() {
+            headings.push(line.to_string());
+        }
+    }
+    headings
+}
+
+/// The text of a `## Heading` line, lowercased for comparison.
+fn heading_text(line: &str) -> Option<String> {
+    let text = line.trim_start_matches('#');
+    let level = line.len() - text.len();
+    ((1..=6).contains(&level) && text.starts_with(' '))
+        .then(|| text.trim().trim_end_matches('#').trim().to_lowercase())
+}
+
+/// The request for [`crate::AIProvider::generate_pull_request`].
+pub fn prompt(
+    commits: &[LoggedCommit],
+    headings: &[String],
+    template: Option<&str>,
+    diff: &str,
+) -> String {
+    let mut content = String::from("Commits on the branch, oldest first:\n");
+    for commit in commits.iter().rev() {
+        content.push_str(&format!("- {}\n", commit.message.replace('\n', "\n  ")));
+    }
+    content.push_str("\nUse these headings in the body:\n");
+    for heading in headings {
+        content.push_str(&format!("{}\n", heading));
+    }
+    if let Some(template) = template {
+        content.push_str(&format!(
+            "\nThe repository's pull request template, whose instructions and checklists apply:\n```markdown\n{}\n```\n",
+            template.trim()
+        ));
+    }
+    content.push_str(&format!(
+        "\nHere is the combined diff against the base branch:\n```diff\n{}\n```",
+        diff
+    ));
+    content
+}
+
+/// Rearranges `body` so it has exactly `headings`, in order. Sections the
+/// model wrote under the same heading text are kept; missing ones are left
+/// empty for the author, and extra ones follow at the end.
+pub fn fit_template(body: &str, headings: &[String]) -> String {
+    let mut preamble = String::new();
+    let mut sections: Vec<(String, String, String)> = Vec::new();
+    let mut in_code = false;
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        match heading_text(line).filter(|_| !in_code) {
+            Some(text) => sections.push((text, line.trim_end().to_string(), String::new())),
+            None => {
+                let content = match sections.last_mut() {
+                    Some((_, _, content)) => content,
+                    None => &mut preamble,
+                };
+                content.push_str(line);
+                content.push('\n');
+            }
+        }
+    }
+
+    let mut parts = Vec::new();
+    if !preamble.trim().is_empty() {
+        parts.push(preamble.trim().to_string());
+    }
+    for heading in headings {
+        let text = heading_text(heading).unwrap_or_default();
+        let content = sections
+            .iter()
+            .position(|(known, _, _)| *known == text)
+            .map(|index| sections.remove(index).2)
+            .unwrap_or_default();
+        parts.push(
+            format!("{}\n\n{}", heading, content.trim())
+                .trim_end()
+                .to_string(),
+        );
+    }
+    for (_, heading, content) in sections {
+        parts.push(
+            format!("{}\n\n{}", heading, content.trim())
+                .trim_end()
+                .to_string(),
+        );
+    }
+    parts.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headings(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn test_template_headings() {
+        let template = "## What\n<!-- Describe it -->\n\n### How to test\n```\n# not a heading\n```\n#hashtag\n- [ ] Tests\n";
+        assert_eq!(
+            template_headings(template),
+            vec!["## What", "### How to test"]
+        );
+    }
+
+    #[test]
+    fn test_fit_template() {
+        let body = "Adds refunds.\n\n## Testing\n\nRan `cargo test`.\n\n## summary\nRefunds for orders.\n\n## Notes\nFollow-up later.\n";
+        assert_eq!(
+            fit_template(body, &headings(&["## Summary", "## Changes", "## Testing"])),
+            "Adds refunds.\n\n## Summary\n\nRefunds for orders.\n\n## Changes\n\n## Testing\n\nRan `cargo test`.\n\n## Notes\n\nFollow-up later."
+        );
+    }
+
+    #[test]
+    fn test_fit_template_ignores_code_blocks() {
+        let body = "## Summary\n```sh\n# run it\n```\n";
+        assert_eq!(
+            fit_template(body, &headings(&["## Summary"])),
+            "## Summary\n\n```sh\n# run it\n```"
+        );
+    }
+
+    #[test]
+    fn test_prompt_lists_commits_oldest_first() {
+        let commits = vec![
+            LoggedCommit {
+                hash: "b".to_string(),
+                message: "fix: second".to_string(),
+            },
+            LoggedCommit {
+                hash: "a".to_string(),
+                message: "feat: first\n\nDetails.".to_string(),
+            },
+        ];
+        let prompt = prompt(&commits, &headings(&["## Summary"]), None, "diff");
+        assert!(prompt.starts_with(
+            "Commits on the branch, oldest first:\n- feat: first\n  \n  Details.\n- fix: second\n"
+        ));
+        assert!(prompt.contains("Use these headings in the body:\n## Summary\n"));
+        assert!(!prompt.contains("template"));
+    }
+}
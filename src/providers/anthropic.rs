@@ -4,7 +4,11 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::json;
 
-use super::{AIProvider, GeneratedCommit};
+use super::http::{self, NetworkSettings};
+use super::{
+    AIProvider, Conversation, GeneratedCommit, GenerationSettings, PolishedEntries, PullRequest,
+    Role, SplitPlan, TokenUsage, CHANGELOG_SYSTEM_PROMPT, PR_SYSTEM_PROMPT, SPLIT_SYSTEM_PROMPT,
+};
 
 #[derive(Debug, serde::Deserialize, JsonSchema)]
 struct Commit {
@@ -14,49 +18,7 @@ struct Commit {
     description: String,
 }
 
-pub struct AnthropicProvider {
-    client: Client,
-    api_key: String,
-    model: String,
-}
-
-impl AnthropicProvider {
-    pub fn new(api_key: String, model: String) -> Self {
-        Self {
-            client: Client::new(),
-            api_key,
-            model,
-        }
-    }
-}
-
-#[derive(Deserialize)]
-struct AnthropicResponse {
-    content: Vec<ContentBlock>,
-    #[allow(dead_code)]
-    stop_reason: Option<String>,
-}
-
-#[derive(Deserialize)]
-#[serde(tag = "type")]
-enum ContentBlock {
-    #[serde(rename = "text")]
-    Text { text: String },
-    #[serde(rename = "tool_use")]
-    ToolUse {
-        #[allow(dead_code)]
-        id: String,
-        name: String,
-        input: serde_json::Value,
-    },
-}
-
-#[async_trait]
-impl AIProvider for AnthropicProvider {
-    async fn generate_commit_message(&self, diff: &str) -> Result<GeneratedCommit, String> {
-        let url = "https://api.anthropic.com/v1/messages";
-
-        let system_prompt = "You are an expert programmer who writes git commit messages following the Conventional Commits specification (https://www.conventionalcommits.org/en/v1.0.0/).
+const SYSTEM_PROMPT: &str = "You are an expert programmer who writes git commit messages following the Conventional Commits specification (https://www.conventionalcommits.org/en/v1.0.0/).
 
 For the title field:
 - MUST follow this exact format: <type>[optional scope]: <description>
@@ -74,43 +36,147 @@ For the description field:
 
 Analyze the git diff carefully and generate an appropriate conventional commit message using the generate_commit tool. The title MUST be 50 characters or less.";
 
+pub struct AnthropicProvider {
+    client: Client,
+    api_key: String,
+    model: String,
+    settings: GenerationSettings,
+}
+
+impl AnthropicProvider {
+    pub fn new(api_key: String, model: String) -> Self {
+        Self {
+            client: http::build_client(&NetworkSettings::default()).unwrap_or_default(),
+            api_key,
+            model,
+            settings: GenerationSettings::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: GenerationSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Replaces the HTTP client with one built from `network`.
+    pub fn with_network(mut self, network: &NetworkSettings) -> Result<Self, String> {
+        self.client = http::build_client(network)?;
+        Ok(self)
+    }
+
+    /// Builds the JSON request body sent to the API for the given conversation.
+    fn request_body(&self, conversation: &Conversation) -> Result<serde_json::Value, String> {
         // Create the tool schema
         let parameters_schema = serde_json::to_value(schemars::schema_for!(Commit))
             .map_err(|e| format!("Failed to create schema: {}", e))?;
 
-        let body = json!({
+        Ok(self.tool_request_body(
+            SYSTEM_PROMPT,
+            "generate_commit",
+            "Generate a conventional commit message with title and description",
+            parameters_schema,
+            conversation,
+        ))
+    }
+
+    /// Builds a request body that makes the model call the tool `tool_name`.
+    fn tool_request_body(
+        &self,
+        system_prompt: &str,
+        tool_name: &str,
+        tool_description: &str,
+        input_schema: serde_json::Value,
+        conversation: &Conversation,
+    ) -> serde_json::Value {
+        let mut body = json!({
             "model": self.model,
-            "max_tokens": 1024,
-            "temperature": 0.2,
+            "max_tokens": self.settings.max_tokens(),
+            "temperature": self.settings.temperature(),
             "system": system_prompt,
-            "messages": [
-                {
-                    "role": "user",
-                    "content": format!("Here is the git diff to analyze:\n```diff\n{}\n```", diff)
-                }
-            ],
+            "messages": conversation
+                .turns()
+                .into_iter()
+                .map(|turn| json!({
+                    "role": match turn.role {
+                        Role::User => "user",
+                        Role::Assistant => "assistant",
+                    },
+                    "content": turn.content
+                }))
+                .collect::<Vec<_>>(),
             "tools": [
                 {
-                    "name": "generate_commit",
-                    "description": "Generate a conventional commit message with title and description",
-                    "input_schema": parameters_schema
+                    "name": tool_name,
+                    "description": tool_description,
+                    "input_schema": input_schema
                 }
             ],
             "tool_choice": {
                 "type": "tool",
-                "name": "generate_commit"
+                "name": tool_name
             }
         });
+        if let Some(top_p) = self.settings.top_p {
+            body["top_p"] = json!(top_p);
+        }
+        if let Some(stop) = self.settings.stop_sequences() {
+            body["stop_sequences"] = json!(stop);
+        }
 
-        let response = self
+        body
+    }
+
+    /// Sends the conversation with a single tool and parses the input of its
+    /// use as `T`.
+    async fn call_tool<T: serde::de::DeserializeOwned>(
+        &self,
+        system_prompt: &str,
+        tool_name: &str,
+        tool_description: &str,
+        schema: serde_json::Value,
+        conversation: &Conversation,
+    ) -> Result<T, String> {
+        let body = self.tool_request_body(
+            system_prompt,
+            tool_name,
+            tool_description,
+            schema,
+            conversation,
+        );
+        let anthropic_response = self.send(&body).await?;
+
+        anthropic_response
+            .content
+            .into_iter()
+            .find_map(|content_block| match content_block {
+                ContentBlock::ToolUse { name, input, .. } if name == tool_name => Some(input),
+                _ => None,
+            })
+            .ok_or("No valid tool use found in Anthropic response".to_string())
+            .and_then(|input| {
+                serde_json::from_value(input)
+                    .map_err(|e| format!("Failed to parse tool input: {}", e))
+            })
+    }
+
+    /// Sends a request body to the messages API.
+    async fn send(&self, body: &serde_json::Value) -> Result<AnthropicResponse, String> {
+        let url = "https://api.anthropic.com/v1/messages";
+
+        let mut request = self
             .client
             .post(url)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
-            .json(&body)
+            .json(body);
+        if let Some(timeout) = self.settings.timeout() {
+            request = request.timeout(timeout);
+        }
+
+        let response = request
             .send()
             .await
-            .map_err(|e| format!("Anthropic API request failed: {}", e))?;
+            .map_err(|e| http::describe_request_error("Anthropic", e))?;
 
         if !response.status().is_success() {
             let error_body = response
@@ -120,10 +186,64 @@ Analyze the git diff carefully and generate an appropriate conventional commit m
             return Err(format!("Anthropic API returned an error: {}", error_body));
         }
 
-        let anthropic_response: AnthropicResponse = response
+        response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse Anthropic response: {}", e))?;
+            .map_err(|e| format!("Failed to parse Anthropic response: {}", e))
+    }
+}
+
+#[derive(Deserialize)]
+struct AnthropicResponse {
+    content: Vec<ContentBlock>,
+    #[allow(dead_code)]
+    stop_reason: Option<String>,
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    input_tokens: u64,
+    output_tokens: u64,
+}
+
+#[derive(Deserialize)]
+struct ModelsResponse {
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum ContentBlock {
+    #[serde(rename = "text")]
+    Text { text: String },
+    #[serde(rename = "tool_use")]
+    ToolUse {
+        #[allow(dead_code)]
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+}
+
+#[async_trait]
+impl AIProvider for AnthropicProvider {
+    async fn generate_commit_message(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<GeneratedCommit, String> {
+        let body = self.request_body(conversation)?;
+        let anthropic_response = self.send(&body).await?;
+        let usage = anthropic_response.usage.as_ref().map(|usage| TokenUsage {
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+        });
 
         // Look for tool use in the content blocks
         for content_block in &anthropic_response.content {
@@ -135,6 +255,7 @@ Analyze the git diff carefully and generate an appropriate conventional commit m
                     return Ok(GeneratedCommit {
                         title: commit.title,
                         description: commit.description,
+                        usage,
                     });
                 }
             }
@@ -148,6 +269,7 @@ Analyze the git diff carefully and generate an appropriate conventional commit m
                     return Ok(GeneratedCommit {
                         title: commit.title,
                         description: commit.description,
+                        usage,
                     });
                 }
             }
@@ -155,6 +277,71 @@ Analyze the git diff carefully and generate an appropriate conventional commit m
 
         Err("No valid tool use or parseable JSON found in Anthropic response".to_string())
     }
+
+    async fn plan_split(&self, conversation: &Conversation) -> Result<SplitPlan, String> {
+        self.call_tool(
+            SPLIT_SYSTEM_PROMPT,
+            "plan_commits",
+            "Group the numbered hunks into conventional commits",
+            SplitPlan::schema()?,
+            conversation,
+        )
+        .await
+    }
+
+    async fn generate_pull_request(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<PullRequest, String> {
+        self.call_tool(
+            PR_SYSTEM_PROMPT,
+            "write_pull_request",
+            "Write the pull request title and description",
+            PullRequest::schema()?,
+            conversation,
+        )
+        .await
+    }
+
+    async fn polish_changelog(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<PolishedEntries, String> {
+        self.call_tool(
+            CHANGELOG_SYSTEM_PROMPT,
+            "polish_entries",
+            "Rewrite the numbered changelog entries",
+            PolishedEntries::schema()?,
+            conversation,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let response = self
+            .client
+            .get("https://api.anthropic.com/v1/models?limit=1000")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01")
+            .send()
+            .await
+            .map_err(|e| http::describe_request_error("Anthropic", e))?;
+
+        if !response.status().is_success() {
+            let error_body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("Anthropic API returned an error: {}", error_body));
+        }
+
+        let models: ModelsResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Anthropic models response: {}", e))?;
+
+        Ok(models.data.into_iter().map(|model| model.id).collect())
+    }
 }
 
 #[cfg(test)]
@@ -167,6 +354,62 @@ mod tests {
         let provider = AnthropicProvider::new("key".to_string(), "model".to_string());
         assert_eq!(provider.api_key, "key");
         assert_eq!(provider.model, "model");
+        assert_eq!(provider.settings, GenerationSettings::default());
+    }
+
+    #[test]
+    fn test_anthropic_request_body_uses_settings() {
+        let provider = AnthropicProvider::new("key".to_string(), "model".to_string())
+            .with_settings(GenerationSettings {
+                temperature: Some(0.5),
+                top_p: Some(0.9),
+                max_tokens: Some(256),
+                stop: Some(vec!["END".to_string()]),
+                ..Default::default()
+            });
+        let body = provider.request_body(&Conversation::new("diff")).unwrap();
+        assert_eq!(body["temperature"], 0.5);
+        assert_eq!(body["top_p"], 0.9f32 as f64);
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["stop_sequences"][0], "END");
+    }
+
+    #[test]
+    fn test_anthropic_request_body_defaults() {
+        let provider = AnthropicProvider::new("key".to_string(), "model".to_string());
+        let body = provider.request_body(&Conversation::new("diff")).unwrap();
+        assert_eq!(body["temperature"], 0.2f32 as f64);
+        assert_eq!(body["max_tokens"], 1024);
+        assert!(body.get("top_p").is_none());
+        assert!(body.get("stop_sequences").is_none());
+    }
+
+    #[test]
+    fn test_anthropic_request_body_includes_feedback_turns() {
+        let provider = AnthropicProvider::new("key".to_string(), "model".to_string());
+        let mut conversation = Conversation::new("diff");
+        conversation.push_feedback("feat: add x", "shorter");
+        let body = provider.request_body(&conversation).unwrap();
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["role"], "assistant");
+        assert_eq!(messages[1]["content"], "feat: add x");
+        assert_eq!(messages[2]["role"], "user");
+    }
+
+    #[test]
+    fn test_anthropic_split_request_forces_plan_tool() {
+        let provider = AnthropicProvider::new("key".to_string(), "model".to_string());
+        let body = provider.tool_request_body(
+            SPLIT_SYSTEM_PROMPT,
+            "plan_commits",
+            "Group hunks",
+            SplitPlan::schema().unwrap(),
+            &Conversation::new("Hunk 1: src/lib.rs"),
+        );
+        assert_eq!(body["system"], SPLIT_SYSTEM_PROMPT);
+        assert_eq!(body["tools"][0]["name"], "plan_commits");
+        assert_eq!(body["tool_choice"]["name"], "plan_commits");
     }
 
     #[test]
@@ -197,6 +440,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_anthropic_models_response_deserialize() {
+        let json = r#"{
+            "data": [
+                {"type": "model", "id": "claude-3-5-haiku-20241022", "display_name": "Claude Haiku 3.5"}
+            ],
+            "has_more": false
+        }"#;
+        let resp: ModelsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.data[0].id, "claude-3-5-haiku-20241022");
+    }
+
     #[test]
     fn test_anthropic_text_response_deserialize() {
         let json = r#"{
@@ -4,7 +4,16 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_json::json;
 
-use super::{AIProvider, GeneratedCommit};
+use super::http::{self, NetworkSettings};
+use super::{
+    candidate_batches, collect_batches, collect_candidates, AIProvider, Conversation,
+    GeneratedCommit, GenerationSettings, PolishedEntries, PullRequest, Role, SplitPlan, TokenUsage,
+    CHANGELOG_SYSTEM_PROMPT, PR_SYSTEM_PROMPT, SPLIT_SYSTEM_PROMPT,
+};
+
+/// The most candidates one request can ask for; Gemini rejects a larger
+/// `candidate_count`.
+const MAX_CANDIDATES_PER_REQUEST: usize = 8;
 
 #[derive(Debug, serde::Deserialize, JsonSchema)]
 struct Commit {
@@ -14,25 +23,133 @@ struct Commit {
     description: String,
 }
 
+const SYSTEM_PROMPT: &str = "You are an expert programmer who writes git commit messages following the Conventional Commits specification (https://www.conventionalcommits.org/en/v1.0.0/).
+
+For the title field:
+- MUST follow this exact format: <type>[optional scope]: <description>
+- Common types: feat (new feature), fix (bug fix), docs (documentation), style (formatting), refactor (code restructuring), test (adding tests), chore (maintenance)  
+- CRITICAL: Keep title under 50 characters total (including type and colon)
+- Use lowercase for type
+- Be specific but concise
+- Examples: \"feat(auth): add OAuth2 login\", \"fix: resolve memory leak\"
+
+For the description field:
+- Provide detailed explanation of what changed and why
+- Use imperative mood (\"add\" not \"added\")
+- Explain the impact and context
+- Include breaking changes if any
+
+Analyze the git diff carefully and respond with a JSON object containing the title and description fields. The title MUST be 50 characters or less.";
+
 pub struct GeminiProvider {
     client: Client,
     api_key: String,
     model: String,
+    settings: GenerationSettings,
 }
 
 impl GeminiProvider {
     pub fn new(api_key: String, model: String) -> Self {
         Self {
-            client: Client::new(),
+            client: http::build_client(&NetworkSettings::default()).unwrap_or_default(),
             api_key,
             model,
+            settings: GenerationSettings::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: GenerationSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Replaces the HTTP client with one built from `network`.
+    pub fn with_network(mut self, network: &NetworkSettings) -> Result<Self, String> {
+        self.client = http::build_client(network)?;
+        Ok(self)
+    }
+
+    /// Builds the JSON request body sent to the API for the given conversation.
+    fn request_body(
+        &self,
+        conversation: &Conversation,
+        candidate_count: u32,
+    ) -> Result<serde_json::Value, String> {
+        // Create the response schema using the new structured output approach
+        let mut response_schema = serde_json::to_value(schemars::schema_for!(Commit))
+            .map_err(|e| format!("Failed to create schema: {}", e))?;
+
+        // Remove $schema and other metadata that Gemini doesn't accept
+        if let Some(obj) = response_schema.as_object_mut() {
+            obj.remove("$schema");
+            obj.remove("title");
         }
+
+        Ok(self.structured_request_body(
+            SYSTEM_PROMPT,
+            response_schema,
+            conversation,
+            candidate_count,
+        ))
+    }
+
+    /// Builds a request body whose response must match `response_schema`.
+    fn structured_request_body(
+        &self,
+        system_prompt: &str,
+        response_schema: serde_json::Value,
+        conversation: &Conversation,
+        candidate_count: u32,
+    ) -> serde_json::Value {
+        let mut body = json!({
+            "system_instruction": {
+                "parts": [
+                    { "text": system_prompt }
+                ]
+            },
+            "contents": conversation
+                .turns()
+                .into_iter()
+                .map(|turn| json!({
+                    "role": match turn.role {
+                        Role::User => "user",
+                        Role::Assistant => "model",
+                    },
+                    "parts": [{ "text": turn.content }]
+                }))
+                .collect::<Vec<_>>(),
+            "generation_config": {
+                "temperature": self.settings.temperature(),
+                "max_output_tokens": self.settings.max_tokens(),
+                "candidate_count": candidate_count,
+                "response_mime_type": "application/json",
+                "response_schema": response_schema
+            }
+        });
+        if let Some(top_p) = self.settings.top_p {
+            body["generation_config"]["top_p"] = json!(top_p);
+        }
+        if let Some(stop) = self.settings.stop_sequences() {
+            body["generation_config"]["stop_sequences"] = json!(stop);
+        }
+
+        body
     }
 }
 
 #[derive(Deserialize)]
 struct GeminiResponse {
     candidates: Vec<Candidate>,
+    #[serde(rename = "usageMetadata", default)]
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Deserialize)]
+struct UsageMetadata {
+    #[serde(rename = "promptTokenCount", default)]
+    prompt_token_count: u64,
+    #[serde(rename = "candidatesTokenCount", default)]
+    candidates_token_count: u64,
 }
 
 #[derive(Deserialize)]
@@ -48,74 +165,208 @@ struct Content {
     parts: Vec<Part>,
 }
 
+#[derive(Deserialize)]
+struct ModelsResponse {
+    #[serde(default)]
+    models: Vec<ModelInfo>,
+}
+
+#[derive(Deserialize)]
+struct ModelInfo {
+    name: String,
+    #[serde(rename = "supportedGenerationMethods", default)]
+    supported_generation_methods: Vec<String>,
+}
+
+impl ModelsResponse {
+    /// Model ids (without the `models/` prefix) that support `generateContent`.
+    fn generative_model_ids(self) -> Vec<String> {
+        self.models
+            .into_iter()
+            .filter(|model| {
+                model
+                    .supported_generation_methods
+                    .iter()
+                    .any(|method| method == "generateContent")
+            })
+            .map(|model| {
+                model
+                    .name
+                    .strip_prefix("models/")
+                    .unwrap_or(&model.name)
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(untagged)]
 enum Part {
     Text { text: String },
 }
 
-#[async_trait]
-impl AIProvider for GeminiProvider {
-    async fn generate_commit_message(&self, diff: &str) -> Result<GeneratedCommit, String> {
+impl GeminiProvider {
+    /// Requests `candidate_count` candidates in a single call.
+    async fn generate_content(
+        &self,
+        conversation: &Conversation,
+        candidate_count: u32,
+    ) -> Result<Vec<GeneratedCommit>, String> {
+        let body = self.request_body(conversation, candidate_count)?;
+        let gemini_response = self.send(&body).await?;
+
+        if gemini_response.candidates.is_empty() {
+            return Err("No candidates in Gemini response".to_string());
+        }
+        let mut candidates = collect_candidates(
+            gemini_response
+                .candidates
+                .iter()
+                .map(parse_candidate)
+                .collect(),
+        )?;
+        candidates[0].usage = gemini_response
+            .usage_metadata
+            .as_ref()
+            .map(|usage| TokenUsage {
+                input_tokens: usage.prompt_token_count,
+                output_tokens: usage.candidates_token_count,
+            });
+        Ok(candidates)
+    }
+
+    /// Requests a single response following `schema` and parses it as `T`.
+    async fn generate_structured<T: serde::de::DeserializeOwned>(
+        &self,
+        system_prompt: &str,
+        schema: serde_json::Value,
+        conversation: &Conversation,
+    ) -> Result<T, String> {
+        let body = self.structured_request_body(system_prompt, schema, conversation, 1);
+        let gemini_response = self.send(&body).await?;
+        let candidate = gemini_response
+            .candidates
+            .first()
+            .ok_or("No candidates in Gemini response")?;
+        parse_structured(candidate)
+    }
+
+    /// Sends a request body to the `generateContent` endpoint.
+    async fn send(&self, body: &serde_json::Value) -> Result<GeminiResponse, String> {
         let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-            self.model, self.api_key
+            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent",
+            self.model
         );
 
-        let system_instruction = "You are an expert programmer who writes git commit messages following the Conventional Commits specification (https://www.conventionalcommits.org/en/v1.0.0/).
+        // The key goes in a header, so it can't leak through an error's URL
+        let mut request = self
+            .client
+            .post(&url)
+            .header("x-goog-api-key", &self.api_key)
+            .json(body);
+        if let Some(timeout) = self.settings.timeout() {
+            request = request.timeout(timeout);
+        }
 
-For the title field:
-- MUST follow this exact format: <type>[optional scope]: <description>
-- Common types: feat (new feature), fix (bug fix), docs (documentation), style (formatting), refactor (code restructuring), test (adding tests), chore (maintenance)  
-- CRITICAL: Keep title under 50 characters total (including type and colon)
-- Use lowercase for type
-- Be specific but concise
-- Examples: \"feat(auth): add OAuth2 login\", \"fix: resolve memory leak\"
+        let response = request
+            .send()
+            .await
+            .map_err(|e| http::describe_request_error("Gemini", e))?;
 
-For the description field:
-- Provide detailed explanation of what changed and why
-- Use imperative mood (\"add\" not \"added\")
-- Explain the impact and context
-- Include breaking changes if any
+        if !response.status().is_success() {
+            let error_body = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(format!("Gemini API returned an error: {}", error_body));
+        }
 
-Analyze the git diff carefully and respond with a JSON object containing the title and description fields. The title MUST be 50 characters or less.";
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Gemini response: {}", e))
+    }
+}
 
-        // Create the response schema using the new structured output approach
-        let mut response_schema = serde_json::to_value(schemars::schema_for!(Commit))
-            .map_err(|e| format!("Failed to create schema: {}", e))?;
+/// Parses the structured JSON output of a single candidate.
+fn parse_candidate(candidate: &Candidate) -> Result<GeneratedCommit, String> {
+    let commit: Commit = parse_structured(candidate)?;
 
-        // Remove $schema and other metadata that Gemini doesn't accept
-        if let Some(obj) = response_schema.as_object_mut() {
-            obj.remove("$schema");
-            obj.remove("title");
-        }
+    Ok(GeneratedCommit {
+        title: commit.title,
+        description: commit.description,
+        usage: None,
+    })
+}
 
-        let body = json!({
-            "system_instruction": {
-                "parts": [
-                    { "text": system_instruction }
-                ]
-            },
-            "contents": [{
-                "parts": [
-                    { "text": format!("Here is the git diff to analyze:\n```diff\n{}\n```", diff) }
-                ]
-            }],
-            "generation_config": {
-                "temperature": 0.2,
-                "candidate_count": 1,
-                "response_mime_type": "application/json",
-                "response_schema": response_schema
-            }
-        });
+/// Deserializes the JSON text of a candidate.
+fn parse_structured<T: serde::de::DeserializeOwned>(candidate: &Candidate) -> Result<T, String> {
+    // With structured output, Gemini returns JSON directly in text parts
+    let Some(Part::Text { text }) = candidate.content.parts.first() else {
+        return Err("No text content found in Gemini response".to_string());
+    };
+    serde_json::from_str(text)
+        .map_err(|e| format!("Failed to parse structured JSON response: {}", e))
+}
+
+#[async_trait]
+impl AIProvider for GeminiProvider {
+    async fn generate_commit_message(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<GeneratedCommit, String> {
+        let mut candidates = self.generate_content(conversation, 1).await?;
+        Ok(candidates.remove(0))
+    }
+
+    async fn generate_candidates(
+        &self,
+        conversation: &Conversation,
+        count: usize,
+    ) -> Result<Vec<GeneratedCommit>, String> {
+        // Larger counts are made up with parallel requests
+        let requests = candidate_batches(count.max(1), MAX_CANDIDATES_PER_REQUEST)
+            .into_iter()
+            .map(|batch| self.generate_content(conversation, batch as u32));
+        collect_batches(futures::future::join_all(requests).await)
+    }
+
+    async fn plan_split(&self, conversation: &Conversation) -> Result<SplitPlan, String> {
+        self.generate_structured(SPLIT_SYSTEM_PROMPT, SplitPlan::schema()?, conversation)
+            .await
+    }
+
+    async fn generate_pull_request(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<PullRequest, String> {
+        self.generate_structured(PR_SYSTEM_PROMPT, PullRequest::schema()?, conversation)
+            .await
+    }
+
+    async fn polish_changelog(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<PolishedEntries, String> {
+        self.generate_structured(
+            CHANGELOG_SYSTEM_PROMPT,
+            PolishedEntries::schema()?,
+            conversation,
+        )
+        .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let url = "https://generativelanguage.googleapis.com/v1beta/models?pageSize=1000";
 
         let response = self
             .client
-            .post(&url)
-            .json(&body)
+            .get(url)
+            .header("x-goog-api-key", &self.api_key)
             .send()
             .await
-            .map_err(|e| format!("Gemini API request failed: {}", e))?;
+            .map_err(|e| http::describe_request_error("Gemini", e))?;
 
         if !response.status().is_success() {
             let error_body = response
@@ -125,29 +376,12 @@ Analyze the git diff carefully and respond with a JSON object containing the tit
             return Err(format!("Gemini API returned an error: {}", error_body));
         }
 
-        let gemini_response: GeminiResponse = response
+        let models: ModelsResponse = response
             .json()
             .await
-            .map_err(|e| format!("Failed to parse Gemini response: {}", e))?;
-
-        let candidate = gemini_response
-            .candidates
-            .first()
-            .ok_or("No candidates in Gemini response".to_string())?;
-
-        // With structured output, Gemini returns JSON directly in text parts
-        if let Some(part) = candidate.content.parts.first() {
-            let Part::Text { text } = part;
-            let commit: Commit = serde_json::from_str(text)
-                .map_err(|e| format!("Failed to parse structured JSON response: {}", e))?;
-
-            return Ok(GeneratedCommit {
-                title: commit.title,
-                description: commit.description,
-            });
-        }
+            .map_err(|e| format!("Failed to parse Gemini models response: {}", e))?;
 
-        Err("No text content found in Gemini response".to_string())
+        Ok(models.generative_model_ids())
     }
 }
 
@@ -161,6 +395,113 @@ mod tests {
         let provider = GeminiProvider::new("key".to_string(), "model".to_string());
         assert_eq!(provider.api_key, "key");
         assert_eq!(provider.model, "model");
+        assert_eq!(provider.settings, GenerationSettings::default());
+    }
+
+    #[test]
+    fn test_gemini_request_body_uses_settings() {
+        let provider = GeminiProvider::new("key".to_string(), "model".to_string()).with_settings(
+            GenerationSettings {
+                temperature: Some(0.5),
+                top_p: Some(0.9),
+                max_tokens: Some(256),
+                stop: Some(vec!["END".to_string()]),
+                ..Default::default()
+            },
+        );
+        let body = provider
+            .request_body(&Conversation::new("diff"), 1)
+            .unwrap();
+        let config = &body["generation_config"];
+        assert_eq!(config["temperature"], 0.5);
+        assert_eq!(config["top_p"], 0.9f32 as f64);
+        assert_eq!(config["max_output_tokens"], 256);
+        assert_eq!(config["stop_sequences"][0], "END");
+    }
+
+    #[test]
+    fn test_gemini_request_body_includes_feedback_turns() {
+        let provider = GeminiProvider::new("key".to_string(), "model".to_string());
+        let mut conversation = Conversation::new("diff");
+        conversation.push_feedback("feat: add x", "shorter");
+        let body = provider.request_body(&conversation, 1).unwrap();
+        let contents = body["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 3);
+        assert_eq!(contents[1]["role"], "model");
+        assert_eq!(contents[1]["parts"][0]["text"], "feat: add x");
+        assert_eq!(contents[2]["role"], "user");
+    }
+
+    #[test]
+    fn test_gemini_request_body_candidate_count() {
+        let provider = GeminiProvider::new("key".to_string(), "model".to_string());
+        let body = provider
+            .request_body(&Conversation::new("diff"), 3)
+            .unwrap();
+        assert_eq!(body["generation_config"]["candidate_count"], 3);
+    }
+
+    #[test]
+    fn test_gemini_candidates_beyond_limit_are_batched() {
+        // --candidates allows 10, above what one Gemini request accepts
+        let batches = candidate_batches(10, MAX_CANDIDATES_PER_REQUEST);
+        assert_eq!(batches, vec![8, 2]);
+        let provider = GeminiProvider::new("key".to_string(), "model".to_string());
+        for batch in batches {
+            let body = provider
+                .request_body(&Conversation::new("diff"), batch as u32)
+                .unwrap();
+            assert!(
+                body["generation_config"]["candidate_count"]
+                    .as_u64()
+                    .unwrap()
+                    <= 8
+            );
+        }
+    }
+
+    #[test]
+    fn test_gemini_parse_multiple_candidates() {
+        let json = r#"{
+            "candidates": [
+                { "content": { "parts": [ { "text": "{\"title\": \"feat: a\", \"description\": \"A\"}" } ] } },
+                { "content": { "parts": [ { "text": "not json" } ] } },
+                { "content": { "parts": [ { "text": "{\"title\": \"feat: b\", \"description\": \"B\"}" } ] } }
+            ]
+        }"#;
+        let resp: GeminiResponse = serde_json::from_str(json).unwrap();
+        let candidates =
+            collect_candidates(resp.candidates.iter().map(parse_candidate).collect()).unwrap();
+        let titles: Vec<&str> = candidates.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["feat: a", "feat: b"]);
+    }
+
+    #[test]
+    fn test_gemini_split_plan_request_and_response() {
+        let provider = GeminiProvider::new("key".to_string(), "model".to_string());
+        let body = provider.structured_request_body(
+            SPLIT_SYSTEM_PROMPT,
+            SplitPlan::schema().unwrap(),
+            &Conversation::new("Hunk 1: src/lib.rs"),
+            1,
+        );
+        assert_eq!(
+            body["system_instruction"]["parts"][0]["text"],
+            SPLIT_SYSTEM_PROMPT
+        );
+        assert_eq!(
+            body["generation_config"]["response_schema"]["required"][0],
+            "commits"
+        );
+
+        let json = r#"{
+            "candidates": [
+                { "content": { "parts": [ { "text": "{\"commits\": [{\"title\": \"feat: a\", \"description\": \"A\", \"hunks\": [2, 1]}]}" } ] } }
+            ]
+        }"#;
+        let resp: GeminiResponse = serde_json::from_str(json).unwrap();
+        let plan: SplitPlan = parse_structured(&resp.candidates[0]).unwrap();
+        assert_eq!(plan.commits[0].hunks, vec![2, 1]);
     }
 
     #[test]
@@ -183,16 +524,25 @@ mod tests {
         assert_eq!(resp.candidates.len(), 1);
 
         // Test structured output parsing
-        if let Part::Text { text } = &resp.candidates[0].content.parts[0] {
-            let commit: Commit = serde_json::from_str(text).unwrap();
-            assert_eq!(commit.title, "feat: add new feature");
-            assert_eq!(
-                commit.description,
-                "Added a new feature to improve functionality"
-            );
-        } else {
-            panic!("Expected text part");
-        }
+        let Part::Text { text } = &resp.candidates[0].content.parts[0];
+        let commit: Commit = serde_json::from_str(text).unwrap();
+        assert_eq!(commit.title, "feat: add new feature");
+        assert_eq!(
+            commit.description,
+            "Added a new feature to improve functionality"
+        );
+    }
+
+    #[test]
+    fn test_gemini_models_response_filters_generative_models() {
+        let json = r#"{
+            "models": [
+                {"name": "models/gemini-1.5-flash-latest", "supportedGenerationMethods": ["generateContent", "countTokens"]},
+                {"name": "models/text-embedding-004", "supportedGenerationMethods": ["embedContent"]}
+            ]
+        }"#;
+        let resp: ModelsResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(resp.generative_model_ids(), vec!["gemini-1.5-flash-latest"]);
     }
 
     #[test]
@@ -209,10 +559,7 @@ mod tests {
             ]
         }"#;
         let resp: GeminiResponse = serde_json::from_str(json).unwrap();
-        if let Part::Text { text } = &resp.candidates[0].content.parts[0] {
-            assert!(text.contains("title"));
-        } else {
-            panic!("Expected text part");
-        }
+        let Part::Text { text } = &resp.candidates[0].content.parts[0];
+        assert!(text.contains("title"));
     }
 }
@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Connection settings shared by the HTTP clients of all providers.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct NetworkSettings {
+    /// Time allowed to establish a connection, in seconds.
+    pub connect_timeout_secs: Option<u64>,
+    /// Total time allowed for a request, in seconds. Per-provider
+    /// `timeout_secs` generation settings take precedence.
+    pub timeout_secs: Option<u64>,
+    /// Proxy used for HTTPS requests, e.g. `http://proxy.corp.example:3128`.
+    /// When unset, the `HTTPS_PROXY` environment variable is honoured.
+    pub https_proxy: Option<String>,
+    /// Comma-separated hosts, domains or IP ranges that bypass `https_proxy`,
+    /// or the `HTTPS_PROXY` environment proxy, replacing `NO_PROXY`.
+    pub no_proxy: Option<String>,
+    /// PEM files with extra CA certificates to trust alongside the built-in roots.
+    #[serde(default)]
+    pub ca_certificates: Vec<PathBuf>,
+}
+
+impl NetworkSettings {
+    pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+    pub const DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+    /// Returns a copy of `self` with every field set in `other` taking precedence.
+    pub fn merge(&self, other: &NetworkSettings) -> NetworkSettings {
+        NetworkSettings {
+            connect_timeout_secs: other.connect_timeout_secs.or(self.connect_timeout_secs),
+            timeout_secs: other.timeout_secs.or(self.timeout_secs),
+            https_proxy: other
+                .https_proxy
+                .clone()
+                .or_else(|| self.https_proxy.clone()),
+            no_proxy: other.no_proxy.clone().or_else(|| self.no_proxy.clone()),
+            ca_certificates: self
+                .ca_certificates
+                .iter()
+                .chain(&other.ca_certificates)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.connect_timeout_secs
+                .unwrap_or(Self::DEFAULT_CONNECT_TIMEOUT_SECS),
+        )
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs.unwrap_or(Self::DEFAULT_TIMEOUT_SECS))
+    }
+
+    /// The HTTPS proxy to configure explicitly, with the setting it came
+    /// from. reqwest reads `HTTPS_PROXY` on its own, but only pairs it with
+    /// `NO_PROXY`, so the environment proxy is taken over when `no_proxy` is
+    /// configured.
+    fn https_proxy_url(
+        &self,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<(&'static str, String)> {
+        if let Some(proxy_url) = &self.https_proxy {
+            return Some(("https_proxy", proxy_url.clone()));
+        }
+        self.no_proxy.as_ref()?;
+        ["HTTPS_PROXY", "https_proxy"].into_iter().find_map(|name| {
+            env(name)
+                .filter(|url| !url.is_empty())
+                .map(|url| (name, url))
+        })
+    }
+
+    /// Returns a copy of `self` whose total timeout is `timeout_secs` when
+    /// set, for clients that can't override the timeout per request.
+    pub fn with_request_timeout(&self, timeout_secs: Option<u64>) -> NetworkSettings {
+        NetworkSettings {
+            timeout_secs: timeout_secs.or(self.timeout_secs),
+            ..self.clone()
+        }
+    }
+}
+
+/// Reads every configured CA bundle, keeping the path for error messages.
+fn read_ca_certificates(network: &NetworkSettings) -> Result<Vec<(PathBuf, Vec<u8>)>, String> {
+    network
+        .ca_certificates
+        .iter()
+        .map(|path| {
+            fs::read(path)
+                .map(|pem| (path.clone(), pem))
+                .map_err(|e| format!("Failed to read CA certificate '{}': {}", path.display(), e))
+        })
+        .collect()
+}
+
+/// Both reqwest versions share the same builder API, so the client
+/// configuration is written once and expanded for each.
+macro_rules! build_reqwest_client {
+    ($reqwest:ident, $network:expr) => {{
+        let network: &NetworkSettings = $network;
+        let mut builder = $reqwest::Client::builder()
+            .connect_timeout(network.connect_timeout())
+            .timeout(network.timeout());
+
+        if let Some((source, proxy_url)) = network.https_proxy_url(|name| std::env::var(name).ok())
+        {
+            let proxy = $reqwest::Proxy::https(&proxy_url)
+                .map_err(|e| format!("Invalid {} '{}': {}", source, proxy_url, e))?
+                .no_proxy(
+                    network
+                        .no_proxy
+                        .as_deref()
+                        .and_then($reqwest::NoProxy::from_string),
+                );
+            builder = builder.proxy(proxy);
+        }
+
+        for (path, pem) in read_ca_certificates(network)? {
+            let certificates = $reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("Invalid CA certificate '{}': {}", path.display(), e))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }};
+}
+
+/// Builds the HTTP client used by the Anthropic and Gemini providers.
+pub fn build_client(network: &NetworkSettings) -> Result<reqwest::Client, String> {
+    build_reqwest_client!(reqwest, network)
+}
+
+/// Builds the HTTP client handed to `async_openai`, which uses an older reqwest.
+pub fn build_openai_client(network: &NetworkSettings) -> Result<openai_reqwest::Client, String> {
+    build_reqwest_client!(openai_reqwest, network)
+}
+
+/// Formats a failed request, calling out timeouts explicitly. The URL is
+/// left out, since it can carry credentials.
+pub(crate) fn describe_request_error(provider: &str, error: reqwest::Error) -> String {
+    let error = error.without_url();
+    if error.is_timeout() {
+        format!("{} API request timed out: {}", provider, error)
+    } else if error.is_connect() {
+        format!("Could not connect to the {} API: {}", provider, error)
+    } else {
+        format!("{} API request failed: {}", provider, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_settings_defaults() {
+        let network = NetworkSettings::default();
+        assert_eq!(network.connect_timeout(), Duration::from_secs(10));
+        assert_eq!(network.timeout(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_with_request_timeout() {
+        let network = NetworkSettings {
+            timeout_secs: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(
+            network.with_request_timeout(Some(300)).timeout(),
+            Duration::from_secs(300)
+        );
+        assert_eq!(network.with_request_timeout(None), network);
+    }
+
+    #[test]
+    fn test_https_proxy_url() {
+        let env = |name: &str| (name == "HTTPS_PROXY").then(|| "http://env:3128".to_string());
+        let mut network = NetworkSettings::default();
+        assert_eq!(network.https_proxy_url(env), None);
+
+        // The environment proxy is only taken over to apply no_proxy to it
+        network.no_proxy = Some("localhost".to_string());
+        assert_eq!(
+            network.https_proxy_url(env),
+            Some(("HTTPS_PROXY", "http://env:3128".to_string()))
+        );
+
+        network.https_proxy = Some("http://config:3128".to_string());
+        assert_eq!(
+            network.https_proxy_url(env),
+            Some(("https_proxy", "http://config:3128".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_network_settings_merge() {
+        let base = NetworkSettings {
+            connect_timeout_secs: Some(5),
+            timeout_secs: Some(30),
+            https_proxy: Some("http://proxy:3128".to_string()),
+            ca_certificates: vec![PathBuf::from("corp.pem")],
+            ..Default::default()
+        };
+        let overrides = NetworkSettings {
+            timeout_secs: Some(90),
+            no_proxy: Some("localhost".to_string()),
+            ca_certificates: vec![PathBuf::from("extra.pem")],
+            ..Default::default()
+        };
+        let merged = base.merge(&overrides);
+        assert_eq!(merged.connect_timeout(), Duration::from_secs(5));
+        assert_eq!(merged.timeout(), Duration::from_secs(90));
+        assert_eq!(merged.https_proxy.as_deref(), Some("http://proxy:3128"));
+        assert_eq!(merged.no_proxy.as_deref(), Some("localhost"));
+        assert_eq!(
+            merged.ca_certificates,
+            vec![PathBuf::from("corp.pem"), PathBuf::from("extra.pem")]
+        );
+    }
+
+    #[test]
+    fn test_build_client_rejects_missing_ca_file() {
+        let network = NetworkSettings {
+            ca_certificates: vec![PathBuf::from("/nonexistent/ca.pem")],
+            ..Default::default()
+        };
+        let error = build_client(&network).unwrap_err();
+        assert!(error.contains("/nonexistent/ca.pem"));
+        assert!(build_openai_client(&network).is_err());
+    }
+
+    #[test]
+    fn test_build_client_rejects_invalid_proxy() {
+        let network = NetworkSettings {
+            https_proxy: Some("not a url".to_string()),
+            ..Default::default()
+        };
+        assert!(build_client(&network)
+            .unwrap_err()
+            .contains("Invalid https_proxy"));
+    }
+
+    #[test]
+    fn test_build_clients() {
+        let network = NetworkSettings::default();
+        assert!(build_client(&network).is_ok());
+        assert!(build_openai_client(&network).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_is_reported() {
+        // Nothing listens on port 9 (discard) on the loopback interface in CI,
+        // so this exercises the connection error path without network access.
+        let client = build_client(&NetworkSettings {
+            connect_timeout_secs: Some(1),
+            timeout_secs: Some(1),
+            ..Default::default()
+        })
+        .unwrap();
+        let error = client
+            .get("http://127.0.0.1:9/models?key=secret-key")
+            .send()
+            .await
+            .unwrap_err();
+        let message = describe_request_error("Test", error);
+        assert!(message.contains("Test API"));
+        assert!(!message.contains("secret-key"), "{}", message);
+    }
+}
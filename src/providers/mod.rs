@@ -1,14 +1,97 @@
 use async_trait::async_trait;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 pub mod anthropic;
 pub mod gemini;
+pub mod http;
 pub mod openai;
 
-#[derive(Debug, Deserialize)]
+use anthropic::AnthropicProvider;
+use gemini::GeminiProvider;
+use http::NetworkSettings;
+use openai::OpenAIProvider;
+
+/// Sampling and request parameters applied by every provider.
+///
+/// Unset fields fall back to the built-in defaults, so settings from the
+/// config file and the command line can be layered with [`merge`](Self::merge).
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct GenerationSettings {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Total time allowed for a single API request, in seconds.
+    pub timeout_secs: Option<u64>,
+    pub stop: Option<Vec<String>>,
+}
+
+impl GenerationSettings {
+    pub const DEFAULT_TEMPERATURE: f32 = 0.2;
+    pub const DEFAULT_MAX_TOKENS: u32 = 1024;
+
+    /// Returns a copy of `self` with every field set in `other` taking precedence.
+    pub fn merge(&self, other: &GenerationSettings) -> GenerationSettings {
+        GenerationSettings {
+            temperature: other.temperature.or(self.temperature),
+            top_p: other.top_p.or(self.top_p),
+            max_tokens: other.max_tokens.or(self.max_tokens),
+            timeout_secs: other.timeout_secs.or(self.timeout_secs),
+            stop: other.stop.clone().or_else(|| self.stop.clone()),
+        }
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.temperature.unwrap_or(Self::DEFAULT_TEMPERATURE)
+    }
+
+    pub fn max_tokens(&self) -> u32 {
+        self.max_tokens.unwrap_or(Self::DEFAULT_MAX_TOKENS)
+    }
+
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs.map(Duration::from_secs)
+    }
+
+    /// Stop sequences, or `None` when none are configured.
+    pub fn stop_sequences(&self) -> Option<&[String]> {
+        self.stop.as_deref().filter(|stop| !stop.is_empty())
+    }
+}
+
+/// Tokens consumed by a generation request, as reported by the provider.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.input_tokens + self.output_tokens
+    }
+
+    /// Sums the usage of several requests, or `None` if none reported usage.
+    pub fn sum<'a>(usages: impl IntoIterator<Item = &'a TokenUsage>) -> Option<TokenUsage> {
+        usages.into_iter().fold(None, |total, usage| {
+            let total = total.unwrap_or_default();
+            Some(TokenUsage {
+                input_tokens: total.input_tokens + usage.input_tokens,
+                output_tokens: total.output_tokens + usage.output_tokens,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct GeneratedCommit {
     pub title: String,
     pub description: String,
+    /// Token usage of the request that produced this message. When one
+    /// request returns several candidates, only the first carries it.
+    #[serde(default)]
+    pub usage: Option<TokenUsage>,
 }
 
 impl GeneratedCommit {
@@ -96,9 +179,403 @@ impl std::fmt::Display for GeneratedCommit {
     }
 }
 
+/// A group of hunks to commit together, as proposed for `commitcraft split`.
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct PlannedCommit {
+    /// The conventional commit title (max 50 chars).
+    pub title: String,
+    /// A description of the changes in this commit.
+    pub description: String,
+    /// The numbers of the hunks that belong to this commit.
+    pub hunks: Vec<usize>,
+}
+
+impl From<&PlannedCommit> for GeneratedCommit {
+    fn from(commit: &PlannedCommit) -> Self {
+        GeneratedCommit {
+            title: commit.title.clone(),
+            description: commit.description.clone(),
+            usage: None,
+        }
+    }
+}
+
+/// The commits proposed for `commitcraft split`, in the order to create them.
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct SplitPlan {
+    /// The commits to create, in order, each with the hunks it contains.
+    pub commits: Vec<PlannedCommit>,
+}
+
+impl SplitPlan {
+    pub fn schema() -> Result<serde_json::Value, String> {
+        tool_schema::<SplitPlan>()
+    }
+}
+
+/// Changelog entries rewritten for `commitcraft changelog --polish`.
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct PolishedEntries {
+    /// The rewritten entries, one per numbered entry and in the same order.
+    pub entries: Vec<String>,
+}
+
+impl PolishedEntries {
+    pub fn schema() -> Result<serde_json::Value, String> {
+        tool_schema::<PolishedEntries>()
+    }
+}
+
+/// A pull request title and markdown body for `commitcraft pr`.
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
+pub struct PullRequest {
+    /// The title of the pull request, in the style of a commit subject.
+    pub title: String,
+    /// The markdown description, with the requested headings.
+    pub body: String,
+}
+
+impl PullRequest {
+    pub fn schema() -> Result<serde_json::Value, String> {
+        tool_schema::<PullRequest>()
+    }
+}
+
+/// The JSON schema of `T`, with nested definitions inlined and integer
+/// formats removed so every provider accepts it.
+fn tool_schema<T: JsonSchema>() -> Result<serde_json::Value, String> {
+    let settings = schemars::gen::SchemaSettings::draft07().with(|settings| {
+        settings.inline_subschemas = true;
+    });
+    let schema = settings.into_generator().into_root_schema_for::<T>();
+    let mut schema =
+        serde_json::to_value(schema).map_err(|e| format!("Failed to create schema: {}", e))?;
+    if let Some(obj) = schema.as_object_mut() {
+        obj.remove("$schema");
+        obj.remove("title");
+    }
+    remove_formats(&mut schema);
+    Ok(schema)
+}
+
+fn remove_formats(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(obj) => {
+            obj.remove("format");
+            obj.remove("minimum");
+            obj.values_mut().for_each(remove_formats);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(remove_formats),
+        _ => {}
+    }
+}
+
+/// System prompt for [`AIProvider::plan_split`].
+pub const SPLIT_SYSTEM_PROMPT: &str = "You are an expert programmer who organizes staged changes into small, atomic git commits following the Conventional Commits specification (https://www.conventionalcommits.org/en/v1.0.0/).
+
+The diff is given as numbered hunks. Group the hunks into logical changes, one commit per change:
+- Every hunk number must appear in exactly one commit
+- Keep changes that depend on each other in the same commit, or order the commits so each one builds on the previous ones
+- Prefer fewer commits over splitting a single change; one commit is fine if the hunks belong together
+
+For each commit:
+- title: <type>[optional scope]: <description>, 50 characters or less, lowercase type
+- description: what changed and why, in imperative mood
+- hunks: the numbers of the hunks it contains
+
+Respond with the commits in the order they should be created.";
+
+/// System prompt for [`AIProvider::generate_pull_request`].
+pub const PR_SYSTEM_PROMPT: &str = "You are an expert programmer who writes pull request descriptions for reviewers.
+
+You are given the commits of a branch, the markdown headings the description must use, and the combined diff against the base branch.
+
+For the title:
+- Summarize the whole branch in one line of at most 72 characters
+- Follow the style of the commit subjects, e.g. Conventional Commits when they use it
+
+For the body:
+- Use exactly the given headings, in the given order, at the given levels
+- Under each heading, write what the heading asks for, based only on the commits and the diff
+- Describe what changed and why, grouping related commits instead of listing every one
+- For testing notes, mention the tests added or changed and how to verify the change; say so when there are none
+- Use markdown lists where they help and keep the text concise";
+
+/// System prompt for [`AIProvider::polish_changelog`].
+pub const CHANGELOG_SYSTEM_PROMPT: &str = "You are a technical writer who edits changelog entries generated from Conventional Commits subjects.
+
+The entries are given as a numbered list, each prefixed with its commit type and scope for context. Rewrite each entry for the readers of the changelog:
+- Return exactly one entry per numbered entry, in the same order
+- Describe the change from the user's point of view, in one short sentence
+- Start with a capital letter and don't end with a period
+- Leave out the number, the commit type and the scope
+- Keep identifiers, flags and file names as they are and don't invent details";
+
+/// Who wrote a turn of a [`Conversation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Assistant,
+}
+
+/// A single message sent to the provider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Turn {
+    pub role: Role,
+    pub content: String,
+}
+
+/// A previous suggestion and the user's hint for improving it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feedback {
+    pub previous: String,
+    pub hint: String,
+}
+
+/// The scope a title must have, decided from the changed files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScopeConstraint {
+    Use(String),
+    /// The changes span several scopes and the title should have none.
+    Omit,
+}
+
+/// The context of a generation request: the diff, recent commit subjects to
+/// imitate, the required scope, and any feedback given on earlier
+/// suggestions, which providers send as follow-up turns.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Conversation {
+    pub diff: String,
+    pub style_examples: Vec<String>,
+    pub scope: Option<ScopeConstraint>,
+    pub feedback: Vec<Feedback>,
+    /// Send `diff` as it is rather than as a diff to analyze, for requests
+    /// about something other than a diff.
+    pub plain: bool,
+}
+
+impl Conversation {
+    pub fn new(diff: impl Into<String>) -> Self {
+        Self {
+            diff: diff.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            diff: text.into(),
+            plain: true,
+            ..Default::default()
+        }
+    }
+
+    /// Adds commit subjects from the repository's history as style examples.
+    pub fn with_style_examples(mut self, examples: Vec<String>) -> Self {
+        self.style_examples = examples;
+        self
+    }
+
+    pub fn with_scope(mut self, scope: Option<ScopeConstraint>) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Records a hint for the suggestion `previous`, so the next request asks
+    /// the model to revise it.
+    pub fn push_feedback(&mut self, previous: impl Into<String>, hint: impl Into<String>) {
+        self.feedback.push(Feedback {
+            previous: previous.into(),
+            hint: hint.into(),
+        });
+    }
+
+    /// The conversation as alternating user and assistant turns, starting
+    /// with the diff.
+    pub fn turns(&self) -> Vec<Turn> {
+        let mut content = String::new();
+        if !self.style_examples.is_empty() {
+            content.push_str(
+                "Recent commit subjects in this repository. Match their style, scopes and wording where they fit:\n",
+            );
+            for example in &self.style_examples {
+                content.push_str(&format!("- {}\n", example));
+            }
+            content.push('\n');
+        }
+        match &self.scope {
+            Some(ScopeConstraint::Use(scope)) => content.push_str(&format!(
+                "Use the scope `{0}` in the title: <type>({0}): <description>.\n\n",
+                scope
+            )),
+            Some(ScopeConstraint::Omit) => content.push_str(
+                "These changes span several parts of the repository, so do not use a scope in the title.\n\n",
+            ),
+            None => {}
+        }
+        if self.plain {
+            content.push_str(&self.diff);
+        } else {
+            content.push_str(&format!(
+                "Here is the git diff to analyze:\n```diff\n{}\n```",
+                self.diff
+            ));
+        }
+        let mut turns = vec![Turn {
+            role: Role::User,
+            content,
+        }];
+        for feedback in &self.feedback {
+            turns.push(Turn {
+                role: Role::Assistant,
+                content: feedback.previous.clone(),
+            });
+            turns.push(Turn {
+                role: Role::User,
+                content: format!(
+                    "Revise the commit message above for the same diff. Feedback: {}",
+                    feedback.hint
+                ),
+            });
+        }
+        turns
+    }
+}
+
 #[async_trait]
-pub trait AIProvider {
-    async fn generate_commit_message(&self, diff: &str) -> Result<GeneratedCommit, String>;
+pub trait AIProvider: Send + Sync {
+    async fn generate_commit_message(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<GeneratedCommit, String>;
+
+    /// Generates up to `count` alternative messages. Providers that can return
+    /// several choices from one request override this; the default sends
+    /// `count` requests in parallel.
+    async fn generate_candidates(
+        &self,
+        conversation: &Conversation,
+        count: usize,
+    ) -> Result<Vec<GeneratedCommit>, String> {
+        let requests = (0..count.max(1)).map(|_| self.generate_commit_message(conversation));
+        collect_candidates(futures::future::join_all(requests).await)
+    }
+
+    /// Groups the numbered hunks in `conversation` into separate commits.
+    async fn plan_split(&self, _conversation: &Conversation) -> Result<SplitPlan, String> {
+        Err("Splitting changes is not supported by this provider".to_string())
+    }
+
+    /// Rewrites the numbered changelog entries in `conversation`.
+    async fn polish_changelog(
+        &self,
+        _conversation: &Conversation,
+    ) -> Result<PolishedEntries, String> {
+        Err("Polishing changelogs is not supported by this provider".to_string())
+    }
+
+    /// Writes a pull request for the commits and diff in `conversation`.
+    async fn generate_pull_request(
+        &self,
+        _conversation: &Conversation,
+    ) -> Result<PullRequest, String> {
+        Err("Generating pull requests is not supported by this provider".to_string())
+    }
+
+    /// Lists the identifiers of the models available to the configured account.
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        Err("Listing models is not supported by this provider".to_string())
+    }
+}
+
+/// Keeps the successful candidates, or returns the first error if all failed.
+pub fn collect_candidates(
+    results: Vec<Result<GeneratedCommit, String>>,
+) -> Result<Vec<GeneratedCommit>, String> {
+    let mut first_error = None;
+    let mut candidates = Vec::new();
+    for result in results {
+        match result {
+            Ok(candidate) => candidates.push(candidate),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match first_error {
+        Some(e) if candidates.is_empty() => Err(e),
+        _ if candidates.is_empty() => Err("The provider returned no candidates".to_string()),
+        _ => Ok(candidates),
+    }
+}
+
+/// Splits a request for `count` candidates into batches of at most
+/// `limit`, the most a provider returns from one call.
+pub fn candidate_batches(count: usize, limit: usize) -> Vec<usize> {
+    (0..count)
+        .step_by(limit)
+        .map(|start| limit.min(count - start))
+        .collect()
+}
+
+/// Collects the candidates of batches requested in parallel, keeping those
+/// of the batches that succeeded.
+pub fn collect_batches(
+    results: Vec<Result<Vec<GeneratedCommit>, String>>,
+) -> Result<Vec<GeneratedCommit>, String> {
+    collect_candidates(
+        results
+            .into_iter()
+            .flat_map(|result| match result {
+                Ok(batch) => batch.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            })
+            .collect(),
+    )
+}
+
+/// Removes candidates that repeat an earlier one, ignoring case and
+/// surrounding whitespace.
+pub fn dedupe_candidates(candidates: Vec<GeneratedCommit>) -> Vec<GeneratedCommit> {
+    let mut seen = std::collections::HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            seen.insert((
+                candidate.title.trim().to_lowercase(),
+                candidate.description.trim().to_lowercase(),
+            ))
+        })
+        .collect()
+}
+
+/// Creates the provider selected by name, configured with the given settings.
+pub fn build_provider(
+    provider_name: &str,
+    api_key: String,
+    model_name: &str,
+    settings: GenerationSettings,
+    network: &NetworkSettings,
+) -> Result<Box<dyn AIProvider>, String> {
+    let model_name = model_name.to_string();
+    Ok(match provider_name {
+        "openai" => Box::new(
+            OpenAIProvider::new(api_key, model_name)
+                .with_settings(settings)
+                .with_network(network)?,
+        ),
+        "gemini" => Box::new(
+            GeminiProvider::new(api_key, model_name)
+                .with_settings(settings)
+                .with_network(network)?,
+        ),
+        "anthropic" => Box::new(
+            AnthropicProvider::new(api_key, model_name)
+                .with_settings(settings)
+                .with_network(network)?,
+        ),
+        _ => return Err(format!("Unknown provider '{}'", provider_name)),
+    })
 }
 
 #[cfg(test)]
@@ -110,9 +587,215 @@ mod tests {
         let commit = GeneratedCommit {
             title: "feat: add new feature".to_string(),
             description: "This adds a new feature to the project.".to_string(),
+            usage: None,
         };
         let s = commit.to_string();
         assert!(s.contains("feat: add new feature"));
         assert!(s.contains("This adds a new feature to the project."));
     }
+
+    #[test]
+    fn test_conversation_turns() {
+        let mut conversation = Conversation::new("+added line");
+        assert_eq!(conversation.turns().len(), 1);
+        assert!(conversation.turns()[0].content.contains("+added line"));
+
+        conversation.push_feedback("feat: add line", "scope is api");
+        let turns = conversation.turns();
+        let roles: Vec<Role> = turns.iter().map(|turn| turn.role).collect();
+        assert_eq!(roles, vec![Role::User, Role::Assistant, Role::User]);
+        assert_eq!(turns[1].content, "feat: add line");
+        assert!(turns[2].content.ends_with("Feedback: scope is api"));
+    }
+
+    #[test]
+    fn test_conversation_style_examples() {
+        let conversation = Conversation::new("+added line").with_style_examples(vec![
+            "feat(api): add pagination".to_string(),
+            "fix(db): close pooled connections".to_string(),
+        ]);
+        let turns = conversation.turns();
+        assert_eq!(turns.len(), 1);
+        assert!(turns[0].content.starts_with("Recent commit subjects"));
+        assert!(turns[0]
+            .content
+            .contains("- feat(api): add pagination\n- fix(db): close pooled connections\n\nHere is the git diff"));
+        assert!(!Conversation::new("x").turns()[0]
+            .content
+            .contains("Recent commit subjects"));
+    }
+
+    #[test]
+    fn test_plain_conversation() {
+        let turns = Conversation::plain("1. feat: add refunds").turns();
+        assert_eq!(turns[0].content, "1. feat: add refunds");
+    }
+
+    #[test]
+    fn test_conversation_scope_constraint() {
+        let turns = Conversation::new("diff")
+            .with_scope(Some(ScopeConstraint::Use("api".to_string())))
+            .turns();
+        assert!(turns[0]
+            .content
+            .starts_with("Use the scope `api` in the title: <type>(api): <description>."));
+        let turns = Conversation::new("diff")
+            .with_scope(Some(ScopeConstraint::Omit))
+            .turns();
+        assert!(turns[0].content.contains("do not use a scope"));
+    }
+
+    fn commit(title: &str, description: &str) -> GeneratedCommit {
+        GeneratedCommit {
+            title: title.to_string(),
+            description: description.to_string(),
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_split_plan_schema() {
+        let schema = SplitPlan::schema().unwrap();
+        let text = schema.to_string();
+        assert!(!text.contains("$ref"));
+        assert!(!text.contains("\"format\""));
+        assert!(schema.get("$schema").is_none());
+        assert_eq!(
+            schema["properties"]["commits"]["items"]["properties"]["hunks"]["items"]["type"],
+            "integer"
+        );
+
+        let plan: SplitPlan = serde_json::from_str(
+            r#"{"commits": [{"title": "feat: a", "description": "b", "hunks": [1, 2]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(plan.commits[0].hunks, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_token_usage_sum() {
+        assert_eq!(TokenUsage::sum(&[]), None);
+        let usages = [
+            TokenUsage {
+                input_tokens: 100,
+                output_tokens: 20,
+            },
+            TokenUsage {
+                input_tokens: 50,
+                output_tokens: 5,
+            },
+        ];
+        let total = TokenUsage::sum(&usages).unwrap();
+        assert_eq!(total.input_tokens, 150);
+        assert_eq!(total.total_tokens(), 175);
+    }
+
+    #[test]
+    fn test_collect_candidates() {
+        let candidates =
+            collect_candidates(vec![Err("timeout".to_string()), Ok(commit("feat: a", ""))])
+                .unwrap();
+        assert_eq!(candidates, vec![commit("feat: a", "")]);
+
+        let error = collect_candidates(vec![Err("first".to_string()), Err("second".to_string())]);
+        assert_eq!(error.unwrap_err(), "first");
+        assert!(collect_candidates(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_candidate_batches() {
+        assert_eq!(candidate_batches(10, 8), vec![8, 2]);
+        assert_eq!(candidate_batches(8, 8), vec![8]);
+        assert_eq!(candidate_batches(3, 8), vec![3]);
+        assert_eq!(candidate_batches(17, 8), vec![8, 8, 1]);
+        assert!(candidate_batches(0, 8).is_empty());
+    }
+
+    #[test]
+    fn test_collect_batches() {
+        let candidates = collect_batches(vec![
+            Ok(vec![commit("feat: a", ""), commit("feat: b", "")]),
+            Err("quota".to_string()),
+            Ok(vec![commit("feat: c", "")]),
+        ])
+        .unwrap();
+        let titles: Vec<&str> = candidates.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["feat: a", "feat: b", "feat: c"]);
+        assert_eq!(
+            collect_batches(vec![Err("quota".to_string())]).unwrap_err(),
+            "quota"
+        );
+    }
+
+    #[test]
+    fn test_dedupe_candidates() {
+        let candidates = dedupe_candidates(vec![
+            commit("feat: add parser", "Adds a parser."),
+            commit("feat: add parser ", "adds a parser."),
+            commit("feat: add parser", "Adds a new parser."),
+            commit("fix: handle empty input", "Adds a parser."),
+        ]);
+        let titles: Vec<&str> = candidates.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "feat: add parser",
+                "feat: add parser",
+                "fix: handle empty input"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generation_settings_defaults() {
+        let settings = GenerationSettings::default();
+        assert_eq!(settings.temperature(), 0.2);
+        assert_eq!(settings.max_tokens(), 1024);
+        assert!(settings.timeout().is_none());
+        assert!(settings.stop_sequences().is_none());
+    }
+
+    #[test]
+    fn test_generation_settings_merge() {
+        let base = GenerationSettings {
+            temperature: Some(0.5),
+            max_tokens: Some(512),
+            stop: Some(vec!["END".to_string()]),
+            ..Default::default()
+        };
+        let overrides = GenerationSettings {
+            temperature: Some(0.9),
+            timeout_secs: Some(30),
+            ..Default::default()
+        };
+        let merged = base.merge(&overrides);
+        assert_eq!(merged.temperature, Some(0.9));
+        assert_eq!(merged.max_tokens, Some(512));
+        assert_eq!(merged.timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(merged.stop_sequences(), Some(&["END".to_string()][..]));
+    }
+
+    #[test]
+    fn test_build_provider() {
+        for name in ["openai", "gemini", "anthropic"] {
+            assert!(build_provider(
+                name,
+                "key".to_string(),
+                "model",
+                GenerationSettings::default(),
+                &NetworkSettings::default(),
+            )
+            .is_ok());
+        }
+        let error = build_provider(
+            "unknown",
+            "key".to_string(),
+            "model",
+            GenerationSettings::default(),
+            &NetworkSettings::default(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(error, "Unknown provider 'unknown'");
+    }
 }
@@ -1,17 +1,22 @@
 use async_openai::{
     config::OpenAIConfig,
     types::{
-        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
-        ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent,
-        ChatCompletionTool, ChatCompletionToolType, CreateChatCompletionRequestArgs,
-        FunctionObject, Role,
+        ChatChoice, ChatCompletionRequestAssistantMessageArgs, ChatCompletionRequestMessage,
+        ChatCompletionRequestSystemMessage, ChatCompletionRequestUserMessage,
+        ChatCompletionRequestUserMessageContent, ChatCompletionTool, ChatCompletionToolType,
+        CreateChatCompletionRequestArgs, CreateChatCompletionResponse, FunctionObject, Role,
     },
     Client,
 };
 use async_trait::async_trait;
 use schemars::JsonSchema;
 
-use super::{AIProvider, GeneratedCommit};
+use super::http::{self, NetworkSettings};
+use super::{
+    collect_candidates, AIProvider, Conversation, GeneratedCommit, GenerationSettings,
+    PolishedEntries, PullRequest, SplitPlan, TokenUsage, CHANGELOG_SYSTEM_PROMPT, PR_SYSTEM_PROMPT,
+    SPLIT_SYSTEM_PROMPT,
+};
 
 #[derive(Debug, serde::Deserialize, JsonSchema)]
 struct Commit {
@@ -24,21 +29,45 @@ struct Commit {
 pub struct OpenAIProvider {
     client: Client<OpenAIConfig>,
     model: String,
+    settings: GenerationSettings,
 }
 
 impl OpenAIProvider {
     pub fn new(api_key: String, model: String) -> Self {
         let config = OpenAIConfig::new().with_api_key(api_key);
+        let http_client =
+            http::build_openai_client(&NetworkSettings::default()).unwrap_or_default();
         Self {
-            client: Client::with_config(config),
+            client: Client::with_config(config).with_http_client(http_client),
             model,
+            settings: GenerationSettings::default(),
         }
     }
-}
 
-#[async_trait]
-impl AIProvider for OpenAIProvider {
-    async fn generate_commit_message(&self, diff: &str) -> Result<GeneratedCommit, String> {
+    pub fn with_settings(mut self, settings: GenerationSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Replaces the HTTP client with one built from `network`. Its timeout
+    /// bounds every request, so the generation `timeout_secs` given to
+    /// [`with_settings`](Self::with_settings) beforehand replaces the
+    /// network one.
+    pub fn with_network(self, network: &NetworkSettings) -> Result<Self, String> {
+        let network = network.with_request_timeout(self.settings.timeout_secs);
+        let http_client = http::build_openai_client(&network)?;
+        Ok(Self {
+            client: self.client.with_http_client(http_client),
+            ..self
+        })
+    }
+
+    /// Requests `count` choices in a single call using the `n` parameter.
+    async fn create_choices(
+        &self,
+        conversation: &Conversation,
+        count: u8,
+    ) -> Result<Vec<GeneratedCommit>, String> {
         let parameters_schema = serde_json::to_value(schemars::schema_for!(Commit))
             .map_err(|e| format!("Failed to create schema: {}", e))?;
 
@@ -64,73 +93,231 @@ For the description field:
 
 REMINDER: Title must be ≤50 characters. Prefer shorter, punchy titles over longer descriptive ones.";
 
-        let messages = vec![
-            ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
+        let function = FunctionObject {
+            name: "generate_commit".to_string(),
+            description: Some("Generate a conventional commit message".to_string()),
+            parameters: Some(parameters_schema),
+        };
+        let response = self
+            .create_chat(system_prompt, function, conversation, count)
+            .await?;
+
+        let mut candidates =
+            collect_candidates(response.choices.iter().map(parse_choice).collect())?;
+        candidates[0].usage = response.usage.as_ref().map(|usage| TokenUsage {
+            input_tokens: usage.prompt_tokens.into(),
+            output_tokens: usage.completion_tokens.into(),
+        });
+        Ok(candidates)
+    }
+
+    /// Sends the conversation with `function` as the only tool.
+    async fn create_chat(
+        &self,
+        system_prompt: &str,
+        function: FunctionObject,
+        conversation: &Conversation,
+        count: u8,
+    ) -> Result<CreateChatCompletionResponse, String> {
+        let mut messages = vec![ChatCompletionRequestMessage::System(
+            ChatCompletionRequestSystemMessage {
                 role: Role::System,
                 content: system_prompt.to_string(),
                 name: None,
-            }),
-            ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
-                role: Role::User,
-                content: ChatCompletionRequestUserMessageContent::Text(format!(
-                    "Here is the git diff:\n\n```diff\n{}\n```",
-                    diff
-                )),
-                name: None,
-            }),
-        ];
+            },
+        )];
+        for turn in conversation.turns() {
+            messages.push(match turn.role {
+                super::Role::User => {
+                    ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                        role: Role::User,
+                        content: ChatCompletionRequestUserMessageContent::Text(turn.content),
+                        name: None,
+                    })
+                }
+                super::Role::Assistant => ChatCompletionRequestMessage::Assistant(
+                    ChatCompletionRequestAssistantMessageArgs::default()
+                        .content(turn.content)
+                        .build()
+                        .map_err(|e| format!("Failed to build OpenAI request: {}", e))?,
+                ),
+            });
+        }
 
         let tools = vec![ChatCompletionTool {
             r#type: ChatCompletionToolType::Function,
-            function: FunctionObject {
-                name: "generate_commit".to_string(),
-                description: Some("Generate a conventional commit message".to_string()),
-                parameters: Some(parameters_schema),
-            },
+            function,
         }];
 
-        let request = CreateChatCompletionRequestArgs::default()
+        let mut request_args = CreateChatCompletionRequestArgs::default();
+        request_args
             .model(&self.model)
             .messages(messages)
             .tools(tools)
             .tool_choice("auto")
-            .temperature(0.2)
+            .n(count)
+            .temperature(self.settings.temperature())
+            .max_tokens(u16::try_from(self.settings.max_tokens()).unwrap_or(u16::MAX));
+        if let Some(top_p) = self.settings.top_p {
+            request_args.top_p(top_p);
+        }
+        if let Some(stop) = self.settings.stop_sequences() {
+            request_args.stop(stop.to_vec());
+        }
+        let request = request_args
             .build()
             .map_err(|e| format!("Failed to build OpenAI request: {}", e))?;
 
-        let response = self
-            .client
-            .chat()
-            .create(request)
-            .await
-            .map_err(|e| format!("OpenAI API call failed: {}", e))?;
+        let chat = self.client.chat();
+        let call = chat.create(request);
+        match self.settings.timeout() {
+            Some(timeout) => tokio::time::timeout(timeout, call).await.map_err(|_| {
+                format!(
+                    "OpenAI API call timed out after {} seconds",
+                    timeout.as_secs()
+                )
+            })?,
+            None => call.await,
+        }
+        .map_err(|e| format!("OpenAI API call failed: {}", e))
+    }
 
+    /// Sends the conversation with `function` and parses the arguments of
+    /// its call as `T`.
+    async fn call_tool<T: serde::de::DeserializeOwned>(
+        &self,
+        system_prompt: &str,
+        function: FunctionObject,
+        conversation: &Conversation,
+    ) -> Result<T, String> {
+        let response = self
+            .create_chat(system_prompt, function, conversation, 1)
+            .await?;
         let choice = response
             .choices
             .first()
-            .ok_or("No response choice from OpenAI".to_string())?;
-
-        let function_details = &choice
-            .message
-            .tool_calls
-            .as_ref()
-            .ok_or("Expected tool calls from OpenAI".to_string())?[0]
-            .function;
-
-        let commit: Commit = serde_json::from_str(&function_details.arguments).map_err(|e| {
+            .ok_or("The provider returned no candidates")?;
+        let arguments = tool_arguments(choice)?;
+        serde_json::from_str(arguments).map_err(|e| {
             format!(
                 "Failed to parse OpenAI tool call arguments: {}\nArguments: {}",
-                e, function_details.arguments
+                e, arguments
             )
-        })?;
-
-        Ok(GeneratedCommit {
-            title: commit.title,
-            description: commit.description,
         })
     }
 }
 
+/// The arguments of the first tool call of a choice.
+fn tool_arguments(choice: &ChatChoice) -> Result<&str, String> {
+    choice
+        .message
+        .tool_calls
+        .as_ref()
+        .and_then(|calls| calls.first())
+        .map(|call| call.function.arguments.as_str())
+        .ok_or("Expected tool calls from OpenAI".to_string())
+}
+
+/// Parses the `generate_commit` tool call of a single choice.
+fn parse_choice(choice: &ChatChoice) -> Result<GeneratedCommit, String> {
+    let arguments = tool_arguments(choice)?;
+    let commit: Commit = serde_json::from_str(arguments).map_err(|e| {
+        format!(
+            "Failed to parse OpenAI tool call arguments: {}\nArguments: {}",
+            e, arguments
+        )
+    })?;
+
+    Ok(GeneratedCommit {
+        title: commit.title,
+        description: commit.description,
+        usage: None,
+    })
+}
+
+#[async_trait]
+impl AIProvider for OpenAIProvider {
+    async fn generate_commit_message(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<GeneratedCommit, String> {
+        let mut choices = self.create_choices(conversation, 1).await?;
+        Ok(choices.remove(0))
+    }
+
+    async fn generate_candidates(
+        &self,
+        conversation: &Conversation,
+        count: usize,
+    ) -> Result<Vec<GeneratedCommit>, String> {
+        // The API takes up to 128 choices (`n`) per request, far more than
+        // --candidates allows, so one request always covers them
+        let count = u8::try_from(count.clamp(1, 128)).unwrap_or(u8::MAX);
+        self.create_choices(conversation, count).await
+    }
+
+    async fn plan_split(&self, conversation: &Conversation) -> Result<SplitPlan, String> {
+        let function = FunctionObject {
+            name: "plan_commits".to_string(),
+            description: Some("Group the numbered hunks into conventional commits".to_string()),
+            parameters: Some(SplitPlan::schema()?),
+        };
+        self.call_tool(SPLIT_SYSTEM_PROMPT, function, conversation)
+            .await
+    }
+
+    async fn generate_pull_request(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<PullRequest, String> {
+        let function = FunctionObject {
+            name: "write_pull_request".to_string(),
+            description: Some("Write the pull request title and description".to_string()),
+            parameters: Some(PullRequest::schema()?),
+        };
+        self.call_tool(PR_SYSTEM_PROMPT, function, conversation)
+            .await
+    }
+
+    async fn polish_changelog(
+        &self,
+        conversation: &Conversation,
+    ) -> Result<PolishedEntries, String> {
+        let function = FunctionObject {
+            name: "polish_entries".to_string(),
+            description: Some("Rewrite the numbered changelog entries".to_string()),
+            parameters: Some(PolishedEntries::schema()?),
+        };
+        self.call_tool(CHANGELOG_SYSTEM_PROMPT, function, conversation)
+            .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let response = self
+            .client
+            .models()
+            .list()
+            .await
+            .map_err(|e| format!("OpenAI API call failed: {}", e))?;
+
+        Ok(response
+            .data
+            .into_iter()
+            .map(|model| model.id)
+            .filter(|id| is_chat_model(id))
+            .collect())
+    }
+}
+
+/// The models endpoint also returns embedding, audio and image models,
+/// which cannot generate commit messages.
+fn is_chat_model(id: &str) -> bool {
+    let chat_prefixes = ["gpt-", "chatgpt-", "o1", "o3", "o4"];
+    let non_chat_markers = ["audio", "realtime", "transcribe", "tts", "image", "search"];
+    chat_prefixes.iter().any(|prefix| id.starts_with(prefix))
+        && !non_chat_markers.iter().any(|marker| id.contains(marker))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,5 +326,26 @@ mod tests {
     fn test_openai_provider_new() {
         let provider = OpenAIProvider::new("key".to_string(), "model".to_string());
         assert_eq!(provider.model, "model");
+        assert_eq!(provider.settings, GenerationSettings::default());
+    }
+
+    #[test]
+    fn test_is_chat_model() {
+        assert!(is_chat_model("gpt-4.1-nano"));
+        assert!(is_chat_model("o3-mini"));
+        assert!(!is_chat_model("text-embedding-3-small"));
+        assert!(!is_chat_model("gpt-4o-realtime-preview"));
+        assert!(!is_chat_model("dall-e-3"));
+    }
+
+    #[test]
+    fn test_openai_provider_with_settings() {
+        let settings = GenerationSettings {
+            temperature: Some(0.7),
+            ..Default::default()
+        };
+        let provider = OpenAIProvider::new("key".to_string(), "model".to_string())
+            .with_settings(settings.clone());
+        assert_eq!(provider.settings, settings);
     }
 }
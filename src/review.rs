@@ -0,0 +1,225 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Actions offered on the interactive review screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewAction {
+    Accept,
+    EditTitle,
+    EditBody,
+    OpenEditor,
+    EditCommand,
+    Regenerate,
+    Cancel,
+}
+
+/// Key hints shown under the message on the review screen.
+pub const ACTION_HINT: &str =
+    "[Enter/a] accept  [t] edit title  [b] edit body  [e] $EDITOR  [g] git command  [r] regenerate  [q] cancel";
+
+/// Parses the user's choice on the review screen. An empty answer accepts.
+pub fn parse_action(input: &str) -> Option<ReviewAction> {
+    match input.trim().to_lowercase().as_str() {
+        "" | "a" | "accept" | "y" | "yes" => Some(ReviewAction::Accept),
+        "t" | "title" => Some(ReviewAction::EditTitle),
+        "b" | "body" => Some(ReviewAction::EditBody),
+        "e" | "editor" => Some(ReviewAction::OpenEditor),
+        "g" | "command" => Some(ReviewAction::EditCommand),
+        "r" | "regenerate" => Some(ReviewAction::Regenerate),
+        "q" | "c" | "cancel" | "quit" | "n" | "no" => Some(ReviewAction::Cancel),
+        _ => None,
+    }
+}
+
+/// Parses a 1-based choice from a numbered list of `count` items into an
+/// index. An empty answer picks the first item.
+pub fn parse_choice(input: &str, count: usize) -> Option<usize> {
+    let input = input.trim();
+    if input.is_empty() {
+        return (count > 0).then_some(0);
+    }
+    input
+        .parse::<usize>()
+        .ok()
+        .filter(|choice| (1..=count).contains(choice))
+        .map(|choice| choice - 1)
+}
+
+/// Joins a title and body into a commit message, omitting an empty body.
+pub fn compose_message(title: &str, body: &str) -> String {
+    let title = title.trim();
+    let body = body.trim();
+    if body.is_empty() {
+        title.to_string()
+    } else {
+        format!("{}\n\n{}", title, body)
+    }
+}
+
+/// Splits a commit message into its title (first line) and body.
+pub fn split_message(message: &str) -> (String, String) {
+    let message = message.trim();
+    match message.split_once('\n') {
+        Some((title, body)) => (title.trim().to_string(), body.trim().to_string()),
+        None => (message.to_string(), String::new()),
+    }
+}
+
+/// Removes `#` comment lines, as git does for edited commit messages.
+pub fn strip_comments(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Opens `message` in `editor`, a shell snippet like git's `core.editor`,
+/// through the file at `path`, and returns the edited text with comment
+/// lines removed. Like `git commit`, callers pass `COMMIT_EDITMSG` in the
+/// git directory rather than a predictable name in the shared temp dir.
+pub fn edit_in_editor(message: &str, editor: &str, path: &Path) -> Result<String, String> {
+    let template = format!(
+        "{}\n\n# Edit the commit message above. Lines starting with '#' are ignored.\n",
+        message
+    );
+    fs::write(path, template).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    let status = editor_command(editor, path)
+        .status()
+        .map_err(|e| format!("Failed to launch editor '{}': {}", editor, e));
+
+    status.and_then(|status| {
+        if status.success() {
+            fs::read_to_string(path)
+                .map(|edited| strip_comments(&edited).trim().to_string())
+                .map_err(|e| format!("Failed to read edited message: {}", e))
+        } else {
+            Err(format!("Editor '{}' exited with {}", editor, status))
+        }
+    })
+}
+
+/// Editors are configured as shell snippets (e.g. "code --wait"), so run
+/// them through the shell like git does, passing the path as an argument.
+#[cfg(not(windows))]
+fn editor_command(editor: &str, path: &Path) -> Command {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(path);
+    command
+}
+
+/// Windows has no `sh` outside Git Bash, so the snippet goes through `cmd`.
+/// The line is passed raw since `cmd` doesn't follow the usual quoting rules.
+#[cfg(windows)]
+fn editor_command(editor: &str, path: &Path) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    let mut command = Command::new("cmd");
+    command
+        .arg("/C")
+        .raw_arg(format!("\"{} \"{}\"\"", editor, path.display()));
+    command
+}
+
+/// The editor git would use, honouring GIT_EDITOR, core.editor, VISUAL and EDITOR.
+pub fn git_editor() -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("var")
+        .arg("GIT_EDITOR")
+        .output()
+        .map_err(|e| format!("Failed to execute 'git var GIT_EDITOR': {}", e))?;
+
+    let editor = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if output.status.success() && !editor.is_empty() {
+        Ok(editor)
+    } else {
+        Ok(std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(parse_action(""), Some(ReviewAction::Accept));
+        assert_eq!(parse_action(" T "), Some(ReviewAction::EditTitle));
+        assert_eq!(parse_action("body"), Some(ReviewAction::EditBody));
+        assert_eq!(parse_action("e"), Some(ReviewAction::OpenEditor));
+        assert_eq!(parse_action("g"), Some(ReviewAction::EditCommand));
+        assert_eq!(parse_action("r"), Some(ReviewAction::Regenerate));
+        assert_eq!(parse_action("q"), Some(ReviewAction::Cancel));
+        assert_eq!(parse_action("x"), None);
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        assert_eq!(parse_choice("", 3), Some(0));
+        assert_eq!(parse_choice(" 2 ", 3), Some(1));
+        assert_eq!(parse_choice("3", 3), Some(2));
+        assert_eq!(parse_choice("0", 3), None);
+        assert_eq!(parse_choice("4", 3), None);
+        assert_eq!(parse_choice("two", 3), None);
+        assert_eq!(parse_choice("", 0), None);
+    }
+
+    #[test]
+    fn test_compose_and_split_roundtrip() {
+        let message = compose_message("feat: add parser", "Adds a parser.\n\nWith details.");
+        assert_eq!(
+            message,
+            "feat: add parser\n\nAdds a parser.\n\nWith details."
+        );
+        let (title, body) = split_message(&message);
+        assert_eq!(title, "feat: add parser");
+        assert_eq!(body, "Adds a parser.\n\nWith details.");
+    }
+
+    #[test]
+    fn test_compose_message_without_body() {
+        assert_eq!(compose_message("fix: typo ", "  \n"), "fix: typo");
+        assert_eq!(
+            split_message("fix: typo"),
+            ("fix: typo".to_string(), String::new())
+        );
+    }
+
+    #[test]
+    fn test_edit_in_editor() {
+        let path = std::env::temp_dir().join(format!(
+            "commitcraft-review-test-{}-EDITMSG",
+            std::process::id()
+        ));
+        // The stub editor replaces the message with a prepared one
+        let replacement = path.with_extension("new");
+        fs::write(&replacement, "feat: final title\n\nfinal body\n# comment\n").unwrap();
+        let copy = if cfg!(windows) { "copy /Y" } else { "cp" };
+        let edited = edit_in_editor(
+            "feat: draft title\n\ndraft body",
+            &format!("{} \"{}\"", copy, replacement.display()),
+            &path,
+        );
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(&replacement);
+        assert_eq!(edited.unwrap(), "feat: final title\n\nfinal body");
+
+        let fail = if cfg!(windows) { "exit 1" } else { "false" };
+        let failed = edit_in_editor("feat: x", fail, &path);
+        let _ = fs::remove_file(&path);
+        assert!(failed.unwrap_err().contains("exited with"));
+    }
+
+    #[test]
+    fn test_strip_comments() {
+        let text = "feat: add x\n\nbody line\n# comment\n#another";
+        assert_eq!(strip_comments(text), "feat: add x\n\nbody line");
+    }
+}
@@ -0,0 +1,351 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::conventional;
+use crate::providers::ScopeConstraint;
+
+/// What to do when the changed files belong to several scopes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MultipleScopes {
+    /// Leave the scope out of the title.
+    #[default]
+    Omit,
+    /// Join the scopes with commas, e.g. `api,db`.
+    Join,
+    /// Use the scope with the most changed files.
+    Most,
+}
+
+/// A glob mapped to a scope name, e.g. `crates/api/**` to `api`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScopeMapping {
+    pub glob: String,
+    pub scope: String,
+}
+
+/// How the scope of a commit is inferred from the changed files.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ScopeSettings {
+    /// Explicit mappings, checked in order before any detected scope.
+    pub map: Vec<ScopeMapping>,
+    /// Use Cargo and npm workspace members as scopes.
+    pub workspaces: bool,
+    /// Use the top-level directory of a file as its scope.
+    pub top_level_dirs: bool,
+    pub multiple: MultipleScopes,
+}
+
+impl Default for ScopeSettings {
+    fn default() -> Self {
+        Self {
+            map: Vec::new(),
+            workspaces: true,
+            top_level_dirs: false,
+            multiple: MultipleScopes::Omit,
+        }
+    }
+}
+
+/// The compiled scope rules of a repository.
+#[derive(Debug, Clone)]
+pub struct ScopeRules {
+    mappings: Vec<(Regex, String)>,
+    members: Vec<Regex>,
+    top_level_dirs: bool,
+    multiple: MultipleScopes,
+}
+
+impl ScopeRules {
+    /// Compiles `settings` and the workspace member globs.
+    pub fn new(settings: &ScopeSettings, members: &[String]) -> Result<Self, String> {
+        let mappings = settings
+            .map
+            .iter()
+            .map(|mapping| Ok((glob_regex(&mapping.glob)?, mapping.scope.clone())))
+            .collect::<Result<Vec<_>, String>>()?;
+        let members = members
+            .iter()
+            .map(|member| member.trim_start_matches("./").trim_end_matches('/'))
+            .filter(|member| !member.is_empty() && *member != ".")
+            .map(glob_regex)
+            .collect::<Result<Vec<_>, String>>()?;
+
+        Ok(ScopeRules {
+            mappings,
+            members,
+            top_level_dirs: settings.top_level_dirs,
+            multiple: settings.multiple,
+        })
+    }
+
+    /// Compiles `settings`, reading the workspace members from the manifests
+    /// in `root` when workspace detection is on.
+    pub fn load(settings: &ScopeSettings, root: &Path) -> Result<Self, String> {
+        let members = if settings.workspaces {
+            workspace_members(root)
+        } else {
+            Vec::new()
+        };
+        Self::new(settings, &members)
+    }
+
+    /// The scope of a single path, relative to the repository root.
+    pub fn scope_of(&self, path: &str) -> Option<String> {
+        if let Some((_, scope)) = self.mappings.iter().find(|(glob, _)| glob.is_match(path)) {
+            return Some(scope.clone());
+        }
+
+        // The deepest directory that is a workspace member
+        let dirs: Vec<&str> = path.match_indices('/').map(|(i, _)| &path[..i]).collect();
+        if let Some(dir) = dirs
+            .iter()
+            .rev()
+            .find(|dir| self.members.iter().any(|member| member.is_match(dir)))
+        {
+            return dir.rsplit('/').next().map(str::to_string);
+        }
+
+        if self.top_level_dirs {
+            return dirs.first().map(|dir| dir.to_string());
+        }
+        None
+    }
+
+    /// The scope the title of a commit touching `files` must have, or `None`
+    /// when no file has a known scope.
+    pub fn infer(&self, files: &[String]) -> Option<ScopeConstraint> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for scope in files.iter().filter_map(|file| self.scope_of(file)) {
+            match counts.iter_mut().find(|(known, _)| *known == scope) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((scope, 1)),
+            }
+        }
+
+        match counts.len() {
+            0 => None,
+            1 => Some(ScopeConstraint::Use(counts.remove(0).0)),
+            _ => match self.multiple {
+                MultipleScopes::Omit => Some(ScopeConstraint::Omit),
+                MultipleScopes::Join => Some(ScopeConstraint::Use(
+                    counts
+                        .into_iter()
+                        .map(|(scope, _)| scope)
+                        .collect::<Vec<_>>()
+                        .join(","),
+                )),
+                MultipleScopes::Most => {
+                    // The first scope wins ties
+                    let most = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+                    counts
+                        .into_iter()
+                        .find(|(_, count)| *count == most)
+                        .map(|(scope, _)| ScopeConstraint::Use(scope))
+                }
+            },
+        }
+    }
+}
+
+/// Rewrites the scope of a conventional title to match `constraint`. Titles
+/// that aren't conventional are returned unchanged.
+pub fn enforce(title: &str, constraint: &ScopeConstraint) -> String {
+    let Ok((commit_type, _, breaking, subject)) = conventional::parse_header(title) else {
+        return title.to_string();
+    };
+    let bang = if breaking { "!" } else { "" };
+    match constraint {
+        ScopeConstraint::Use(scope) => format!("{}({}){}: {}", commit_type, scope, bang, subject),
+        ScopeConstraint::Omit => format!("{}{}: {}", commit_type, bang, subject),
+    }
+}
+
+/// The member globs of the Cargo and npm workspaces at `root`. Missing or
+/// invalid manifests are skipped.
+pub fn workspace_members(root: &Path) -> Vec<String> {
+    let mut members = Vec::new();
+    if let Ok(content) = fs::read_to_string(root.join("Cargo.toml")) {
+        members.extend(cargo_members(&content));
+    }
+    if let Ok(content) = fs::read_to_string(root.join("package.json")) {
+        members.extend(npm_workspaces(&content));
+    }
+    members
+}
+
+/// The `[workspace] members` of a `Cargo.toml`.
+fn cargo_members(content: &str) -> Vec<String> {
+    let Ok(manifest) = content.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    manifest
+        .get("workspace")
+        .and_then(|workspace| workspace.get("members"))
+        .and_then(|members| members.as_array())
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|member| member.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The `workspaces` of a `package.json`, either a list or `{ "packages": [...] }`.
+fn npm_workspaces(content: &str) -> Vec<String> {
+    let Ok(package) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    let workspaces = package.get("workspaces");
+    workspaces
+        .and_then(|workspaces| workspaces.as_array())
+        .or_else(|| {
+            workspaces
+                .and_then(|workspaces| workspaces.get("packages"))
+                .and_then(|packages| packages.as_array())
+        })
+        .map(|members| {
+            members
+                .iter()
+                .filter_map(|member| member.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Compiles a glob matched against the whole path: `*` and `?` stay within a
+/// path segment and `**` spans any number of them.
+fn glob_regex(glob: &str) -> Result<Regex, String> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(|e| format!("Invalid scope glob '{}': {}", glob, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn test_glob_regex() {
+        let glob = glob_regex("crates/api/**").unwrap();
+        assert!(glob.is_match("crates/api/src/lib.rs"));
+        assert!(!glob.is_match("crates/apix/src/lib.rs"));
+        let glob = glob_regex("docs/*.md").unwrap();
+        assert!(glob.is_match("docs/intro.md"));
+        assert!(!glob.is_match("docs/guide/intro.md"));
+        let glob = glob_regex("**/migrations/?.sql").unwrap();
+        assert!(glob.is_match("migrations/1.sql"));
+        assert!(glob.is_match("db/migrations/2.sql"));
+        assert!(!glob.is_match("db/migrations/10.sql"));
+    }
+
+    #[test]
+    fn test_scope_of() {
+        let settings = ScopeSettings {
+            map: vec![ScopeMapping {
+                glob: "crates/api/**".to_string(),
+                scope: "http".to_string(),
+            }],
+            top_level_dirs: true,
+            ..Default::default()
+        };
+        let rules = ScopeRules::new(&settings, &files(&["crates/*", "./tools/cli/"])).unwrap();
+        assert_eq!(rules.scope_of("crates/api/src/lib.rs").unwrap(), "http");
+        assert_eq!(rules.scope_of("crates/db/src/lib.rs").unwrap(), "db");
+        assert_eq!(rules.scope_of("tools/cli/main.rs").unwrap(), "cli");
+        assert_eq!(rules.scope_of("docs/intro.md").unwrap(), "docs");
+        assert_eq!(rules.scope_of("README.md"), None);
+
+        let rules = ScopeRules::new(&ScopeSettings::default(), &[]).unwrap();
+        assert_eq!(rules.scope_of("docs/intro.md"), None);
+    }
+
+    #[test]
+    fn test_infer_multiple_scopes() {
+        let changed = files(&[
+            "crates/db/src/lib.rs",
+            "crates/api/src/lib.rs",
+            "crates/api/src/routes.rs",
+            "Cargo.lock",
+        ]);
+        let rules = |multiple| {
+            let settings = ScopeSettings {
+                multiple,
+                ..Default::default()
+            };
+            ScopeRules::new(&settings, &files(&["crates/*"])).unwrap()
+        };
+        assert_eq!(
+            rules(MultipleScopes::Omit).infer(&changed),
+            Some(ScopeConstraint::Omit)
+        );
+        assert_eq!(
+            rules(MultipleScopes::Join).infer(&changed),
+            Some(ScopeConstraint::Use("db,api".to_string()))
+        );
+        assert_eq!(
+            rules(MultipleScopes::Most).infer(&changed),
+            Some(ScopeConstraint::Use("api".to_string()))
+        );
+        assert_eq!(
+            rules(MultipleScopes::Omit).infer(&changed[1..]),
+            Some(ScopeConstraint::Use("api".to_string()))
+        );
+        assert_eq!(rules(MultipleScopes::Omit).infer(&changed[3..]), None);
+    }
+
+    #[test]
+    fn test_enforce() {
+        let api = ScopeConstraint::Use("api".to_string());
+        assert_eq!(enforce("feat: add routes", &api), "feat(api): add routes");
+        assert_eq!(enforce("feat(web)!: drop v1", &api), "feat(api)!: drop v1");
+        assert_eq!(
+            enforce("fix(api): handle errors", &ScopeConstraint::Omit),
+            "fix: handle errors"
+        );
+        assert_eq!(enforce("Add routes", &api), "Add routes");
+    }
+
+    #[test]
+    fn test_workspace_manifests() {
+        assert_eq!(
+            cargo_members("[workspace]\nmembers = [\"crates/*\", \"xtask\"]\n"),
+            vec!["crates/*", "xtask"]
+        );
+        assert!(cargo_members("[package]\nname = \"app\"\n").is_empty());
+        assert_eq!(
+            npm_workspaces(r#"{"workspaces": ["packages/*"]}"#),
+            vec!["packages/*"]
+        );
+        assert_eq!(
+            npm_workspaces(r#"{"workspaces": {"packages": ["apps/*"]}}"#),
+            vec!["apps/*"]
+        );
+        assert!(npm_workspaces("not json").is_empty());
+    }
+}
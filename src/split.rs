@@ -0,0 +1,422 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::diff::strip_diff_prefix;
+use crate::git::{self, CommitOptions};
+use crate::providers::SplitPlan;
+use crate::review;
+
+/// The changes to one file in a patch.
+#[derive(Debug, Clone, PartialEq)]
+struct FilePatch {
+    /// Everything before the first hunk: the `diff --git`, mode, rename and
+    /// `---`/`+++` lines.
+    header: Vec<u8>,
+    /// Parts that can be staged separately. A file that is added, deleted,
+    /// renamed, copied, binary or changes mode has a single part. They are
+    /// bytes, so content that isn't UTF-8 is committed unchanged.
+    parts: Vec<Vec<u8>>,
+}
+
+/// A change that can be staged on its own: one hunk of a modified file, or
+/// every change to a file that has to be staged whole.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// The 1-based number the model refers to the hunk by.
+    pub number: usize,
+    pub path: String,
+    /// Why the file is staged whole, e.g. `new file`; `None` for a hunk of a
+    /// modified file.
+    pub kind: Option<&'static str>,
+    file: usize,
+    part: usize,
+}
+
+/// The staged changes broken into numbered hunks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StagedHunks {
+    files: Vec<FilePatch>,
+    hunks: Vec<Hunk>,
+}
+
+impl StagedHunks {
+    /// Parses a patch from [`git::get_staged_patch`].
+    pub fn parse(patch: &[u8]) -> Self {
+        let mut sections: Vec<Vec<&[u8]>> = Vec::new();
+        for line in patch.split_inclusive(|&byte| byte == b'\n') {
+            match sections.last_mut() {
+                Some(section) if !line.starts_with(b"diff --git ") => section.push(line),
+                _ => sections.push(vec![line]),
+            }
+        }
+
+        let mut files = Vec::new();
+        let mut hunks = Vec::new();
+        for section in sections {
+            let hunk_start = section
+                .iter()
+                .position(|line| line.starts_with(b"@@ ") || line.starts_with(b"GIT binary patch"))
+                .unwrap_or(section.len());
+            let header = section[..hunk_start].concat();
+            let kind = whole_file_kind(&section);
+
+            let parts = match kind {
+                Some(_) => vec![section[hunk_start..].concat()],
+                None => {
+                    let mut parts: Vec<Vec<u8>> = Vec::new();
+                    for line in &section[hunk_start..] {
+                        match parts.last_mut() {
+                            Some(part) if !line.starts_with(b"@@ ") => part.extend_from_slice(line),
+                            _ => parts.push(line.to_vec()),
+                        }
+                    }
+                    parts
+                }
+            };
+
+            let path = file_path(&section[..hunk_start]);
+            for part in 0..parts.len() {
+                hunks.push(Hunk {
+                    number: hunks.len() + 1,
+                    path: path.clone(),
+                    kind,
+                    file: files.len(),
+                    part,
+                });
+            }
+            files.push(FilePatch { header, parts });
+        }
+
+        StagedHunks { files, hunks }
+    }
+
+    pub fn hunks(&self) -> &[Hunk] {
+        &self.hunks
+    }
+
+    pub fn len(&self) -> usize {
+        self.hunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hunks.is_empty()
+    }
+
+    /// The hunks as numbered sections for the model to group. Binary
+    /// contents are left out.
+    pub fn prompt(&self) -> String {
+        self.hunks
+            .iter()
+            .map(|hunk| {
+                let file = &self.files[hunk.file];
+                let header = String::from_utf8_lossy(&file.header);
+                let part = String::from_utf8_lossy(&file.parts[hunk.part]);
+                let content = match hunk.kind {
+                    Some("binary") => header.into_owned(),
+                    Some(_) => format!("{}{}", header, part),
+                    None => part.into_owned(),
+                };
+                let label = match hunk.kind {
+                    Some(kind) => format!("Hunk {}: {} ({})", hunk.number, hunk.path, kind),
+                    None => format!("Hunk {}: {}", hunk.number, hunk.path),
+                };
+                format!("{}\n{}", label, content.trim_end())
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// A patch with only the given hunks, which applies to the tree the
+    /// staged changes were made against.
+    pub fn patch(&self, numbers: &[usize]) -> Vec<u8> {
+        let selected: HashSet<usize> = numbers.iter().copied().collect();
+        let mut patch = Vec::new();
+        for (index, file) in self.files.iter().enumerate() {
+            let parts: Vec<&Hunk> = self
+                .hunks
+                .iter()
+                .filter(|hunk| hunk.file == index && selected.contains(&hunk.number))
+                .collect();
+            if parts.is_empty() {
+                continue;
+            }
+            patch.extend_from_slice(&file.header);
+            for hunk in parts {
+                patch.extend_from_slice(&file.parts[hunk.part]);
+            }
+        }
+        patch
+    }
+}
+
+/// Why a file's changes can't be split into hunks, if they can't.
+fn whole_file_kind(section: &[&[u8]]) -> Option<&'static str> {
+    let has = |prefix: &str| {
+        section
+            .iter()
+            .any(|line| line.starts_with(prefix.as_bytes()))
+    };
+    if has("GIT binary patch") || has("Binary files ") {
+        Some("binary")
+    } else if has("new file mode") {
+        Some("new file")
+    } else if has("deleted file mode") {
+        Some("deleted file")
+    } else if has("rename from ") {
+        Some("renamed")
+    } else if has("copy from ") {
+        Some("copied")
+    } else if has("old mode ") {
+        Some("mode change")
+    } else if !has("@@ ") {
+        Some("whole file")
+    } else {
+        None
+    }
+}
+
+/// The path a file section of a patch ends up at.
+fn file_path(header: &[&[u8]]) -> String {
+    let header: Vec<String> = header
+        .iter()
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect();
+    let find = |prefix: &str| {
+        header
+            .iter()
+            .find_map(|line| line.strip_prefix(prefix))
+            .map(|path| path.trim_end_matches('\n'))
+    };
+    find("+++ ")
+        .and_then(strip_diff_prefix)
+        .or_else(|| find("--- ").and_then(strip_diff_prefix))
+        .or_else(|| find("rename to ").map(str::to_string))
+        .or_else(|| find("copy to ").map(str::to_string))
+        .or_else(|| {
+            find("diff --git ")
+                .and_then(|paths| paths.rsplit_once(" b/"))
+                .map(|(_, path)| path.to_string())
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Checks that the plan has commits, each with hunks, and that every hunk
+/// exists and is used at most once. Returns the hunks the plan leaves out.
+pub fn check_plan(plan: &SplitPlan, hunk_count: usize) -> Result<Vec<usize>, String> {
+    if plan.commits.is_empty() {
+        return Err("The proposed plan has no commits".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for (index, commit) in plan.commits.iter().enumerate() {
+        if commit.hunks.is_empty() {
+            return Err(format!(
+                "Commit {} ('{}') has no hunks",
+                index + 1,
+                commit.title
+            ));
+        }
+        for &number in &commit.hunks {
+            if number == 0 || number > hunk_count {
+                return Err(format!("Hunk {} does not exist", number));
+            }
+            if !seen.insert(number) {
+                return Err(format!("Hunk {} is in more than one commit", number));
+            }
+        }
+    }
+
+    Ok((1..=hunk_count)
+        .filter(|number| !seen.contains(number))
+        .collect())
+}
+
+/// Creates the commits of `plan` in order.
+///
+/// The index of each commit is the original `HEAD` tree plus the hunks of
+/// that commit and every earlier one, so hunks of the same file can go to
+/// different commits. Hunks the plan leaves out stay staged. If anything
+/// fails, or `interrupted` is set before the next commit, the original
+/// index is restored and the error says how many commits were created.
+/// Every commit is made with `options`.
+pub fn create_commits(
+    hunks: &StagedHunks,
+    plan: &SplitPlan,
+    options: &CommitOptions,
+    interrupted: &AtomicBool,
+) -> Result<usize, String> {
+    let original = git::write_index_tree()?;
+    let base = git::head_tree()?;
+
+    let mut included = Vec::new();
+    for (created, commit) in plan.commits.iter().enumerate() {
+        included.extend(commit.hunks.iter().copied());
+        let message = review::compose_message(&commit.title, &commit.description);
+        let result = if interrupted.load(Ordering::SeqCst) {
+            Err("Interrupted.".to_string())
+        } else {
+            git::read_index_tree(&base)
+                .and_then(|()| git::apply_to_index(&hunks.patch(&included)))
+                .and_then(|()| git::commit_with(&message, options))
+        };
+
+        if let Err(e) = result {
+            let restored = match git::read_index_tree(&original) {
+                Ok(()) => "the remaining changes are staged again".to_string(),
+                Err(restore_error) => format!(
+                    "restoring the index failed ({}); run 'git read-tree {}' to restore it",
+                    restore_error, original
+                ),
+            };
+            return Err(format!(
+                "{}\nCreated {} of {} commits; {}.",
+                e.trim(),
+                created,
+                plan.commits.len(),
+                restored
+            ));
+        }
+    }
+
+    git::read_index_tree(&original)?;
+    Ok(plan.commits.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::PlannedCommit;
+
+    const PATCH: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,4 @@
++pub mod split;
+ pub mod cli;
+ pub mod diff;
+ pub mod git;
+@@ -20,3 +21,3 @@ fn main() {
+ a
+-b
++c
+ d
+diff --git a/old.rs b/new.rs
+similarity index 90%
+rename from old.rs
+rename to new.rs
+index 3333333..4444444 100644
+--- a/old.rs
++++ b/new.rs
+@@ -1 +1 @@
+-old
++new
+diff --git a/added.txt b/added.txt
+new file mode 100644
+index 0000000..5555555
+--- /dev/null
++++ b/added.txt
+@@ -0,0 +1 @@
++hello
+diff --git a/moved.txt b/renamed.txt
+similarity index 100%
+rename from moved.txt
+rename to renamed.txt
+diff --git a/logo.png b/logo.png
+index 6666666..7777777 100644
+GIT binary patch
+literal 4
+LcmZQzWMT#Y01f~L
+
+literal 0
+HcmV?d00001
+
+";
+
+    fn plan(groups: &[&[usize]]) -> SplitPlan {
+        SplitPlan {
+            commits: groups
+                .iter()
+                .map(|hunks| PlannedCommit {
+                    title: "chore: x".to_string(),
+                    description: String::new(),
+                    hunks: hunks.to_vec(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_hunks() {
+        let staged = StagedHunks::parse(PATCH.as_bytes());
+        let summary: Vec<(usize, &str, Option<&str>)> = staged
+            .hunks()
+            .iter()
+            .map(|hunk| (hunk.number, hunk.path.as_str(), hunk.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, "src/lib.rs", None),
+                (2, "src/lib.rs", None),
+                (3, "new.rs", Some("renamed")),
+                (4, "added.txt", Some("new file")),
+                (5, "renamed.txt", Some("renamed")),
+                (6, "logo.png", Some("binary")),
+            ]
+        );
+        assert!(StagedHunks::parse(b"").is_empty());
+    }
+
+    #[test]
+    fn test_patch_selects_hunks() {
+        let staged = StagedHunks::parse(PATCH.as_bytes());
+        let patch = String::from_utf8(staged.patch(&[2])).unwrap();
+        assert!(patch.starts_with("diff --git a/src/lib.rs b/src/lib.rs\n"));
+        assert!(patch.contains("@@ -20,3 +21,3 @@"));
+        assert!(!patch.contains("+pub mod split;"));
+        assert!(!patch.contains("new.rs"));
+
+        let patch = String::from_utf8(staged.patch(&[5, 3])).unwrap();
+        assert!(patch.starts_with("diff --git a/old.rs b/new.rs\n"));
+        assert!(patch.contains("rename from moved.txt\nrename to renamed.txt\n"));
+
+        let all: Vec<usize> = (1..=staged.len()).collect();
+        assert_eq!(staged.patch(&all), PATCH.as_bytes());
+    }
+
+    #[test]
+    fn test_patch_keeps_bytes_that_are_not_utf8() {
+        let patch = b"diff --git a/caf\xe9.txt b/caf\xe9.txt\nindex 1111111..2222222 100644\n--- a/caf\xe9.txt\n+++ b/caf\xe9.txt\n@@ -1 +1 @@\n-caf\n+caf\xe9\n";
+        let staged = StagedHunks::parse(patch);
+        assert_eq!(staged.hunks()[0].path, "caf\u{FFFD}.txt");
+        assert_eq!(staged.patch(&[1]), patch);
+        assert!(staged.prompt().contains("+caf\u{FFFD}"));
+    }
+
+    #[test]
+    fn test_prompt_omits_binary_content() {
+        let prompt = StagedHunks::parse(PATCH.as_bytes()).prompt();
+        assert!(prompt.starts_with("Hunk 1: src/lib.rs\n@@ -1,3 +1,4 @@"));
+        assert!(prompt.contains("Hunk 4: added.txt (new file)\ndiff --git a/added.txt"));
+        assert!(prompt.contains("Hunk 6: logo.png (binary)"));
+        assert!(!prompt.contains("literal 4"));
+    }
+
+    #[test]
+    fn test_check_plan() {
+        assert_eq!(check_plan(&plan(&[&[1, 3], &[2]]), 4).unwrap(), vec![4]);
+        assert!(check_plan(&plan(&[&[1, 2, 3, 4]]), 4).unwrap().is_empty());
+        assert_eq!(
+            check_plan(&plan(&[&[1], &[1, 2]]), 2).unwrap_err(),
+            "Hunk 1 is in more than one commit"
+        );
+        assert_eq!(
+            check_plan(&plan(&[&[3]]), 2).unwrap_err(),
+            "Hunk 3 does not exist"
+        );
+        assert!(check_plan(&plan(&[&[0]]), 2).is_err());
+        assert!(check_plan(&plan(&[&[1], &[]]), 2).is_err());
+        assert!(check_plan(&plan(&[]), 2).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::conventional::{self, Footer};
+use crate::git::LoggedCommit;
+
+/// Splits `base..head` or `base...head` into its ends. A single revision is
+/// the branch to squash into `HEAD`, as in `git merge --squash <branch>`.
+pub fn parse_range(range: &str) -> (String, String) {
+    let or_head = |rev: &str| {
+        if rev.is_empty() {
+            "HEAD".to_string()
+        } else {
+            rev.to_string()
+        }
+    };
+    match range.split_once("...").or_else(|| range.split_once("..")) {
+        Some((base, head)) => (or_head(base), or_head(head)),
+        None => ("HEAD".to_string(), range.to_string()),
+    }
+}
+
+/// The request for a single message summarising `commits`, with the net
+/// diff of the branch.
+pub fn prompt(commits: &[LoggedCommit], diff: &str) -> String {
+    let mut content = String::from(
+        "These commits are squashed into one. Write a single commit message that summarizes all of them, based on their messages and the net diff.\n\nCommit messages, oldest first:\n",
+    );
+    for commit in commits.iter().rev() {
+        content.push_str(&format!("- {}\n", commit.message.replace('\n', "\n  ")));
+    }
+    content.push_str(&format!(
+        "\nHere is the net diff of the squashed commits:\n```diff\n{}\n```",
+        diff
+    ));
+    content
+}
+
+/// The footers of `commits` that must survive the squash: breaking changes,
+/// including those only marked with `!`, and co-authors. Oldest first and
+/// without duplicates.
+pub fn preserved_footers(commits: &[LoggedCommit]) -> Vec<Footer> {
+    let mut preserved: Vec<Footer> = Vec::new();
+    for commit in commits.iter().rev() {
+        let footers = match conventional::parse(&commit.message) {
+            Ok(parsed) => {
+                let mut footers: Vec<Footer> = parsed
+                    .footers
+                    .iter()
+                    .filter(|footer| is_co_author(footer))
+                    .cloned()
+                    .collect();
+                if let Some(description) = parsed.breaking_description() {
+                    footers.insert(
+                        0,
+                        Footer {
+                            token: "BREAKING CHANGE".to_string(),
+                            value: description.to_string(),
+                        },
+                    );
+                }
+                footers
+            }
+            // Trailers of messages that aren't conventional are kept too
+            Err(_) => {
+                let body = commit.message.split_once('\n').map_or("", |(_, body)| body);
+                conventional::split_footers(body)
+                    .1
+                    .into_iter()
+                    .filter(|footer| footer.is_breaking_change() || is_co_author(footer))
+                    .collect()
+            }
+        };
+        for footer in footers {
+            if !preserved.iter().any(|known| same_footer(known, &footer)) {
+                preserved.push(footer);
+            }
+        }
+    }
+    // Breaking changes come before the co-author trailers
+    preserved.sort_by_key(is_co_author);
+    preserved
+}
+
+/// Adds the `footers` that `description` doesn't already have.
+pub fn apply_footers(description: &str, footers: &[Footer]) -> String {
+    let (_, existing) = conventional::split_footers(description);
+    footers
+        .iter()
+        .filter(|footer| !existing.iter().any(|known| same_footer(known, footer)))
+        .fold(description.trim_end().to_string(), |description, footer| {
+            conventional::append_footer(
+                &description,
+                &format!("{}: {}", footer.token, footer.value),
+            )
+        })
+}
+
+fn is_co_author(footer: &Footer) -> bool {
+    footer.token.eq_ignore_ascii_case("Co-authored-by")
+}
+
+fn same_footer(a: &Footer, b: &Footer) -> bool {
+    let token = |footer: &Footer| {
+        if footer.is_breaking_change() {
+            "breaking change".to_string()
+        } else {
+            footer.token.to_lowercase()
+        }
+    };
+    token(a) == token(b) && a.value.trim().eq_ignore_ascii_case(b.value.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(messages: &[&str]) -> Vec<LoggedCommit> {
+        messages
+            .iter()
+            .map(|message| LoggedCommit {
+                hash: "abc".to_string(),
+                message: message.to_string(),
+            })
+            .collect()
+    }
+
+    fn footer(token: &str, value: &str) -> Footer {
+        Footer {
+            token: token.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let range = |base: &str, head: &str| (base.to_string(), head.to_string());
+        assert_eq!(parse_range("main..feature"), range("main", "feature"));
+        assert_eq!(parse_range("main...feature"), range("main", "feature"));
+        assert_eq!(parse_range("main.."), range("main", "HEAD"));
+        assert_eq!(parse_range("feature"), range("HEAD", "feature"));
+    }
+
+    #[test]
+    fn test_preserved_footers() {
+        // Newest first, as git log lists them
+        let commits = log(&[
+            "fix: handle errors\n\nCo-authored-by: Ann <ann@example.com>",
+            "wip\n\nco-authored-by: Ann <ann@example.com>\nCo-authored-by: Bo <bo@example.com>",
+            "feat(api)!: drop v1\n\nRefs: #12",
+            "feat: add v2\n\nBREAKING CHANGE: clients must send a token\nCo-authored-by: Bo <bo@example.com>",
+        ]);
+        assert_eq!(
+            preserved_footers(&commits),
+            vec![
+                footer("BREAKING CHANGE", "clients must send a token"),
+                footer("BREAKING CHANGE", "drop v1"),
+                footer("Co-authored-by", "Bo <bo@example.com>"),
+                footer("co-authored-by", "Ann <ann@example.com>"),
+            ]
+        );
+        assert!(preserved_footers(&log(&["fix: typo"])).is_empty());
+    }
+
+    #[test]
+    fn test_apply_footers() {
+        let footers = vec![
+            footer("BREAKING CHANGE", "drop v1"),
+            footer("Co-authored-by", "Bo <bo@example.com>"),
+        ];
+        assert_eq!(
+            apply_footers("Adds v2.", &footers),
+            "Adds v2.\n\nBREAKING CHANGE: drop v1\nCo-authored-by: Bo <bo@example.com>"
+        );
+        // Footers the model already wrote are not repeated
+        assert_eq!(
+            apply_footers("Adds v2.\n\nBREAKING-CHANGE: drop v1", &footers),
+            "Adds v2.\n\nBREAKING-CHANGE: drop v1\nCo-authored-by: Bo <bo@example.com>"
+        );
+    }
+
+    #[test]
+    fn test_prompt_lists_messages_oldest_first() {
+        let prompt = prompt(&log(&["fix: second", "feat: first"]), "diff");
+        assert!(prompt.contains("oldest first:\n- feat: first\n- fix: second\n"));
+        assert!(prompt.ends_with("```diff\ndiff\n```"));
+    }
+}
@@ -0,0 +1,272 @@
+/// The most commits listed for each side of a submodule bump.
+const MAX_LOGGED_COMMITS: usize = 20;
+
+/// A submodule whose recorded commit a diff changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bump {
+    pub path: String,
+    /// The commit recorded before, `None` for a new submodule.
+    pub old: Option<String>,
+    /// The commit recorded after, `None` for a removed submodule.
+    pub new: Option<String>,
+}
+
+/// The subjects of the commits a bump brings in and of those it goes back
+/// past, newest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BumpLog {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(7)]
+}
+
+impl Bump {
+    /// Describes the bump as `git diff --submodule=log` does, listing the
+    /// commits in `log`, or noting that they aren't available.
+    pub fn describe(&self, log: Option<&BumpLog>) -> String {
+        let (old, new) = match (&self.old, &self.new) {
+            (Some(old), Some(new)) => (old, new),
+            (None, new) => {
+                let new = new.as_deref().unwrap_or_default();
+                return format!(
+                    "Submodule {} 0000000...{} (new submodule)",
+                    self.path,
+                    short(new)
+                );
+            }
+            (Some(old), None) => {
+                return format!(
+                    "Submodule {} {}...0000000 (submodule deleted)",
+                    self.path,
+                    short(old)
+                );
+            }
+        };
+        let range = format!("Submodule {} {}..{}", self.path, short(old), short(new));
+        let Some(log) = log else {
+            return format!("{} (commits not present)", range);
+        };
+
+        let rewind = log.added.is_empty() && !log.removed.is_empty();
+        let mut description = format!("{}{}:", range, if rewind { " (rewind)" } else { "" });
+        for (marker, subjects) in [('<', &log.removed), ('>', &log.added)] {
+            for subject in subjects.iter().take(MAX_LOGGED_COMMITS) {
+                description.push_str(&format!("\n  {} {}", marker, subject));
+            }
+            if subjects.len() > MAX_LOGGED_COMMITS {
+                description.push_str(&format!(
+                    "\n  {} and {} more",
+                    marker,
+                    subjects.len() - MAX_LOGGED_COMMITS
+                ));
+            }
+        }
+        description
+    }
+}
+
+/// Splits `diff` into the sections of its files, each starting with its
+/// `diff --git` line. Anything before the first one is a section too.
+fn sections(diff: &str) -> Vec<&str> {
+    let mut starts = vec![0];
+    let mut offset = 0;
+    for line in diff.split_inclusive('\n') {
+        if line.starts_with("diff --git ") && offset > 0 {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    starts.push(diff.len());
+    starts
+        .windows(2)
+        .map(|range| &diff[range[0]..range[1]])
+        .collect()
+}
+
+/// The submodule bump a file section of a diff records, if it's one.
+/// Submodules are the entries with mode 160000, whose content git shows as
+/// `Subproject commit <hash>`.
+fn section_bump(section: &str) -> Option<Bump> {
+    let header = section.split("\n@@").next().unwrap_or_default();
+    if !header.starts_with("diff --git ") || !header.contains("160000") {
+        return None;
+    }
+    let recorded = |prefix: &str| {
+        section.lines().find_map(|line| {
+            let hash = line.strip_prefix(prefix)?.trim();
+            // Modified content inside the submodule doesn't change the commit
+            Some(hash.strip_suffix("-dirty").unwrap_or(hash).to_string())
+        })
+    };
+    let old = recorded("-Subproject commit ");
+    let new = recorded("+Subproject commit ");
+    if old == new {
+        return None;
+    }
+
+    let path = header
+        .lines()
+        .find_map(|line| line.strip_prefix("+++ b/"))
+        .or_else(|| header.lines().find_map(|line| line.strip_prefix("--- a/")))
+        .or_else(|| {
+            header
+                .lines()
+                .next()?
+                .split_once(" b/")
+                .map(|(_, path)| path)
+        })?;
+    Some(Bump {
+        path: path.trim().to_string(),
+        old,
+        new,
+    })
+}
+
+/// The submodule bumps in `diff`.
+pub fn bumps(diff: &str) -> Vec<Bump> {
+    sections(diff)
+        .into_iter()
+        .filter_map(section_bump)
+        .collect()
+}
+
+/// Follows the section of each submodule bump in `diff` with its
+/// description, so the model sees which commits it brings in rather than
+/// two opaque hashes. `log` reads the commits of a bump, `None` when they
+/// aren't available.
+pub fn expand(diff: &str, log: impl Fn(&Bump) -> Option<BumpLog>) -> String {
+    let mut expanded = String::with_capacity(diff.len());
+    for section in sections(diff) {
+        expanded.push_str(section);
+        if let Some(bump) = section_bump(section) {
+            if !expanded.ends_with('\n') {
+                expanded.push('\n');
+            }
+            expanded.push_str(&bump.describe(log(&bump).as_ref()));
+            expanded.push('\n');
+        }
+    }
+    if !diff.ends_with('\n') && expanded.ends_with('\n') {
+        expanded.pop();
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "1111111aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const NEW: &str = "2222222bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+    fn bump_section(path: &str, old: &str, new: &str) -> String {
+        format!(
+            "diff --git a/{path} b/{path}\nindex 1111111..2222222 160000\n--- a/{path}\n+++ b/{path}\n@@ -1 +1 @@\n-Subproject commit {old}\n+Subproject commit {new}\n"
+        )
+    }
+
+    const FILE_SECTION: &str = "diff --git a/src/lib.rs b/src/lib.rs\nindex 3333333..4444444 100644\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-Subproject commit 1\n+Subproject commit 2\n";
+
+    fn bump() -> Bump {
+        Bump {
+            path: "vendor/lib".to_string(),
+            old: Some(OLD.to_string()),
+            new: Some(NEW.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_bumps() {
+        let diff = format!(
+            "{}{}{}",
+            FILE_SECTION,
+            bump_section("vendor/lib", OLD, NEW),
+            bump_section("vendor/dirty", OLD, &format!("{}-dirty", OLD)),
+        );
+        assert_eq!(bumps(&diff), vec![bump()]);
+
+        let added = "diff --git a/ext b/ext\nnew file mode 160000\nindex 0000000..2222222\n--- /dev/null\n+++ b/ext\n@@ -0,0 +1 @@\n+Subproject commit 2222222bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\n";
+        let removed = "diff --git a/ext b/ext\ndeleted file mode 160000\nindex 1111111..0000000\n--- a/ext\n+++ /dev/null\n@@ -1 +0,0 @@\n-Subproject commit 1111111aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\n";
+        assert_eq!(
+            bumps(&format!("{}{}", added, removed)),
+            vec![
+                Bump {
+                    path: "ext".to_string(),
+                    old: None,
+                    new: Some(NEW.to_string()),
+                },
+                Bump {
+                    path: "ext".to_string(),
+                    old: Some(OLD.to_string()),
+                    new: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe() {
+        let log = BumpLog {
+            added: vec!["feat: add parser".to_string(), "fix: crash".to_string()],
+            removed: Vec::new(),
+        };
+        assert_eq!(
+            bump().describe(Some(&log)),
+            "Submodule vendor/lib 1111111..2222222:\n  > feat: add parser\n  > fix: crash"
+        );
+        let rewind = BumpLog {
+            added: Vec::new(),
+            removed: vec!["fix: crash".to_string()],
+        };
+        assert_eq!(
+            bump().describe(Some(&rewind)),
+            "Submodule vendor/lib 1111111..2222222 (rewind):\n  < fix: crash"
+        );
+        assert_eq!(
+            bump().describe(None),
+            "Submodule vendor/lib 1111111..2222222 (commits not present)"
+        );
+        let many = BumpLog {
+            added: (0..25).map(|n| format!("commit {}", n)).collect(),
+            removed: Vec::new(),
+        };
+        let description = bump().describe(Some(&many));
+        assert!(description.contains("  > commit 19\n  > and 5 more"));
+        assert!(!description.contains("commit 20"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let diff = format!("{}{}", bump_section("vendor/lib", OLD, NEW), FILE_SECTION);
+        let diff = diff.trim_end();
+        let expanded = expand(diff, |bump| {
+            assert_eq!(bump.path, "vendor/lib");
+            Some(BumpLog {
+                added: vec!["feat: add parser".to_string()],
+                removed: Vec::new(),
+            })
+        });
+        assert_eq!(
+            expanded,
+            format!(
+                "{}Submodule vendor/lib 1111111..2222222:\n  > feat: add parser\n{}",
+                bump_section("vendor/lib", OLD, NEW),
+                FILE_SECTION.trim_end()
+            )
+        );
+
+        let last = bump_section("vendor/lib", OLD, NEW);
+        assert_eq!(
+            expand(last.trim_end(), |_| None),
+            format!(
+                "{}Submodule vendor/lib 1111111..2222222 (commits not present)",
+                last
+            )
+        );
+        assert_eq!(expand(FILE_SECTION, |_| unreachable!()), FILE_SECTION);
+        assert_eq!(expand("", |_| unreachable!()), "");
+    }
+}
@@ -0,0 +1,357 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::conventional;
+use crate::review;
+
+/// Where ticket IDs are added to a commit message.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TicketPlacement {
+    /// A `Refs: PAY-123` footer.
+    #[default]
+    Footer,
+    /// A ` (PAY-123)` suffix on the title.
+    Title,
+}
+
+/// How ticket IDs are found in branch names and added to messages.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TicketSettings {
+    /// Regexes matched against the branch name. The first capture group is
+    /// the ID, or the whole match if there is no group.
+    pub patterns: Vec<String>,
+    pub placement: TicketPlacement,
+    /// The footer token, e.g. `Refs` or `Closes`.
+    pub footer_token: String,
+}
+
+impl Default for TicketSettings {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            placement: TicketPlacement::Footer,
+            footer_token: "Refs".to_string(),
+        }
+    }
+}
+
+/// The ticket IDs of the current branch and the rules for adding them.
+#[derive(Debug, Clone)]
+pub struct TicketRefs {
+    pub ids: Vec<String>,
+    placement: TicketPlacement,
+    footer_token: String,
+    patterns: Vec<Regex>,
+    /// The ID part of each pattern on its own, anchored to a whole value, so
+    /// footers can be checked without the branch context around the ID.
+    id_patterns: Vec<Regex>,
+}
+
+impl TicketRefs {
+    /// Extracts the ticket IDs from `branch`, in order of appearance.
+    pub fn from_branch(settings: &TicketSettings, branch: &str) -> Result<Self, String> {
+        let patterns = settings
+            .patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid ticket pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let id_patterns = settings
+            .patterns
+            .iter()
+            .filter_map(|pattern| first_group(pattern))
+            .filter_map(|group| Regex::new(&format!("^(?:{})$", group)).ok())
+            .collect();
+
+        let mut ids: Vec<String> = Vec::new();
+        for pattern in &patterns {
+            for captures in pattern.captures_iter(branch) {
+                let id = captures.get(1).or_else(|| captures.get(0));
+                if let Some(id) = id.map(|id| id.as_str().to_string()) {
+                    if !id.is_empty() && !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+            }
+        }
+
+        Ok(TicketRefs {
+            ids,
+            placement: settings.placement,
+            footer_token: settings.footer_token.clone(),
+            patterns,
+            id_patterns,
+        })
+    }
+
+    /// No ticket patterns are configured, so there is nothing to add or check.
+    pub fn is_disabled(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// Adds the IDs the message doesn't mention yet, in the configured place.
+    pub fn apply(&self, title: &mut String, description: &mut String) {
+        match self.placement {
+            TicketPlacement::Title => {
+                let missing: Vec<&str> = self
+                    .ids
+                    .iter()
+                    .filter(|id| !title.contains(id.as_str()))
+                    .map(String::as_str)
+                    .collect();
+                if !missing.is_empty() {
+                    *title = format!("{} ({})", title.trim_end(), missing.join(", "));
+                }
+            }
+            TicketPlacement::Footer => {
+                let referenced = self.footer_values(description);
+                let missing: Vec<&str> = self
+                    .ids
+                    .iter()
+                    .filter(|id| !referenced.contains(id))
+                    .map(String::as_str)
+                    .collect();
+                if missing.is_empty() {
+                    return;
+                }
+                let footer = format!("{}: {}", self.footer_token, missing.join(", "));
+                *description = conventional::append_footer(description, &footer);
+            }
+        }
+    }
+
+    /// Checks that `message` references the branch's tickets and that its
+    /// ticket footers hold only valid IDs. Returns the problems found.
+    pub fn check(&self, message: &str) -> Vec<String> {
+        if self.is_disabled() {
+            return Vec::new();
+        }
+        let (title, body) = review::split_message(message);
+        let mut errors = Vec::new();
+
+        let referenced = self.footer_values(&body);
+        for value in &referenced {
+            if !self.is_ticket_id(value) {
+                errors.push(format!(
+                    "'{}: {}' does not reference a valid ticket ID",
+                    self.footer_token, value
+                ));
+            }
+        }
+
+        for id in &self.ids {
+            let found = match self.placement {
+                TicketPlacement::Footer => referenced.contains(id),
+                TicketPlacement::Title => title.contains(id.as_str()),
+            };
+            if !found {
+                errors.push(match self.placement {
+                    TicketPlacement::Footer => {
+                        format!("Missing '{}: {}' footer", self.footer_token, id)
+                    }
+                    TicketPlacement::Title => format!("Title does not reference {}", id),
+                });
+            }
+        }
+        errors
+    }
+
+    /// The comma-separated values of the ticket footers in `body`.
+    fn footer_values(&self, body: &str) -> Vec<String> {
+        let (_, footers) = conventional::split_footers(body);
+        footers
+            .iter()
+            .filter(|footer| footer.token.eq_ignore_ascii_case(&self.footer_token))
+            .flat_map(|footer| footer.value.split([',', '\n']))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect()
+    }
+
+    /// Whether `value` is an ID the patterns would extract: the first capture
+    /// group, or the whole match, covers all of it.
+    fn is_ticket_id(&self, value: &str) -> bool {
+        let extracted = self.patterns.iter().any(|pattern| {
+            pattern.captures_iter(value).any(|captures| {
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .is_some_and(|id| id.as_str() == value)
+            })
+        });
+        extracted || self.id_patterns.iter().any(|id| id.is_match(value))
+    }
+}
+
+/// The source of the first capture group in `pattern`, e.g. `[A-Z]+-\d+` for
+/// `^feature/([A-Z]+-\d+)`. Returns `None` if the pattern has no group.
+fn first_group(pattern: &str) -> Option<&str> {
+    let bytes = pattern.as_bytes();
+    let mut start = None;
+    let mut depth = 0;
+    let mut in_class = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' if !in_class => in_class = true,
+            b']' if in_class => in_class = false,
+            b'(' if !in_class => {
+                if start.is_none() {
+                    let rest = &pattern[i + 1..];
+                    if !rest.starts_with('?') {
+                        start = Some(i + 1);
+                    } else if let Some(name) =
+                        rest.strip_prefix("?P<").or_else(|| rest.strip_prefix("?<"))
+                    {
+                        let name_end = name.find('>')?;
+                        i += rest.len() - name.len() + name_end + 1;
+                        start = Some(i + 1);
+                    }
+                }
+                if start.is_some() {
+                    depth += 1;
+                }
+            }
+            b')' if !in_class && start.is_some() => {
+                depth -= 1;
+                if depth == 0 {
+                    return start.map(|start| &pattern[start..i]);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn refs(placement: TicketPlacement, branch: &str) -> TicketRefs {
+        let settings = TicketSettings {
+            patterns: vec![r"([A-Z]+-\d+)".to_string()],
+            placement,
+            ..Default::default()
+        };
+        TicketRefs::from_branch(&settings, branch).unwrap()
+    }
+
+    #[test]
+    fn test_extract_ids_from_branch() {
+        assert_eq!(
+            refs(TicketPlacement::Footer, "feature/PAY-123-refund").ids,
+            vec!["PAY-123"]
+        );
+        assert_eq!(
+            refs(TicketPlacement::Footer, "fix/PAY-1-and-OPS-22-PAY-1").ids,
+            vec!["PAY-1", "OPS-22"]
+        );
+        assert!(refs(TicketPlacement::Footer, "main").ids.is_empty());
+
+        let settings = TicketSettings {
+            patterns: vec!["(".to_string()],
+            ..Default::default()
+        };
+        assert!(TicketRefs::from_branch(&settings, "main").is_err());
+        assert!(TicketRefs::from_branch(&TicketSettings::default(), "PAY-1")
+            .unwrap()
+            .is_disabled());
+    }
+
+    #[test]
+    fn test_apply_footer() {
+        let refs = refs(TicketPlacement::Footer, "feature/PAY-123-refund");
+        let mut title = "feat: add refunds".to_string();
+        let mut description = "Adds refunds.".to_string();
+        refs.apply(&mut title, &mut description);
+        assert_eq!(title, "feat: add refunds");
+        assert_eq!(description, "Adds refunds.\n\nRefs: PAY-123");
+
+        // Applying again changes nothing
+        refs.apply(&mut title, &mut description);
+        assert_eq!(description, "Adds refunds.\n\nRefs: PAY-123");
+
+        let mut description = "Adds refunds.\n\nCloses #4".to_string();
+        refs.apply(&mut title, &mut description);
+        assert_eq!(description, "Adds refunds.\n\nCloses #4\nRefs: PAY-123");
+
+        let mut description = String::new();
+        refs.apply(&mut title, &mut description);
+        assert_eq!(description, "Refs: PAY-123");
+    }
+
+    #[test]
+    fn test_apply_title() {
+        let refs = refs(TicketPlacement::Title, "feature/PAY-123-refund");
+        let mut title = "feat: add refunds".to_string();
+        let mut description = "Adds refunds.".to_string();
+        refs.apply(&mut title, &mut description);
+        assert_eq!(title, "feat: add refunds (PAY-123)");
+        assert_eq!(description, "Adds refunds.");
+        refs.apply(&mut title, &mut description);
+        assert_eq!(title, "feat: add refunds (PAY-123)");
+    }
+
+    #[test]
+    fn test_check() {
+        let refs_footer = refs(TicketPlacement::Footer, "feature/PAY-123-refund");
+        assert!(refs_footer
+            .check("feat: add refunds\n\nBody.\n\nRefs: PAY-123")
+            .is_empty());
+        assert_eq!(
+            refs_footer.check("feat: add refunds\n\nBody."),
+            vec!["Missing 'Refs: PAY-123' footer"]
+        );
+        assert_eq!(
+            refs_footer.check("feat: add refunds\n\nRefs: PAY-123, later"),
+            vec!["'Refs: later' does not reference a valid ticket ID"]
+        );
+
+        let refs_title = refs(TicketPlacement::Title, "feature/PAY-123-refund");
+        assert!(refs_title.check("feat: add refunds (PAY-123)").is_empty());
+        assert_eq!(
+            refs_title.check("feat: add refunds"),
+            vec!["Title does not reference PAY-123"]
+        );
+
+        // Footers are validated even on branches without a ticket
+        let refs_main = refs(TicketPlacement::Footer, "main");
+        assert!(refs_main.check("fix: typo").is_empty());
+        assert_eq!(refs_main.check("fix: typo\n\nRefs: soon").len(), 1);
+    }
+
+    #[test]
+    fn test_check_with_anchored_pattern() {
+        let settings = TicketSettings {
+            patterns: vec![r"^feature/([A-Z]+-\d+)".to_string()],
+            ..Default::default()
+        };
+        let refs = TicketRefs::from_branch(&settings, "feature/PAY-123-refund").unwrap();
+        assert_eq!(refs.ids, vec!["PAY-123"]);
+        assert!(refs
+            .check("feat: add refunds\n\nRefs: PAY-123, OPS-7")
+            .is_empty());
+        assert_eq!(
+            refs.check("feat: add refunds\n\nRefs: PAY-123, feature/PAY-123"),
+            vec!["'Refs: feature/PAY-123' does not reference a valid ticket ID"]
+        );
+    }
+
+    #[test]
+    fn test_first_group() {
+        assert_eq!(first_group(r"^feature/([A-Z]+-\d+)"), Some(r"[A-Z]+-\d+"));
+        assert_eq!(
+            first_group(r"(?i)(?:fix|feat)/(?P<id>[a-z]+-(\d+))"),
+            Some(r"[a-z]+-(\d+)")
+        );
+        assert_eq!(first_group(r"[(]\(x\)(#\d+)"), Some(r"#\d+"));
+        assert_eq!(first_group(r"[A-Z]+-\d+"), None);
+    }
+}
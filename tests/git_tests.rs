@@ -0,0 +1,1144 @@
+//! Tests for the git module against throwaway repositories.
+//!
+//! The git functions act on the current directory, so every test runs inside
+//! [`in_temp_repo`], which holds a global lock while the directory is changed.
+
+use commitcraft::backend::{GitBackend, Libgit2, Subprocess, BACKEND_VAR};
+use commitcraft::config;
+use commitcraft::git::{self, ChangeScope, CommitOptions, CommitSettings};
+use commitcraft::pair::{self, Pairing};
+use commitcraft::providers::{PlannedCommit, ScopeConstraint, SplitPlan};
+use commitcraft::scopes::{ScopeRules, ScopeSettings};
+use commitcraft::split::{self, StagedHunks};
+use commitcraft::{operation, pr};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+static CWD_LOCK: Mutex<()> = Mutex::new(());
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn run_git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("git should be installed for tests");
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn write(dir: &Path, path: &str, content: &str) {
+    let path = dir.join(path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(path, content).unwrap();
+}
+
+/// Creates an empty repository, runs `test` with it as the current directory
+/// and removes it afterwards.
+fn in_temp_repo(test: impl FnOnce(&Path)) {
+    let _guard = CWD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let dir: PathBuf = std::env::temp_dir().join(format!(
+        "commitcraft-git-test-{}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    run_git(&dir, &["init", "-q", "-b", "main"]);
+    run_git(&dir, &["config", "user.name", "Test"]);
+    run_git(&dir, &["config", "user.email", "test@example.com"]);
+    run_git(&dir, &["config", "commit.gpgsign", "false"]);
+
+    let previous = std::env::current_dir().unwrap();
+    std::env::set_current_dir(&dir).unwrap();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| test(&dir)));
+    std::env::set_current_dir(previous).unwrap();
+    let _ = fs::remove_dir_all(&dir);
+    if let Err(panic) = result {
+        std::panic::resume_unwind(panic);
+    }
+}
+
+fn commit_all(dir: &Path, message: &str) {
+    run_git(dir, &["add", "-A"]);
+    run_git(dir, &["commit", "-q", "-m", message]);
+}
+
+/// Installs a pre-commit hook running the shell `script`. Only Unix needs
+/// the hook to be executable.
+fn write_pre_commit_hook(dir: &Path, script: &str) {
+    let hook = dir.join(".git/hooks/pre-commit");
+    fs::write(&hook, format!("#!/bin/sh\n{}", script)).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+}
+
+#[test]
+fn test_no_staged_changes_suggests_all_and_paths() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        commit_all(dir, "init");
+        write(dir, "a.txt", "two\n");
+
+        let error = git::get_staged_diff().unwrap_err();
+        assert!(error.contains("git add"));
+        assert!(error.contains("--all"));
+        assert!(error.contains("commitcraft -- <path>"));
+    });
+}
+
+#[test]
+fn test_all_scope_includes_unstaged_tracked_changes() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        write(dir, "b.txt", "one\n");
+        commit_all(dir, "init");
+        write(dir, "a.txt", "two\n");
+        write(dir, "b.txt", "two\n");
+        run_git(dir, &["add", "b.txt"]);
+        write(dir, "untracked.txt", "new\n");
+
+        let diff = git::get_diff(&ChangeScope::All).unwrap();
+        assert!(diff.contains("a/a.txt"));
+        assert!(diff.contains("a/b.txt"));
+        assert!(!diff.contains("untracked.txt"));
+        assert_eq!(
+            git::get_changed_files(&ChangeScope::All).unwrap(),
+            vec!["a.txt", "b.txt"]
+        );
+
+        let options = CommitOptions {
+            scope: ChangeScope::All,
+            ..Default::default()
+        };
+        git::commit_with("chore: update both", &options).unwrap();
+        let status = run_git(dir, &["status", "--porcelain"]);
+        assert_eq!(status.trim(), "?? untracked.txt");
+    });
+}
+
+#[test]
+fn test_paths_scope_limits_diff_and_commit() {
+    in_temp_repo(|dir| {
+        write(dir, "src/api/handler.rs", "fn a() {}\n");
+        write(dir, "src/db.rs", "fn b() {}\n");
+        commit_all(dir, "init");
+        write(dir, "src/api/handler.rs", "fn a() { todo!() }\n");
+        write(dir, "src/db.rs", "fn b() { todo!() }\n");
+        run_git(dir, &["add", "src/db.rs"]);
+
+        let scope = ChangeScope::Paths(vec!["src/api".to_string()]);
+        let diff = git::get_diff(&scope).unwrap();
+        assert!(diff.contains("src/api/handler.rs"));
+        assert!(!diff.contains("src/db.rs"));
+        assert_eq!(
+            git::get_changed_files(&scope).unwrap(),
+            vec!["src/api/handler.rs"]
+        );
+
+        let options = CommitOptions {
+            scope,
+            ..Default::default()
+        };
+        git::commit_with("fix(api): stub handler", &options).unwrap();
+        let committed = run_git(dir, &["show", "--name-only", "--format=", "HEAD"]);
+        assert_eq!(committed.trim(), "src/api/handler.rs");
+        // The staged change outside the paths stays staged
+        let staged = run_git(dir, &["diff", "--staged", "--name-only"]);
+        assert_eq!(staged.trim(), "src/db.rs");
+    });
+}
+
+#[test]
+fn test_paths_scope_without_changes() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        commit_all(dir, "init");
+
+        let error = git::get_diff(&ChangeScope::Paths(vec!["a.txt".to_string()])).unwrap_err();
+        assert_eq!(error, "There are no changes to commit in a.txt.");
+        assert!(git::get_diff(&ChangeScope::All).is_err());
+    });
+}
+
+#[test]
+fn test_all_scope_before_first_commit() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        run_git(dir, &["add", "a.txt"]);
+        write(dir, "a.txt", "two\n");
+
+        let diff = git::get_diff(&ChangeScope::All).unwrap();
+        assert!(diff.contains("+two"));
+    });
+}
+
+const LINES: &str = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15\n16\n17\n18\n19\n20\n";
+
+fn plan(commits: &[(&str, &[usize])]) -> SplitPlan {
+    SplitPlan {
+        commits: commits
+            .iter()
+            .map(|(title, hunks)| PlannedCommit {
+                title: title.to_string(),
+                description: String::new(),
+                hunks: hunks.to_vec(),
+            })
+            .collect(),
+    }
+}
+
+/// Stages two distant edits to one file, a new file and a renamed file.
+fn stage_mixed_changes(dir: &Path) -> StagedHunks {
+    write(dir, "numbers.txt", LINES);
+    write(dir, "old_name.txt", "keep me\n");
+    commit_all(dir, "init");
+
+    write(
+        dir,
+        "numbers.txt",
+        &LINES
+            .replace("\n2\n", "\ntwo\n")
+            .replace("19\n", "nineteen\n"),
+    );
+    write(dir, "added.txt", "new\n");
+    run_git(dir, &["mv", "old_name.txt", "new_name.txt"]);
+    run_git(dir, &["add", "-A"]);
+
+    let staged = StagedHunks::parse(&git::get_staged_patch().unwrap());
+    let summary: Vec<(&str, Option<&str>)> = staged
+        .hunks()
+        .iter()
+        .map(|hunk| (hunk.path.as_str(), hunk.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("added.txt", Some("new file")),
+            ("new_name.txt", Some("renamed")),
+            ("numbers.txt", None),
+            ("numbers.txt", None),
+        ]
+    );
+    staged
+}
+
+#[test]
+fn test_split_creates_commits_from_hunks() {
+    in_temp_repo(|dir| {
+        let staged = stage_mixed_changes(dir);
+        let plan = plan(&[
+            ("fix: spell out nineteen", &[4]),
+            ("feat: add file and rename", &[1, 2, 3]),
+        ]);
+        assert_eq!(
+            split::create_commits(
+                &staged,
+                &plan,
+                &CommitOptions::default(),
+                &AtomicBool::new(false)
+            )
+            .unwrap(),
+            2
+        );
+
+        let log = run_git(dir, &["log", "--format=%s", "-3"]);
+        assert_eq!(
+            log,
+            "feat: add file and rename\nfix: spell out nineteen\ninit\n"
+        );
+        let first = run_git(dir, &["show", "HEAD~1:numbers.txt"]);
+        assert!(first.contains("nineteen") && !first.contains("two"));
+        let renamed = run_git(dir, &["show", "--name-status", "--format=", "-M", "HEAD"]);
+        assert!(renamed.contains("R100\told_name.txt\tnew_name.txt"));
+        assert!(renamed.contains("A\tadded.txt"));
+
+        // Everything was committed and the working tree is untouched
+        assert_eq!(run_git(dir, &["status", "--porcelain"]), "");
+    });
+}
+
+#[test]
+fn test_split_leaves_unplanned_hunks_staged() {
+    in_temp_repo(|dir| {
+        let staged = stage_mixed_changes(dir);
+        let plan = plan(&[("feat: add file", &[1])]);
+        assert_eq!(
+            split::check_plan(&plan, staged.len()).unwrap(),
+            vec![2, 3, 4]
+        );
+        split::create_commits(
+            &staged,
+            &plan,
+            &CommitOptions::default(),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let status = run_git(dir, &["status", "--porcelain"]);
+        assert_eq!(status, "R  old_name.txt -> new_name.txt\nM  numbers.txt\n");
+    });
+}
+
+#[test]
+fn test_split_restores_index_on_failure() {
+    in_temp_repo(|dir| {
+        let staged = stage_mixed_changes(dir);
+        let before = run_git(dir, &["diff", "--staged"]);
+        write_pre_commit_hook(dir, "exit 1\n");
+
+        let plan = plan(&[("feat: a", &[1, 2]), ("fix: b", &[3, 4])]);
+        let error = split::create_commits(
+            &staged,
+            &plan,
+            &CommitOptions::default(),
+            &AtomicBool::new(false),
+        )
+        .unwrap_err();
+        assert!(error.contains("Created 0 of 2 commits"));
+        assert_eq!(run_git(dir, &["log", "--format=%s"]), "init\n");
+        assert_eq!(run_git(dir, &["diff", "--staged"]), before);
+    });
+}
+
+#[test]
+fn test_split_restores_index_when_a_later_commit_fails() {
+    in_temp_repo(|dir| {
+        let staged = stage_mixed_changes(dir);
+        let before = run_git(dir, &["diff", "--staged", "HEAD"]);
+        // Lets the first commit through and rejects the second
+        write_pre_commit_hook(
+            dir,
+            "[ -f .git/first-done ] && exit 1\ntouch .git/first-done\n",
+        );
+
+        let plan = plan(&[("feat: a", &[1, 2]), ("fix: b", &[3, 4])]);
+        let error = split::create_commits(
+            &staged,
+            &plan,
+            &CommitOptions::default(),
+            &AtomicBool::new(false),
+        )
+        .unwrap_err();
+        assert!(error.contains("Created 1 of 2 commits"));
+        assert!(error.contains("the remaining changes are staged again"));
+        assert_eq!(run_git(dir, &["log", "--format=%s"]), "feat: a\ninit\n");
+        // The index holds everything that was staged, as before the split
+        assert_eq!(run_git(dir, &["diff", "--staged", "HEAD~1"]), before);
+    });
+}
+
+#[test]
+fn test_split_stops_when_interrupted() {
+    in_temp_repo(|dir| {
+        let staged = stage_mixed_changes(dir);
+        let before = run_git(dir, &["diff", "--staged"]);
+
+        let plan = plan(&[("feat: a", &[1, 2]), ("fix: b", &[3, 4])]);
+        let error = split::create_commits(
+            &staged,
+            &plan,
+            &CommitOptions::default(),
+            &AtomicBool::new(true),
+        )
+        .unwrap_err();
+        assert!(error.starts_with("Interrupted."));
+        assert!(error.contains("Created 0 of 2 commits"));
+        assert_eq!(run_git(dir, &["log", "--format=%s"]), "init\n");
+        assert_eq!(run_git(dir, &["diff", "--staged"]), before);
+    });
+}
+
+#[test]
+fn test_split_keeps_content_that_is_not_utf8() {
+    in_temp_repo(|dir| {
+        let latin1 = |text: &str| {
+            text.replace('e', "\u{e9}")
+                .chars()
+                .map(|c| c as u8)
+                .collect::<Vec<u8>>()
+        };
+        fs::write(dir.join("menu.txt"), latin1(LINES)).unwrap();
+        commit_all(dir, "init");
+        let edited = latin1(&LINES.replace("\n2\n", "\ncafe\n").replace("19\n", "the\n"));
+        fs::write(dir.join("menu.txt"), &edited).unwrap();
+        run_git(dir, &["add", "-A"]);
+
+        let staged = StagedHunks::parse(&git::get_staged_patch().unwrap());
+        assert_eq!(staged.len(), 2);
+        let plan = plan(&[("feat: a", &[1]), ("feat: b", &[2])]);
+        split::create_commits(
+            &staged,
+            &plan,
+            &CommitOptions::default(),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let committed = Command::new("git")
+            .args(["cat-file", "blob", "HEAD:menu.txt"])
+            .current_dir(dir)
+            .output()
+            .unwrap()
+            .stdout;
+        assert_eq!(committed, edited);
+        assert_eq!(run_git(dir, &["status", "--porcelain"]), "");
+    });
+}
+
+#[test]
+fn test_split_in_repository_without_commits() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "a\n");
+        write(dir, "b.txt", "b\n");
+        run_git(dir, &["add", "-A"]);
+
+        let staged = StagedHunks::parse(&git::get_staged_patch().unwrap());
+        let plan = plan(&[("feat: add a", &[1]), ("feat: add b", &[2])]);
+        split::create_commits(
+            &staged,
+            &plan,
+            &CommitOptions::default(),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        assert_eq!(
+            run_git(dir, &["log", "--format=%s"]),
+            "feat: add b\nfeat: add a\n"
+        );
+        assert_eq!(run_git(dir, &["status", "--porcelain"]), "");
+    });
+}
+
+#[test]
+fn test_recent_subjects() {
+    in_temp_repo(|dir| {
+        assert!(git::get_recent_subjects(5, &[]).unwrap().is_empty());
+
+        write(dir, "src/api.rs", "a\n");
+        commit_all(dir, "feat(api): add endpoint");
+        write(dir, "docs/guide.md", "b\n");
+        commit_all(dir, "docs: write guide");
+        write(dir, "src/api.rs", "c\n");
+        commit_all(dir, "fix(api): handle timeouts");
+
+        assert_eq!(
+            git::get_recent_subjects(2, &[]).unwrap(),
+            vec!["fix(api): handle timeouts", "docs: write guide"]
+        );
+        assert_eq!(
+            git::get_recent_subjects(5, &["src/api.rs".to_string()]).unwrap(),
+            vec!["fix(api): handle timeouts", "feat(api): add endpoint"]
+        );
+        assert!(git::get_recent_subjects(0, &[]).unwrap().is_empty());
+    });
+}
+
+#[test]
+fn test_current_branch() {
+    in_temp_repo(|dir| {
+        // An unborn branch still has a name
+        assert_eq!(git::current_branch().as_deref(), Some("main"));
+        write(dir, "a.txt", "a\n");
+        commit_all(dir, "init");
+        run_git(dir, &["checkout", "-q", "-b", "feature/PAY-123-refund"]);
+        assert_eq!(
+            git::current_branch().as_deref(),
+            Some("feature/PAY-123-refund")
+        );
+        run_git(dir, &["checkout", "-q", "--detach"]);
+        assert_eq!(git::current_branch(), None);
+    });
+}
+
+#[test]
+fn test_scope_from_cargo_workspace() {
+    in_temp_repo(|dir| {
+        write(dir, "Cargo.toml", "[workspace]\nmembers = [\"crates/*\"]\n");
+        write(dir, "crates/api/src/lib.rs", "a\n");
+        write(dir, "crates/db/src/lib.rs", "b\n");
+        commit_all(dir, "init");
+        write(dir, "crates/api/src/lib.rs", "c\n");
+        run_git(dir, &["add", "-A"]);
+
+        let root = git::repo_root().unwrap();
+        let rules = ScopeRules::load(&ScopeSettings::default(), &root).unwrap();
+        let files = git::get_staged_files().unwrap();
+        assert_eq!(
+            rules.infer(&files),
+            Some(ScopeConstraint::Use("api".to_string()))
+        );
+
+        write(dir, "crates/db/src/lib.rs", "d\n");
+        run_git(dir, &["add", "-A"]);
+        let files = git::get_staged_files().unwrap();
+        assert_eq!(rules.infer(&files), Some(ScopeConstraint::Omit));
+    });
+}
+
+#[test]
+fn test_log_and_tags() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "a\n");
+        commit_all(dir, "feat: first");
+        run_git(dir, &["tag", "v1.0.0"]);
+        write(dir, "a.txt", "b\n");
+        commit_all(dir, "fix(api): handle timeouts\n\nLonger body.");
+        write(dir, "a.txt", "c\n");
+        commit_all(dir, "docs: guide");
+
+        assert!(git::is_tag("v1.0.0"));
+        assert!(!git::is_tag("main"));
+        assert_eq!(git::latest_tag("HEAD").as_deref(), Some("v1.0.0"));
+        assert_eq!(git::latest_tag("v1.0.0^"), None);
+
+        let log = git::get_log(Some("v1.0.0"), "HEAD").unwrap();
+        let messages: Vec<&str> = log.iter().map(|commit| commit.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["docs: guide", "fix(api): handle timeouts\n\nLonger body."]
+        );
+        assert!(!log[0].hash.is_empty());
+        assert_eq!(git::get_log(None, "HEAD").unwrap().len(), 3);
+        assert!(git::get_log(Some("v9"), "HEAD").is_err());
+        assert_eq!(git::commit_date("HEAD").unwrap().len(), 10);
+    });
+}
+
+#[test]
+fn test_merged_tags_and_create_tag() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "a\n");
+        commit_all(dir, "feat: first");
+        git::create_tag("v0.1.0", "v0.1.0").unwrap();
+        run_git(dir, &["checkout", "-q", "-b", "other"]);
+        write(dir, "a.txt", "b\n");
+        commit_all(dir, "fix: other");
+        run_git(dir, &["tag", "v0.1.1"]);
+        run_git(dir, &["checkout", "-q", "main"]);
+
+        // Tags on other branches are not part of this history
+        assert_eq!(git::get_merged_tags("HEAD").unwrap(), vec!["v0.1.0"]);
+        let kind = run_git(dir, &["cat-file", "-t", "v0.1.0"]);
+        assert_eq!(kind.trim(), "tag");
+        assert!(git::create_tag("v0.1.0", "again").is_err());
+    });
+}
+
+#[test]
+fn test_branch_diff_and_template() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "a\n");
+        commit_all(dir, "init");
+        run_git(dir, &["checkout", "-q", "-b", "feature"]);
+        write(dir, "b.txt", "feature\n");
+        commit_all(dir, "feat: add b");
+        run_git(dir, &["checkout", "-q", "main"]);
+        write(dir, "a.txt", "main moved on\n");
+        commit_all(dir, "chore: main only");
+        run_git(dir, &["checkout", "-q", "feature"]);
+
+        // Only the branch's own changes, not what happened on main since
+        let diff = git::get_branch_diff("main", "HEAD").unwrap();
+        assert!(diff.contains("+feature"));
+        assert!(!diff.contains("main moved on"));
+        let commits = git::get_log(Some("main"), "HEAD").unwrap();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(git::remote_default_branch(), None);
+
+        assert_eq!(pr::find_template(dir), None);
+        write(
+            dir,
+            ".github/PULL_REQUEST_TEMPLATE.md",
+            "## What\n\n## Why\n",
+        );
+        let template = pr::find_template(dir).unwrap();
+        assert_eq!(pr::template_headings(&template), vec!["## What", "## Why"]);
+    });
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_paths_and_messages() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    in_temp_repo(|dir| {
+        run_git(dir, &["config", "core.quotepath", "false"]);
+        write(dir, "a.txt", "a\n");
+        commit_all(dir, "init");
+        run_git(dir, &["checkout", "-q", "-b", "feature"]);
+        // "café" in Latin-1, as a file name, in the file and in the message
+        fs::write(dir.join(OsStr::from_bytes(b"caf\xe9.txt")), b"caf\xe9\n").unwrap();
+        fs::write(dir.join(".git/MESSAGE"), b"feat: add caf\xe9\n").unwrap();
+        run_git(dir, &["add", "-A"]);
+
+        // The patch split replays keeps the original bytes
+        let patch = git::get_staged_patch().unwrap();
+        assert!(patch.windows(10).any(|line| line == b"+++ b/caf\xe9"));
+        assert!(patch.windows(6).any(|line| line == b"+caf\xe9\n"));
+        for backend in [&Libgit2 as &dyn GitBackend, &Subprocess] {
+            let diff = backend.diff(&ChangeScope::Staged).unwrap();
+            assert!(diff.contains("+caf\u{FFFD}"), "{}", diff);
+            assert_eq!(
+                backend.changed_files(&ChangeScope::Staged).unwrap(),
+                vec!["caf\u{FFFD}.txt"]
+            );
+        }
+
+        run_git(dir, &["commit", "-q", "-F", ".git/MESSAGE"]);
+        assert!(git::get_branch_diff("main", "HEAD")
+            .unwrap()
+            .contains("+caf\u{FFFD}"));
+        for backend in [&Libgit2 as &dyn GitBackend, &Subprocess] {
+            let commits = backend.log(Some("main"), "HEAD").unwrap();
+            assert_eq!(commits.len(), 1);
+            assert!(commits[0].message.starts_with("feat: add caf"));
+        }
+    });
+}
+
+#[test]
+fn test_git_path() {
+    in_temp_repo(|_| {
+        let path = git::git_path("SQUASH_MSG").unwrap();
+        assert!(path.ends_with(".git/SQUASH_MSG"), "{}", path.display());
+    });
+}
+
+/// Runs a git command expected to stop with conflicts.
+fn run_git_conflicting(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .expect("git should be installed for tests")
+        .status;
+    assert!(!status.success(), "git {:?} should have conflicted", args);
+}
+
+/// A repository where `main` and `side` both changed `a.txt`.
+fn diverged_branches(dir: &Path) {
+    write(dir, "a.txt", "one\n");
+    commit_all(dir, "feat: add a");
+    run_git(dir, &["checkout", "-q", "-b", "side"]);
+    write(dir, "a.txt", "side\n");
+    commit_all(dir, "fix: change a on side");
+    run_git(dir, &["checkout", "-q", "main"]);
+    write(dir, "a.txt", "main\n");
+    commit_all(dir, "fix: change a on main");
+}
+
+#[test]
+fn test_operation_in_progress_merge() {
+    in_temp_repo(|dir| {
+        assert_eq!(git::operation_in_progress(), None);
+        diverged_branches(dir);
+        run_git_conflicting(dir, &["merge", "side"]);
+
+        let side = run_git(dir, &["rev-parse", "side"]).trim().to_string();
+        assert_eq!(
+            git::operation_in_progress(),
+            Some(git::Operation::Merge { heads: vec![side] })
+        );
+        let prepared = git::prepared_message().unwrap();
+        assert!(prepared.starts_with("Merge branch 'side'"));
+        assert_eq!(operation::conflicts(&prepared), vec!["a.txt"]);
+    });
+}
+
+#[test]
+fn test_operation_in_progress_revert_and_cherry_pick() {
+    in_temp_repo(|dir| {
+        diverged_branches(dir);
+        let head = run_git(dir, &["rev-parse", "HEAD"]).trim().to_string();
+        run_git(dir, &["revert", "--no-commit", "HEAD"]);
+        assert_eq!(
+            git::operation_in_progress(),
+            Some(git::Operation::Revert {
+                commit: head.clone()
+            })
+        );
+        assert_eq!(git::commit_message(&head).unwrap(), "fix: change a on main");
+        run_git(dir, &["revert", "--abort"]);
+
+        let side = run_git(dir, &["rev-parse", "side"]).trim().to_string();
+        run_git_conflicting(dir, &["cherry-pick", "side"]);
+        assert_eq!(
+            git::operation_in_progress(),
+            Some(git::Operation::CherryPick { commit: side })
+        );
+        assert!(git::prepared_message()
+            .unwrap()
+            .starts_with("fix: change a on side"));
+    });
+}
+
+#[test]
+fn test_operation_in_progress_rebase() {
+    in_temp_repo(|dir| {
+        diverged_branches(dir);
+        run_git_conflicting(dir, &["rebase", "side"]);
+        assert_eq!(git::operation_in_progress(), Some(git::Operation::Rebase));
+    });
+}
+
+/// Staged, unstaged, renamed, added and deleted changes across two directories.
+fn mixed_changes(dir: &Path) {
+    write(dir, "src/lib.rs", "fn a() {}\n");
+    write(
+        dir,
+        "src/old.rs",
+        "fn old() {}\nfn kept() {}\nfn more() {}\n",
+    );
+    write(dir, "docs/guide.md", "# Guide\n");
+    write(dir, "gone.txt", "bye\n");
+    commit_all(dir, "feat: initial");
+    write(dir, "docs/guide.md", "# Guide\n\nMore.\n");
+    commit_all(dir, "docs: extend guide");
+
+    write(dir, "src/lib.rs", "fn a() {}\nfn b() {}\n");
+    run_git(dir, &["mv", "src/old.rs", "src/new.rs"]);
+    write(dir, "src/added.rs", "fn added() {}");
+    run_git(dir, &["rm", "-q", "gone.txt"]);
+    run_git(dir, &["add", "-A"]);
+    write(dir, "docs/guide.md", "# Guide\n\nMore, unstaged.\n");
+}
+
+#[test]
+fn test_backends_agree() {
+    in_temp_repo(|dir| {
+        mixed_changes(dir);
+        let backends: [&dyn GitBackend; 2] = [&Libgit2, &Subprocess];
+        let scopes = [
+            ChangeScope::Staged,
+            ChangeScope::All,
+            ChangeScope::Paths(vec!["docs".to_string()]),
+        ];
+        for scope in &scopes {
+            let [library, command] = backends.map(|backend| backend.diff(scope).unwrap());
+            assert_eq!(library, command, "diff of {:?}", scope);
+            let [library, command] = backends.map(|backend| backend.changed_files(scope).unwrap());
+            assert_eq!(library, command, "files of {:?}", scope);
+        }
+        assert_eq!(
+            Libgit2.changed_files(&ChangeScope::Staged).unwrap(),
+            vec!["gone.txt", "src/added.rs", "src/lib.rs", "src/new.rs"]
+        );
+
+        let docs = ["docs/guide.md".to_string()];
+        for (count, paths) in [(10, &docs[..]), (1, &[][..]), (0, &[][..])] {
+            let [library, command] =
+                backends.map(|backend| backend.recent_subjects(count, paths).unwrap());
+            assert_eq!(library, command);
+        }
+        let [library, command] = backends.map(|backend| backend.log(None, "HEAD").unwrap());
+        assert_eq!(library, command);
+        let [library, command] =
+            backends.map(|backend| backend.log(Some("HEAD~1"), "HEAD").unwrap());
+        assert_eq!(library, command);
+        assert_eq!(library.len(), 1);
+
+        for backend in backends {
+            assert!(backend.is_repository().unwrap());
+            assert_eq!(backend.current_branch().unwrap().as_deref(), Some("main"));
+            assert_eq!(
+                backend.repo_root().unwrap().canonicalize().unwrap(),
+                dir.canonicalize().unwrap()
+            );
+        }
+        run_git(dir, &["checkout", "-q", "--detach"]);
+        assert_eq!(Libgit2.current_branch().unwrap(), None);
+    });
+}
+
+#[test]
+fn test_libgit2_paths_from_subdirectory() {
+    in_temp_repo(|dir| {
+        mixed_changes(dir);
+        std::env::set_current_dir(dir.join("docs")).unwrap();
+        let scope = ChangeScope::Paths(vec!["guide.md".to_string()]);
+        assert_eq!(
+            Libgit2.changed_files(&scope).unwrap(),
+            Subprocess.changed_files(&scope).unwrap()
+        );
+        let scope = ChangeScope::Paths(vec!["../src".to_string()]);
+        assert_eq!(
+            Libgit2.changed_files(&scope).unwrap(),
+            vec!["src/added.rs", "src/lib.rs", "src/new.rs"]
+        );
+    });
+}
+
+#[test]
+fn test_libgit2_reads_non_utf8_changes() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        commit_all(dir, "feat: add a");
+        fs::write(dir.join("latin1.txt"), b"caf\xe9\n").unwrap();
+        run_git(dir, &["add", "-A"]);
+
+        assert!(Subprocess
+            .diff(&ChangeScope::Staged)
+            .unwrap()
+            .contains("+caf\u{fffd}"));
+        let diff = Libgit2.diff(&ChangeScope::Staged).unwrap();
+        assert!(diff.contains("+caf\u{fffd}"), "{}", diff);
+        assert!(git::get_staged_diff().unwrap().contains("latin1.txt"));
+    });
+}
+
+#[test]
+fn test_libgit2_before_first_commit() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        run_git(dir, &["add", "-A"]);
+        assert_eq!(
+            Libgit2.diff(&ChangeScope::Staged).unwrap(),
+            Subprocess.diff(&ChangeScope::Staged).unwrap()
+        );
+        assert!(Libgit2.recent_subjects(5, &[]).unwrap().is_empty());
+        assert_eq!(Libgit2.current_branch().unwrap().as_deref(), Some("main"));
+    });
+}
+
+#[test]
+fn test_commit_options_are_passed_to_git() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        commit_all(dir, "feat: add a");
+        write(
+            dir,
+            ".commitcraft.toml",
+            "[commit]\nsignoff = true\ntrailers = [\"Reviewed-by: Bo <bo@example.com>\"]\n",
+        );
+        // A hook that rejects every commit
+        write_pre_commit_hook(dir, "exit 1\n");
+
+        let repo = config::load_repo_config(dir).unwrap();
+        let cli = CommitSettings {
+            no_verify: Some(true),
+            author: Some("Ann Lee <ann@example.com>".to_string()),
+            date: Some("2025-02-01T10:00:00Z".to_string()),
+            ..Default::default()
+        };
+        let options = repo
+            .commit
+            .merge(&cli)
+            .options(false, ChangeScope::Paths(vec!["a.txt".to_string()]));
+        write(dir, "a.txt", "two\n");
+        git::commit_with("fix: change a", &options).unwrap();
+
+        let logged = run_git(dir, &["log", "-1", "--format=%an <%ae>|%aI|%B"]);
+        assert_eq!(
+            logged.trim(),
+            "Ann Lee <ann@example.com>|2025-02-01T10:00:00+00:00|fix: change a\n\nSigned-off-by: Test <test@example.com>\nReviewed-by: Bo <bo@example.com>"
+        );
+
+        // Taken out of the flags, the trailers are part of the message shown
+        // for review, which is committed as is
+        write(dir, "a.txt", "three\n");
+        let mut options = repo
+            .commit
+            .merge(&cli)
+            .options(false, ChangeScope::Paths(vec!["a.txt".to_string()]));
+        let trailers = options.take_trailers(git::committer().as_deref());
+        let mut description = "Reviewed-by: Bo <bo@example.com>".to_string();
+        trailers.apply(&mut description);
+        let message = format!("fix: change a again\n\n{}", description);
+        assert_eq!(
+            message,
+            "fix: change a again\n\nReviewed-by: Bo <bo@example.com>\nSigned-off-by: Test <test@example.com>"
+        );
+        git::commit_with(&message, &options).unwrap();
+        assert_eq!(run_git(dir, &["log", "-1", "--format=%B"]).trim(), message);
+
+        // Without --no-verify the hook still runs
+        write(dir, "a.txt", "four\n");
+        let options = repo
+            .commit
+            .options(false, ChangeScope::Paths(vec!["a.txt".to_string()]));
+        assert!(git::commit_with("fix: again", &options).is_err());
+    });
+}
+
+#[test]
+fn test_repo_config_missing_or_invalid() {
+    in_temp_repo(|dir| {
+        assert_eq!(
+            config::load_repo_config(dir).unwrap(),
+            config::RepoConfig::default()
+        );
+        write(dir, ".commitcraft.toml", "[commit]\nsignoff = \"yes\"\n");
+        assert!(config::load_repo_config(dir)
+            .unwrap_err()
+            .contains(".commitcraft.toml"));
+    });
+}
+
+#[test]
+fn test_pairing_session_is_shared_by_worktrees() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        commit_all(dir, "init");
+        let path = git::common_git_path(pair::SESSION_FILE).unwrap();
+        assert!(pair::load_session(&path).unwrap().is_empty());
+
+        let coauthors = pair::resolve(
+            &[
+                "Alice <alice@example.com>".to_string(),
+                "Test <TEST@example.com>".to_string(),
+            ],
+            &Default::default(),
+        )
+        .unwrap();
+        pair::save_session(&path, &coauthors).unwrap();
+
+        run_git(dir, &["worktree", "add", "-q", "linked"]);
+        std::env::set_current_dir(dir.join("linked")).unwrap();
+        let linked = git::common_git_path(pair::SESSION_FILE).unwrap();
+        let session = pair::load_session(&linked).unwrap();
+        assert_eq!(session, coauthors);
+
+        // The committer isn't credited as their own co-author
+        let pairing = Pairing::new(session, Vec::new(), git::user_email().as_deref());
+        let mut description = String::new();
+        pairing.apply(&mut description);
+        assert_eq!(description, "Co-authored-by: Alice <alice@example.com>");
+        std::env::set_current_dir(dir).unwrap();
+    });
+}
+
+#[test]
+fn test_submodule_bump_lists_its_commits() {
+    in_temp_repo(|dir| {
+        let upstream = dir.join("upstream");
+        fs::create_dir_all(&upstream).unwrap();
+        run_git(&upstream, &["init", "-q", "-b", "main"]);
+        run_git(&upstream, &["config", "user.name", "Test"]);
+        run_git(&upstream, &["config", "user.email", "test@example.com"]);
+        write(&upstream, "f.txt", "one\n");
+        commit_all(&upstream, "feat: first");
+        write(dir, ".gitignore", "upstream/\n");
+        run_git(
+            dir,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                upstream.to_str().unwrap(),
+                "vendor/lib",
+            ],
+        );
+        commit_all(dir, "add lib");
+
+        write(&upstream, "f.txt", "one\ntwo\n");
+        commit_all(&upstream, "feat: add parser");
+        write(&upstream, "f.txt", "one\ntwo\nthree\n");
+        commit_all(&upstream, "fix: crash on empty input");
+        let checkout = dir.join("vendor/lib");
+        run_git(
+            &checkout,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "pull",
+                "-q",
+                "origin",
+                "main",
+            ],
+        );
+        run_git(dir, &["add", "vendor/lib"]);
+
+        let [library, command] = [&Libgit2 as &dyn GitBackend, &Subprocess]
+            .map(|b| b.diff(&ChangeScope::Staged).unwrap());
+        assert_eq!(library, command);
+        let diff = git::get_staged_diff().unwrap();
+        assert!(diff.starts_with("diff --git a/vendor/lib b/vendor/lib"));
+        assert!(
+            diff.ends_with(":\n  > fix: crash on empty input\n  > feat: add parser"),
+            "{}",
+            diff
+        );
+        commit_all(dir, "bump lib");
+
+        // Going back lists the commits left out
+        run_git(&checkout, &["checkout", "-q", "HEAD~2"]);
+        run_git(dir, &["add", "vendor/lib"]);
+        let diff = git::get_staged_diff().unwrap();
+        assert!(
+            diff.ends_with("(rewind):\n  < fix: crash on empty input\n  < feat: add parser"),
+            "{}",
+            diff
+        );
+    });
+}
+
+/// Removes environment variables when dropped, even if the test panics.
+struct EnvGuard(&'static [&'static str]);
+
+impl Drop for EnvGuard {
+    fn drop(&mut self) {
+        for name in self.0 {
+            std::env::remove_var(name);
+        }
+    }
+}
+
+#[test]
+fn test_separated_git_dir_through_environment() {
+    in_temp_repo(|dir| {
+        let tree = dir.join("tree");
+        let store = dir.join("store.git");
+        run_git(
+            dir,
+            &[
+                "init",
+                "-q",
+                "-b",
+                "main",
+                "--separate-git-dir",
+                store.to_str().unwrap(),
+                tree.to_str().unwrap(),
+            ],
+        );
+        for (key, value) in [
+            ("user.name", "Test"),
+            ("user.email", "test@example.com"),
+            ("commit.gpgsign", "false"),
+        ] {
+            run_git(&tree, &["config", key, value]);
+        }
+        let upstream = dir.join("upstream");
+        fs::create_dir_all(&upstream).unwrap();
+        run_git(&upstream, &["init", "-q", "-b", "main"]);
+        run_git(&upstream, &["config", "user.name", "Test"]);
+        run_git(&upstream, &["config", "user.email", "test@example.com"]);
+        write(&upstream, "f.txt", "one\n");
+        commit_all(&upstream, "feat: first");
+        run_git(
+            &tree,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                upstream.to_str().unwrap(),
+                "vendor/lib",
+            ],
+        );
+        write(&tree, "a.txt", "one\n");
+        commit_all(&tree, "init");
+        write(&upstream, "f.txt", "one\ntwo\n");
+        commit_all(&upstream, "feat: add parser");
+        run_git(
+            &tree.join("vendor/lib"),
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "pull",
+                "-q",
+                "origin",
+                "main",
+            ],
+        );
+
+        // Only the environment says where the repository is
+        fs::remove_file(tree.join(".git")).unwrap();
+        let elsewhere = dir.join("elsewhere");
+        fs::create_dir_all(&elsewhere).unwrap();
+        std::env::set_current_dir(&elsewhere).unwrap();
+        let _env = EnvGuard(&["GIT_DIR", "GIT_WORK_TREE", BACKEND_VAR]);
+        std::env::set_var("GIT_DIR", &store);
+        std::env::set_var("GIT_WORK_TREE", &tree);
+
+        for (backend, content) in [("libgit2", "two\n"), ("subprocess", "three\n")] {
+            std::env::set_var(BACKEND_VAR, backend);
+            git::require_work_tree().unwrap();
+            assert_eq!(
+                git::get_repo_info().unwrap(),
+                ("store".to_string(), "main".to_string())
+            );
+            assert_eq!(
+                git::repo_root().unwrap().canonicalize().unwrap(),
+                tree.canonicalize().unwrap()
+            );
+
+            write(&tree, "a.txt", content);
+            run_git(&tree, &["add", "-A"]);
+            let diff = git::get_staged_diff().unwrap();
+            assert!(diff.contains(&format!("+{}", content.trim())), "{}", diff);
+            let message = format!("fix: commit with {}", backend);
+            git::commit_with(&message, &CommitOptions::default()).unwrap();
+            assert_eq!(
+                run_git(&tree, &["log", "-1", "--format=%s"]).trim(),
+                message
+            );
+
+            // The submodule's log is read from its own repository, not GIT_DIR
+            if backend == "libgit2" {
+                assert!(
+                    diff.contains("Submodule vendor/lib") && diff.contains("  > feat: add parser"),
+                    "{}",
+                    diff
+                );
+            }
+        }
+        assert_eq!(run_git(&tree, &["status", "--porcelain"]), "");
+    });
+}
+
+#[test]
+fn test_worktrees_and_bare_repositories() {
+    in_temp_repo(|dir| {
+        write(dir, "a.txt", "one\n");
+        commit_all(dir, "init");
+        let project = dir.file_name().unwrap().to_str().unwrap().to_string();
+        run_git(dir, &["worktree", "add", "-q", "-b", "feature", "linked"]);
+        run_git(dir, &["clone", "-q", "--bare", ".", "bare.git"]);
+
+        std::env::set_current_dir(dir.join("linked")).unwrap();
+        assert_eq!(
+            git::get_repo_info().unwrap(),
+            (project.clone(), "feature".to_string())
+        );
+        let [library, command] =
+            [&Libgit2 as &dyn GitBackend, &Subprocess].map(|b| b.common_dir().unwrap());
+        assert_eq!(library, command);
+        assert_eq!(library, dir.join(".git").canonicalize().unwrap());
+
+        std::env::set_current_dir(dir.join("bare.git")).unwrap();
+        for backend in [&Libgit2 as &dyn GitBackend, &Subprocess] {
+            assert!(!backend.is_repository().unwrap(), "{}", backend.name());
+        }
+        assert!(git::require_work_tree()
+            .unwrap_err()
+            .contains("is a bare repository"));
+
+        std::env::set_current_dir(dir.join(".git")).unwrap();
+        for backend in [&Libgit2 as &dyn GitBackend, &Subprocess] {
+            assert!(!backend.is_repository().unwrap(), "{}", backend.name());
+        }
+        assert!(git::require_work_tree()
+            .unwrap_err()
+            .contains("Inside the git directory"));
+        std::env::set_current_dir(dir).unwrap();
+        assert!(git::require_work_tree().is_ok());
+    });
+}
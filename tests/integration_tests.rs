@@ -2,6 +2,7 @@
 mod integration_tests {
     use commitcraft::providers::{
         anthropic::AnthropicProvider, gemini::GeminiProvider, openai::OpenAIProvider, AIProvider,
+        Conversation,
     };
     use std::env;
 
@@ -29,7 +30,9 @@ index 1234567..abcdefg 100644
             env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY must be set for integration tests");
         let provider = OpenAIProvider::new(api_key, "gpt-4.1-nano".to_string());
 
-        let result = provider.generate_commit_message(TEST_DIFF).await;
+        let result = provider
+            .generate_commit_message(&Conversation::new(TEST_DIFF))
+            .await;
 
         match result {
             Ok(commit) => {
@@ -37,7 +40,7 @@ index 1234567..abcdefg 100644
                 println!("OpenAI commit description: {}", commit.description);
 
                 // Verify the commit follows conventional commits format
-                assert!(commit.title.len() > 0, "Title should not be empty");
+                assert!(!commit.title.is_empty(), "Title should not be empty");
                 assert!(commit.title.len() <= 50, "Title should be 50 chars or less");
                 assert!(
                     commit.title.contains(":"),
@@ -54,7 +57,7 @@ index 1234567..abcdefg 100644
                 );
 
                 assert!(
-                    commit.description.len() > 0,
+                    !commit.description.is_empty(),
                     "Description should not be empty"
                 );
             }
@@ -72,7 +75,9 @@ index 1234567..abcdefg 100644
             .expect("GOOGLE_AI_API_KEY must be set for integration tests");
         let provider = GeminiProvider::new(api_key, "gemini-1.5-flash".to_string());
 
-        let result = provider.generate_commit_message(TEST_DIFF).await;
+        let result = provider
+            .generate_commit_message(&Conversation::new(TEST_DIFF))
+            .await;
 
         match result {
             Ok(commit) => {
@@ -80,7 +85,7 @@ index 1234567..abcdefg 100644
                 println!("Gemini commit description: {}", commit.description);
 
                 // Verify the commit follows conventional commits format
-                assert!(commit.title.len() > 0, "Title should not be empty");
+                assert!(!commit.title.is_empty(), "Title should not be empty");
                 assert!(commit.title.len() <= 50, "Title should be 50 chars or less");
                 assert!(
                     commit.title.contains(":"),
@@ -97,7 +102,7 @@ index 1234567..abcdefg 100644
                 );
 
                 assert!(
-                    commit.description.len() > 0,
+                    !commit.description.is_empty(),
                     "Description should not be empty"
                 );
             }
@@ -115,7 +120,9 @@ index 1234567..abcdefg 100644
             .expect("ANTHROPIC_API_KEY must be set for integration tests");
         let provider = AnthropicProvider::new(api_key, "claude-3-5-haiku-20241022".to_string());
 
-        let result = provider.generate_commit_message(TEST_DIFF).await;
+        let result = provider
+            .generate_commit_message(&Conversation::new(TEST_DIFF))
+            .await;
 
         match result {
             Ok(commit) => {
@@ -123,7 +130,7 @@ index 1234567..abcdefg 100644
                 println!("Anthropic commit description: {}", commit.description);
 
                 // Verify the commit follows conventional commits format
-                assert!(commit.title.len() > 0, "Title should not be empty");
+                assert!(!commit.title.is_empty(), "Title should not be empty");
                 assert!(commit.title.len() <= 50, "Title should be 50 chars or less");
                 assert!(
                     commit.title.contains(":"),
@@ -140,7 +147,7 @@ index 1234567..abcdefg 100644
                 );
 
                 assert!(
-                    commit.description.len() > 0,
+                    !commit.description.is_empty(),
                     "Description should not be empty"
                 );
             }
@@ -171,7 +178,9 @@ index 1234567..abcdefg 100644
 
         for (name, provider) in providers {
             println!("\nTesting {} provider...", name);
-            let result = provider.generate_commit_message(TEST_DIFF).await;
+            let result = provider
+                .generate_commit_message(&Conversation::new(TEST_DIFF))
+                .await;
 
             match result {
                 Ok(commit) => {